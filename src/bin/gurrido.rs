@@ -0,0 +1,127 @@
+//! Solve a puzzle file and print the board, solver stats and difficulty
+//!
+//! Usage: `gurrido [--format non|webpbn|json|olsak|clues|csv] [--play] <file>`, where `--play`
+//! needs the `tui` feature
+
+use gurridolib::difficulty::DifficultyReport;
+use gurridolib::formats::Format;
+use gurridolib::render;
+use gurridolib::solver::technique::Technique;
+use gurridolib::solver::Solver;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: gurrido [--format non|webpbn|json|olsak|clues|csv] [--play] <file>";
+
+fn fail(message: &str) -> ! {
+    eprintln!("gurrido: {}", message);
+    process::exit(2);
+}
+
+fn parse_format(name: &str) -> Format {
+    match name {
+        "non" => Format::Non,
+        "webpbn" | "xml" => Format::Webpbn,
+        "json" => Format::Json,
+        "olsak" => Format::Olsak,
+        "clues" => Format::Clues,
+        "csv" | "tsv" => Format::Csv,
+        _ => fail(&format!("unknown format `{}`\n{}", name, USAGE)),
+    }
+}
+
+fn main() {
+    let mut format = None;
+    let mut path = None;
+    let mut play = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--format" => {
+                let name = args.next().unwrap_or_else(|| fail(USAGE));
+                format = Some(parse_format(&name));
+            }
+            "-p" | "--play" => play = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+
+    let path = path.unwrap_or_else(|| fail(USAGE));
+    let input =
+        std::fs::read_to_string(&path).unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+    let format = format
+        .or_else(|| Format::from_path(Path::new(&path)))
+        .or_else(|| Format::sniff(&input))
+        .unwrap_or_else(|| fail("cannot tell the format of the file, pass --format"));
+    let puzzle = format
+        .parse(&input)
+        .unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+    if let Err(errors) = puzzle.validate() {
+        let (last, rest) = errors.split_last().unwrap();
+        for err in rest {
+            eprintln!("gurrido: {}: {}", path, err);
+        }
+        fail(&format!("{}: {}", path, last));
+    }
+
+    if play {
+        play_puzzle(puzzle);
+        return;
+    }
+
+    let mut solver = Solver::new(&puzzle);
+    let solution = match solver.next_solution() {
+        Some(solution) => solution,
+        None => {
+            println!("no solution");
+            process::exit(1);
+        }
+    };
+    let unique = solver.next_solution().is_none();
+
+    print!("{}", render::with_clues(&puzzle, &solution));
+    println!();
+    println!("solution: {}", if unique { "unique" } else { "not unique" });
+
+    let stats = solver.stats();
+    println!("line solves: {}", stats.line_solves);
+    println!("guesses: {}", stats.guesses);
+    println!("backtracks: {}", stats.backtracks);
+    println!("max depth: {}", stats.max_depth);
+    println!("contradictions: {}", stats.contradictions);
+
+    if let Some(report) = DifficultyReport::analyse(&puzzle) {
+        println!();
+        for &technique in Technique::ALL.iter() {
+            let count = report.count(technique);
+            if count > 0 {
+                println!(
+                    "{:>14}: {} ({:.1}%)",
+                    technique.name(),
+                    count,
+                    report.percent(technique)
+                );
+            }
+        }
+        println!("difficulty score: {}", report.score());
+    }
+}
+
+#[cfg(feature = "tui")]
+fn play_puzzle(puzzle: gurridolib::puzzle::Puzzle) {
+    let session = gurridolib::session::Session::new(puzzle)
+        .unwrap_or_else(|| fail("the puzzle has no solution"));
+    let stdin = std::io::stdin();
+    gurridolib::tui::run(session, stdin.lock(), std::io::stdout())
+        .unwrap_or_else(|err| fail(&err.to_string()));
+}
+
+#[cfg(not(feature = "tui"))]
+fn play_puzzle(_: gurridolib::puzzle::Puzzle) {
+    fail("--play needs gurrido built with the `tui` feature");
+}
@@ -0,0 +1,71 @@
+// Little helpers shared by the compact binary encodings
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, pos: 0 }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    /// `None` once the input runs out
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        let value = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// `None` on truncated or overlong input
+    pub(crate) fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip() {
+        let mut out = Vec::new();
+        for &value in &[0, 1, 127, 128, 300, u64::MAX] {
+            write_varint(&mut out, value);
+        }
+        assert_eq!(out[..4], [0, 1, 127, 0x80]);
+
+        let mut reader = ByteReader::new(&out);
+        for &value in &[0, 1, 127, 128, 300, u64::MAX] {
+            assert_eq!(reader.varint(), Some(value));
+        }
+        assert!(reader.is_done());
+        assert_eq!(reader.varint(), None);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::puzzle::Puzzle;
+use crate::solver::replay::Replay;
+use crate::solver::technique::Technique;
+use crate::solver::Solver;
+use crate::spaces::grid::CellPos;
+use std::collections::BTreeMap;
+
+/// How a puzzle gets solved, broken down by method
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifficultyReport {
+    /// Cells solved by each technique
+    pub counts: BTreeMap<Technique, usize>,
+    /// Deepest nesting of guesses the search needed
+    pub max_depth: usize,
+    /// Where line logic first got stuck, if it ever did
+    pub first_guess: Option<CellPos>,
+    /// Line solves performed before the first solution was found
+    pub line_solves: usize,
+    pub cells: usize,
+}
+
+impl DifficultyReport {
+    /// Analyse a puzzle, `None` if it has no solution
+    pub fn analyse(puzzle: &Puzzle) -> Option<DifficultyReport> {
+        let replay = Replay::record(puzzle)?;
+        let mut solver = Solver::new(puzzle);
+        solver.next_solution()?;
+
+        let mut counts = BTreeMap::new();
+        for step in replay.steps() {
+            *counts.entry(step.technique).or_insert(0) += 1;
+        }
+        let first_guess = replay
+            .steps()
+            .iter()
+            .find(|step| step.technique == Technique::Guess)
+            .map(|step| step.pos);
+
+        Some(DifficultyReport {
+            counts,
+            max_depth: solver.stats().max_depth,
+            first_guess,
+            line_solves: solver.stats().line_solves,
+            cells: replay.steps().len(),
+        })
+    }
+
+    pub fn count(&self, kind: Technique) -> usize {
+        self.counts.get(&kind).cloned().unwrap_or(0)
+    }
+
+    /// Share of the board solved by a technique, from 0 to 100
+    pub fn percent(&self, kind: Technique) -> f64 {
+        if self.cells == 0 {
+            return 0.0;
+        }
+        self.count(kind) as f64 * 100.0 / self.cells as f64
+    }
+
+    /// Single number summary weighting each cell by how hard its technique is,
+    /// plus twenty-five per level of search depth
+    pub fn score(&self) -> usize {
+        let weight = |technique| match technique {
+            Technique::CompletedLine | Technique::Overlap | Technique::Punctuation => 1,
+            Technique::EdgeLogic => 2,
+            Technique::CrossLine => 3,
+            Technique::Probing => 5,
+            Technique::Guess => 10,
+        };
+        self.counts
+            .iter()
+            .map(|(&technique, &count)| weight(technique) * count)
+            .sum::<usize>()
+            + 25 * self.max_depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn line_solvable_breakdown() {
+        let puzzle = Puzzle::from_goal(from_art(&["###", "#.#", "###"]));
+        let report = DifficultyReport::analyse(&puzzle).unwrap();
+
+        let line_cells: usize = Technique::ALL
+            .iter()
+            .filter(|t| t.is_line_technique())
+            .map(|&t| report.count(t))
+            .sum();
+        assert_eq!(line_cells, 9);
+        assert_eq!(report.count(Technique::Overlap), 8);
+        assert_eq!(report.count(Technique::Punctuation), 1);
+        assert_eq!(report.max_depth, 0);
+        assert_eq!(report.first_guess, None);
+    }
+
+    #[test]
+    fn guessing_breakdown() {
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let report = DifficultyReport::analyse(&puzzle).unwrap();
+
+        assert_eq!(report.count(Technique::Guess), 1);
+        assert_eq!(report.percent(Technique::Guess), 25.0);
+        assert_eq!(report.max_depth, 1);
+        assert_eq!(report.first_guess, Some(CellPos::new(0, 0)));
+    }
+
+    #[test]
+    fn unsolvable() {
+        let puzzle = Puzzle::new(vec![vec![2], vec![]], vec![vec![1], vec![]]);
+        assert!(DifficultyReport::analyse(&puzzle).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::puzzle::{ClueError, Puzzle};
+use crate::spaces::{Line, LineId};
+
+/// Edits a puzzle's clues one at a time, keeping each line's hint
+/// structures and validation up to date
+///
+/// Only the edited line is rebuilt and rechecked after each change.
+#[derive(Clone, Debug)]
+pub struct ClueEditor {
+    puzzle: Puzzle,
+    // Rows then columns, `None` for unclued or invalid lines
+    lines: Vec<Option<Line>>,
+    errors: Vec<Vec<ClueError>>,
+}
+
+impl ClueEditor {
+    pub fn new(puzzle: Puzzle) -> ClueEditor {
+        let count = puzzle.width() + puzzle.height();
+        let mut editor = ClueEditor {
+            puzzle,
+            lines: vec![None; count],
+            errors: vec![Vec::new(); count],
+        };
+        let lines: Vec<LineId> = editor.puzzle.lines().collect();
+        for line in lines {
+            editor.rebuild(line);
+        }
+        editor
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    pub fn into_puzzle(self) -> Puzzle {
+        self.puzzle
+    }
+
+    fn index(&self, line: LineId) -> usize {
+        match line {
+            LineId::Row(row) => row,
+            LineId::Column(col) => self.puzzle.height() + col,
+        }
+    }
+
+    /// Hint structures for a line, `None` while it is unclued or invalid
+    pub fn line(&self, line: LineId) -> Option<&Line> {
+        self.lines[self.index(line)].as_ref()
+    }
+
+    pub fn line_errors(&self, line: LineId) -> &[ClueError] {
+        &self.errors[self.index(line)]
+    }
+
+    /// Every problem currently known, in line order
+    pub fn errors(&self) -> impl Iterator<Item = &ClueError> {
+        self.errors.iter().flatten()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    fn rebuild(&mut self, line: LineId) {
+        let i = self.index(line);
+        self.errors[i] = self.puzzle.line_errors(line);
+        let structural = self.errors[i]
+            .iter()
+            .any(|err| !matches!(err, ClueError::GoalMismatch { .. }));
+        self.lines[i] = match self.puzzle.clues(line) {
+            Some(clues) if !structural => Some(Line::new(clues, self.puzzle.line_len(line))),
+            _ => None,
+        };
+    }
+
+    // Change a line's clues, treating an unclued line as having none
+    fn edit<T>(&mut self, line: LineId, change: impl FnOnce(&mut Vec<usize>) -> T) -> T {
+        let mut clues = self.puzzle.clues(line).unwrap_or(&[]).to_vec();
+        let result = change(&mut clues);
+        self.puzzle.set_clues(line, clues);
+        self.rebuild(line);
+        result
+    }
+
+    /// Insert a clue before `index`, panicking if `index` is past the end
+    pub fn insert_clue(&mut self, line: LineId, index: usize, clue: usize) {
+        self.edit(line, |clues| clues.insert(index, clue))
+    }
+
+    /// Remove and return the clue at `index`
+    pub fn remove_clue(&mut self, line: LineId, index: usize) -> usize {
+        self.edit(line, |clues| clues.remove(index))
+    }
+
+    /// Replace the clue at `index`, returning the old value
+    pub fn set_clue(&mut self, line: LineId, index: usize, clue: usize) -> usize {
+        self.edit(line, |clues| std::mem::replace(&mut clues[index], clue))
+    }
+
+    /// Replace every clue of a line, `None` leaving it unclued
+    pub fn set_clues(&mut self, line: LineId, clues: Option<Vec<usize>>) {
+        match clues {
+            Some(clues) => self.puzzle.set_clues(line, clues),
+            None => self.puzzle.remove_clues(line),
+        }
+        self.rebuild(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn edits_rebuild_only_their_line() {
+        let mut editor = ClueEditor::new(Puzzle::new(vec![vec![1]; 2], vec![vec![1]; 3]));
+        let row = LineId::Row(0);
+
+        editor.insert_clue(row, 0, 1);
+        assert_eq!(editor.puzzle().clues(row), Some(&[1, 1][..]));
+        let hints = editor.line(row).unwrap().hints();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[1].solutions()[0].offset(), 2);
+
+        assert_eq!(editor.set_clue(row, 1, 2), 1);
+        assert!(!editor.is_valid());
+        assert!(editor.line(row).is_none());
+        assert_eq!(
+            editor.line_errors(row),
+            &[ClueError::TooLong {
+                line: row,
+                needed: 4,
+                length: 3
+            }]
+        );
+
+        assert_eq!(editor.remove_clue(row, 0), 1);
+        assert!(editor.is_valid());
+        assert_eq!(editor.line(row).unwrap().hints()[0].hint(), 2);
+    }
+
+    #[test]
+    fn goal_mismatches_are_reported() {
+        let mut editor = ClueEditor::new(Puzzle::from_goal(from_art(&["#.", "##"])));
+        assert!(editor.is_valid());
+
+        editor.set_clue(LineId::Column(1), 0, 2);
+        let column = LineId::Column(1);
+        assert_eq!(
+            editor.errors().collect::<Vec<_>>(),
+            vec![&ClueError::GoalMismatch { line: column }]
+        );
+        assert!(editor.line(column).is_some());
+
+        editor.set_clues(column, None);
+        assert!(editor.is_valid());
+        assert!(editor.line(column).is_none());
+    }
+}
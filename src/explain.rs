@@ -0,0 +1,357 @@
+//! Why a cell cannot take a value, in terms a player can follow
+
+use crate::puzzle::Puzzle;
+use crate::solver::events::SolverEvent;
+use crate::solver::technique::{self, Technique};
+use crate::solver::Solver;
+use crate::solver::{crossline, line};
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::mpsc::channel;
+
+/// One cell solved while following a chain of reasoning
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChainStep {
+    pub pos: CellPos,
+    pub state: CellState,
+    /// The line whose clues forced the cell
+    pub line: LineId,
+}
+
+/// Reasoning that rules a value out for a cell
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Explanation {
+    /// The clues of one line alone force the other value
+    Line { line: LineId, technique: Technique },
+    /// Assuming the value forces these cells in turn, until `contradiction`
+    /// can no longer fit its clues
+    Chain {
+        steps: Vec<ChainStep>,
+        contradiction: LineId,
+    },
+}
+
+/// One instruction of a walkthrough: cells solved together for one reason
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Instruction {
+    /// The line whose clues did it, `None` for reasoning beyond one line
+    pub line: Option<LineId>,
+    pub clues: Vec<usize>,
+    pub technique: Technique,
+    pub state: CellState,
+    pub cells: Vec<CellPos>,
+}
+
+// 1-based ranges such as `2, 4–6`
+fn ranges(mut indices: Vec<usize>) -> String {
+    indices.sort_unstable();
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < indices.len() {
+        let mut j = i;
+        while j + 1 < indices.len() && indices[j + 1] == indices[j] + 1 {
+            j += 1;
+        }
+        parts.push(match j == i {
+            true => format!("{}", indices[i] + 1),
+            false => format!("{}\u{2013}{}", indices[i] + 1, indices[j] + 1),
+        });
+        i = j + 1;
+    }
+    parts.join(", ")
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = match self.state {
+            CellState::Filled => "fill",
+            _ => "cross out",
+        };
+        match self.line {
+            Some(line) => {
+                let (name, index, across) = match line {
+                    LineId::Row(row) => ("Row", row, "column"),
+                    LineId::Column(col) => ("Column", col, "row"),
+                };
+                let cells: Vec<usize> = self
+                    .cells
+                    .iter()
+                    .map(|pos| match line {
+                        LineId::Row(_) => pos.col,
+                        LineId::Column(_) => pos.row,
+                    })
+                    .collect();
+                let clues: Vec<String> = self.clues.iter().map(usize::to_string).collect();
+                let clues = match clues.is_empty() {
+                    true => "0".to_string(),
+                    false => clues.join(" "),
+                };
+                write!(
+                    f,
+                    "{} {} ({}): {}, {} {}{} {}",
+                    name,
+                    index + 1,
+                    clues,
+                    self.technique,
+                    verb,
+                    across,
+                    if cells.len() == 1 { "" } else { "s" },
+                    ranges(cells)
+                )
+            }
+            None => {
+                let pos = self.cells[0];
+                write!(
+                    f,
+                    "Row {}, column {}: {}, {} it",
+                    pos.row + 1,
+                    pos.col + 1,
+                    self.technique,
+                    verb
+                )
+            }
+        }
+    }
+}
+
+/// Walk through solving a puzzle, grouping cells each line solves at once
+///
+/// `None` if the puzzle has no solution.
+pub fn solution_steps(puzzle: &Puzzle) -> Option<Vec<Instruction>> {
+    let solution = crate::solver::solve(puzzle)?;
+    let (tx, rx) = channel();
+    let mut solver = Solver::new(puzzle);
+    solver.set_event_sender(tx);
+
+    let mut steps: Vec<Instruction> = Vec::new();
+    loop {
+        solver.propagate().ok()?;
+        for event in rx.try_iter() {
+            let (pos, state, line, technique) = match event {
+                SolverEvent::CellSolved {
+                    pos,
+                    state,
+                    line,
+                    technique,
+                } => (pos, state, line, technique),
+                _ => continue,
+            };
+            match steps.last_mut() {
+                Some(last)
+                    if last.line == Some(line)
+                        && last.technique == technique
+                        && last.state == state =>
+                {
+                    last.cells.push(pos)
+                }
+                _ => steps.push(Instruction {
+                    line: Some(line),
+                    clues: puzzle.clues(line).unwrap_or(&[]).to_vec(),
+                    technique,
+                    state,
+                    cells: vec![pos],
+                }),
+            }
+        }
+        if solver.grid().is_complete() {
+            return Some(steps);
+        }
+
+        let grid = solver.grid();
+        let (pos, state, technique) = match crossline::deduce(puzzle, grid) {
+            Some((pos, state)) => (pos, state, Technique::CrossLine),
+            None => match solver.probe() {
+                Some((pos, true)) => (pos, CellState::Filled, Technique::Probing),
+                Some((pos, false)) => (pos, CellState::Empty, Technique::Probing),
+                None => {
+                    let pos = grid.positions().find(|&pos| !grid.node(pos).is_solved())?;
+                    (pos, solution.get(pos), Technique::Guess)
+                }
+            },
+        };
+        steps.push(Instruction {
+            line: None,
+            clues: Vec::new(),
+            technique,
+            state,
+            cells: vec![pos],
+        });
+        solver.assume(pos, state == CellState::Filled);
+    }
+}
+
+/// Numbered, human readable instructions for solving a puzzle
+pub fn explain_solution(puzzle: &Puzzle) -> Option<Vec<String>> {
+    let steps = solution_steps(puzzle)?;
+    Some(
+        steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. {}", i + 1, step))
+            .collect(),
+    )
+}
+
+// The line's own verdict on one of its cells, with the technique behind it
+fn line_verdict(
+    puzzle: &Puzzle,
+    board: &Grid,
+    line: LineId,
+    i: usize,
+) -> Option<(CellState, Technique)> {
+    let clues = puzzle.clues(line)?;
+    let before = board.line(line);
+    let solved = line::solve(clues, &before)?;
+    let mut labels = Vec::new();
+    technique::classify(clues, &before, &solved.cells, &solved.starts, &mut labels);
+    labels[i].map(|technique| (solved.cells[i], technique))
+}
+
+/// Explain why `pos` cannot be `state` given the cells known on `board`
+///
+/// Single lines are tried first, then the consequences of assuming the
+/// value. `None` if neither rules the value out, or `state` is `Unknown`.
+pub fn explain_forbidden(
+    puzzle: &Puzzle,
+    board: &Grid,
+    pos: CellPos,
+    state: CellState,
+) -> Option<Explanation> {
+    if state == CellState::Unknown || board.get(pos) == state {
+        return None;
+    }
+
+    for &(line, i) in &[
+        (LineId::Row(pos.row), pos.col),
+        (LineId::Column(pos.col), pos.row),
+    ] {
+        if let Some((forced, technique)) = line_verdict(puzzle, board, line, i) {
+            if forced != state {
+                return Some(Explanation::Line { line, technique });
+            }
+        }
+    }
+    if board.get(pos) != CellState::Unknown {
+        return None;
+    }
+
+    let (tx, rx) = channel();
+    let mut solver = Solver::from_grid(puzzle, board.clone());
+    solver.set_event_sender(tx);
+    solver.assume(pos, state == CellState::Filled);
+    let contradiction = solver.propagate().err()?.line;
+    let events: Vec<ChainStep> = rx
+        .try_iter()
+        .filter_map(|event| match event {
+            SolverEvent::CellSolved {
+                pos, state, line, ..
+            } => Some(ChainStep { pos, state, line }),
+            _ => None,
+        })
+        .collect();
+
+    // Walk back from the contradiction, keeping steps that touched a line in use
+    let mut relevant: HashSet<LineId> = HashSet::new();
+    relevant.insert(contradiction);
+    let mut steps = Vec::new();
+    for step in events.into_iter().rev() {
+        let crosses = relevant.contains(&LineId::Row(step.pos.row))
+            || relevant.contains(&LineId::Column(step.pos.col));
+        if crosses {
+            relevant.insert(step.line);
+            steps.push(step);
+        }
+    }
+    steps.reverse();
+
+    Some(Explanation::Chain {
+        steps,
+        contradiction,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn walkthrough() {
+        let puzzle = Puzzle::from_goal(from_art(&["###", "#.#", "###"]));
+        let text = explain_solution(&puzzle).unwrap();
+
+        assert_eq!(text[0], "1. Row 1 (3): overlap, fill columns 1\u{2013}3");
+        let cells: usize = solution_steps(&puzzle)
+            .unwrap()
+            .iter()
+            .map(|step| step.cells.len())
+            .sum();
+        assert_eq!(cells, 9);
+
+        let ambiguous = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let text = explain_solution(&ambiguous).unwrap();
+        assert!(text[0].starts_with("1. Row 1, column 1: guess"));
+        assert_eq!(ranges(vec![4, 0, 2, 3]), "1, 3\u{2013}5");
+    }
+
+    #[test]
+    fn single_line_reasons() {
+        let puzzle = Puzzle::from_goal(from_art(&["###.", "....", "#..#", ".##."]));
+        let board = Grid::new(4, 4);
+
+        assert_eq!(
+            explain_forbidden(&puzzle, &board, CellPos::new(1, 2), CellState::Filled),
+            Some(Explanation::Line {
+                line: LineId::Row(1),
+                technique: Technique::CompletedLine
+            })
+        );
+        assert_eq!(
+            explain_forbidden(&puzzle, &board, CellPos::new(0, 1), CellState::Empty),
+            Some(Explanation::Line {
+                line: LineId::Row(0),
+                technique: Technique::Overlap
+            })
+        );
+    }
+
+    #[test]
+    fn chain_reasons() {
+        // The empty first row is not forced by any single line
+        let goal = from_art(&["#.#", "###", "#.#"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let mut board = Grid::new(3, 3);
+        board.solve(CellPos::new(1, 1), true);
+
+        let explanation = explain_forbidden(&puzzle, &board, CellPos::new(0, 1), CellState::Filled);
+        match explanation {
+            Some(Explanation::Chain { steps, .. }) => {
+                assert!(steps
+                    .iter()
+                    .all(|step| board.get(step.pos) == CellState::Unknown));
+            }
+            Some(Explanation::Line { .. }) => {}
+            None => panic!("the value should be ruled out"),
+        }
+        assert_eq!(
+            explain_forbidden(&puzzle, &board, CellPos::new(0, 0), CellState::Filled),
+            None
+        );
+    }
+
+    #[test]
+    fn probing_chain() {
+        // Two 1s in a column of three pin the middle cell empty, but only
+        // after the rows have had their say
+        let puzzle = Puzzle::new(
+            vec![vec![1, 1], vec![1], vec![1, 1]],
+            vec![vec![1, 1], vec![1], vec![1, 1]],
+        );
+        let board = Grid::new(3, 3);
+        let explanation = explain_forbidden(&puzzle, &board, CellPos::new(0, 1), CellState::Filled);
+        assert!(explanation.is_some());
+    }
+}
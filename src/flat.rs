@@ -0,0 +1,298 @@
+//! Puzzles and boards as flat numeric arrays, for handing across language
+//! boundaries in one piece instead of cell by cell
+//!
+//! Cells are row-major bytes holding `CellState` codes: 0 unknown, 1 empty,
+//! 2 filled. Clues are rows then columns, each line's count in `counts`
+//! (-1 for an unclued line) and every clue one after another in `values`.
+//! Solver events become flat JSON objects, the cells of a solution using the
+//! same codes, so a browser can take a whole run with one `JSON.parse`.
+
+use crate::formats::json::write_string;
+use crate::puzzle::Puzzle;
+use crate::solver::deduction::Deduction;
+use crate::solver::events::SolverEvent;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::fmt;
+use std::fmt::Write;
+
+/// Clues of every line packed into two arrays
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatClues {
+    pub counts: Vec<i32>,
+    pub values: Vec<i32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlatError {
+    /// The arrays are not as long as the board needs
+    SizeMismatch,
+    /// A cell byte that is not a state code
+    BadCell(usize),
+    /// A clue or count below its smallest allowed value
+    Negative(usize),
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlatError::SizeMismatch => write!(f, "array length does not match the board"),
+            FlatError::BadCell(i) => write!(f, "cell {} is not a state code", i),
+            FlatError::Negative(i) => write!(f, "entry {} is negative", i),
+        }
+    }
+}
+
+impl std::error::Error for FlatError {}
+
+pub fn cell_code(state: CellState) -> u8 {
+    state as u8
+}
+
+pub fn cell_state(code: u8) -> Option<CellState> {
+    match code {
+        0 => Some(CellState::Unknown),
+        1 => Some(CellState::Empty),
+        2 => Some(CellState::Filled),
+        _ => None,
+    }
+}
+
+/// Every cell's state code, row by row
+pub fn cells(grid: &Grid) -> Vec<u8> {
+    grid.positions()
+        .map(|pos| cell_code(grid.get(pos)))
+        .collect()
+}
+
+pub fn grid_from_cells(width: usize, height: usize, cells: &[u8]) -> Result<Grid, FlatError> {
+    if cells.len() != width * height {
+        return Err(FlatError::SizeMismatch);
+    }
+    let mut grid = Grid::new(width, height);
+    for (i, &code) in cells.iter().enumerate() {
+        let state = cell_state(code).ok_or(FlatError::BadCell(i))?;
+        grid.set_state(CellPos::new(i / width, i % width), state);
+    }
+    Ok(grid)
+}
+
+pub fn clues(puzzle: &Puzzle) -> FlatClues {
+    let mut flat = FlatClues::default();
+    for line in puzzle.lines() {
+        match puzzle.clues(line) {
+            Some(clues) => {
+                flat.counts.push(clues.len() as i32);
+                flat.values.extend(clues.iter().map(|&clue| clue as i32));
+            }
+            None => flat.counts.push(-1),
+        }
+    }
+    flat
+}
+
+/// Build a puzzle from packed clues, with `counts` holding rows then columns
+pub fn puzzle_from_clues(
+    width: usize,
+    height: usize,
+    counts: &[i32],
+    values: &[i32],
+) -> Result<Puzzle, FlatError> {
+    if counts.len() != width + height {
+        return Err(FlatError::SizeMismatch);
+    }
+    let mut lines = Vec::with_capacity(counts.len());
+    let mut next = 0;
+    for (i, &count) in counts.iter().enumerate() {
+        if count < -1 {
+            return Err(FlatError::Negative(i));
+        }
+        if count == -1 {
+            lines.push(None);
+            continue;
+        }
+        let end = next + count as usize;
+        let clues = values.get(next..end).ok_or(FlatError::SizeMismatch)?;
+        if let Some(j) = clues.iter().position(|&clue| clue < 0) {
+            return Err(FlatError::Negative(next + j));
+        }
+        lines.push(Some(clues.iter().map(|&clue| clue as usize).collect()));
+        next = end;
+    }
+    if next != values.len() {
+        return Err(FlatError::SizeMismatch);
+    }
+    let cols = lines.split_off(height);
+    Ok(Puzzle::from_lines(lines, cols))
+}
+
+fn write_line(out: &mut String, line: LineId) {
+    let (kind, index) = match line {
+        LineId::Row(row) => ("row", row),
+        LineId::Column(col) => ("column", col),
+    };
+    let _ = write!(out, "\"line\":\"{}\",\"index\":{}", kind, index);
+}
+
+/// One event as a JSON object tagged by `type`
+pub fn event_json(event: &SolverEvent) -> String {
+    let mut out = String::from("{\"type\":");
+    match event {
+        SolverEvent::CellSolved {
+            pos,
+            state,
+            line,
+            technique,
+        } => {
+            let _ = write!(
+                out,
+                "\"cellSolved\",\"row\":{},\"col\":{},\"state\":{},",
+                pos.row,
+                pos.col,
+                cell_code(*state)
+            );
+            write_line(&mut out, *line);
+            out.push_str(",\"technique\":");
+            write_string(&mut out, technique.name());
+        }
+        SolverEvent::Guess { cell, depth } | SolverEvent::Backtrack { cell, depth } => {
+            let kind = match event {
+                SolverEvent::Guess { .. } => "guess",
+                _ => "backtrack",
+            };
+            let _ = write!(
+                out,
+                "\"{}\",\"row\":{},\"col\":{},\"depth\":{}",
+                kind, cell.row, cell.col, depth
+            );
+        }
+        SolverEvent::Contradiction { line } => {
+            out.push_str("\"contradiction\",");
+            write_line(&mut out, *line);
+        }
+        SolverEvent::Solution(grid) => {
+            let _ = write!(
+                out,
+                "\"solution\",\"width\":{},\"height\":{},\"cells\":{:?}",
+                grid.width(),
+                grid.height(),
+                cells(grid)
+            );
+        }
+        SolverEvent::Finished { solutions } => {
+            let _ = write!(out, "\"finished\",\"solutions\":{}", solutions);
+        }
+    }
+    out.push('}');
+    out
+}
+
+/// A deduction as a JSON object, its reason given by `Reason::key`
+pub fn deduction_json(deduction: &Deduction) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{{\"row\":{},\"col\":{},\"state\":{},\"reason\":",
+        deduction.cell.row,
+        deduction.cell.col,
+        cell_code(deduction.state)
+    );
+    write_string(&mut out, deduction.reason.key());
+    out.push('}');
+    out
+}
+
+/// A run of events as a JSON array
+pub fn events_json(events: &[SolverEvent]) -> String {
+    let objects: Vec<String> = events.iter().map(event_json).collect();
+    format!("[{}]", objects.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::deduction::Reason;
+    use crate::solver::technique::Technique;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn cells_round_trip() {
+        let mut grid = from_art(&["#.", ".#", "##"]);
+        grid.set_state(CellPos::new(2, 0), CellState::Unknown);
+        let flat = cells(&grid);
+
+        assert_eq!(flat, vec![2, 1, 1, 2, 0, 2]);
+        assert_eq!(grid_from_cells(2, 3, &flat), Ok(grid));
+        assert_eq!(grid_from_cells(3, 3, &flat), Err(FlatError::SizeMismatch));
+        assert_eq!(
+            grid_from_cells(2, 3, &[0, 0, 0, 3, 0, 0]),
+            Err(FlatError::BadCell(3))
+        );
+    }
+
+    #[test]
+    fn clues_round_trip() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.#", "###"]));
+        puzzle.set_goal(None);
+        puzzle.remove_clues(LineId::Column(1));
+        let flat = clues(&puzzle);
+
+        assert_eq!(flat.counts, vec![2, 1, 1, -1, 1]);
+        assert_eq!(flat.values, vec![1, 1, 3, 2, 2]);
+        assert_eq!(
+            puzzle_from_clues(3, 2, &flat.counts, &flat.values),
+            Ok(puzzle)
+        );
+        assert_eq!(
+            puzzle_from_clues(3, 2, &flat.counts, &[1, 1, 3, -2, 2]),
+            Err(FlatError::Negative(3))
+        );
+        assert_eq!(
+            puzzle_from_clues(3, 2, &flat.counts, &flat.values[1..]),
+            Err(FlatError::SizeMismatch)
+        );
+    }
+
+    #[test]
+    fn deductions_as_json() {
+        let deduction = Deduction {
+            cell: CellPos::new(3, 0),
+            state: CellState::Empty,
+            reason: Reason::CrossLine,
+        };
+        assert_eq!(
+            deduction_json(&deduction),
+            "{\"row\":3,\"col\":0,\"state\":1,\"reason\":\"cross_line\"}"
+        );
+    }
+
+    #[test]
+    fn events_as_json() {
+        let events = vec![
+            SolverEvent::CellSolved {
+                pos: CellPos::new(1, 2),
+                state: CellState::Filled,
+                line: LineId::Column(2),
+                technique: Technique::EdgeLogic,
+            },
+            SolverEvent::Backtrack {
+                cell: CellPos::new(0, 1),
+                depth: 2,
+            },
+            SolverEvent::Solution(from_art(&["#.", ".#"])),
+            SolverEvent::Finished { solutions: 1 },
+        ];
+
+        assert_eq!(
+            events_json(&events),
+            concat!(
+                "[{\"type\":\"cellSolved\",\"row\":1,\"col\":2,\"state\":2,",
+                "\"line\":\"column\",\"index\":2,\"technique\":\"edge logic\"},",
+                "{\"type\":\"backtrack\",\"row\":0,\"col\":1,\"depth\":2},",
+                "{\"type\":\"solution\",\"width\":2,\"height\":2,\"cells\":[2, 1, 1, 2]},",
+                "{\"type\":\"finished\",\"solutions\":1}]"
+            )
+        );
+    }
+}
@@ -0,0 +1,426 @@
+pub mod binary;
+pub mod clues;
+pub mod csv;
+pub mod json;
+pub mod non;
+pub mod olsak;
+pub mod pack;
+pub mod webpbn;
+mod xml;
+
+use crate::puzzle::Puzzle;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+/// Problem found while reading a puzzle file, `line` counting from 1
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// A problem located in the input, as reported by `Format::diagnose`
+///
+/// `line` and `column` count from 1. A column of 0 means only the line is
+/// known, and a line of 0 means the problem is with the document as a whole.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseDiagnostic {
+    /// Byte offset into the input
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Non,
+    Webpbn,
+    Json,
+    /// Mirek Olšák's solver input
+    Olsak,
+    /// Bare clue lines, rows then columns after a blank line
+    Clues,
+    /// Clue lines saved from a spreadsheet as CSV or TSV
+    Csv,
+}
+
+/// What a puzzle carries beyond its clues, which not every format can hold
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Metadata {
+    /// Lines with no clue at all, as opposed to an empty clue
+    UncluedLines,
+    Goal,
+    Givens,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseDiagnostic {
+    /// A problem at byte `offset` of `input`
+    pub(crate) fn at(input: &str, offset: usize, message: impl Into<String>) -> ParseDiagnostic {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseDiagnostic {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Locate an error known only by its line at the start of that line
+    pub(crate) fn from_error(input: &str, error: ParseError) -> ParseDiagnostic {
+        let offset = match error.line {
+            0 => 0,
+            line => input
+                .match_indices('\n')
+                .nth(line - 2)
+                .map_or(if line == 1 { 0 } else { input.len() }, |(i, _)| i + 1),
+        };
+        ParseDiagnostic {
+            offset,
+            line: error.line,
+            column: 0,
+            message: error.message,
+        }
+    }
+}
+
+impl From<ParseDiagnostic> for ParseError {
+    fn from(diagnostic: ParseDiagnostic) -> ParseError {
+        ParseError::new(diagnostic.line, diagnostic.message)
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (0, _) => write!(f, "{}", self.message),
+            (line, 0) => write!(f, "line {}: {}", line, self.message),
+            (line, column) => write!(f, "line {}, column {}: {}", line, column, self.message),
+        }
+    }
+}
+
+impl Format {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "non" => Some(Format::Non),
+            "xml" | "pbn" => Some(Format::Webpbn),
+            "json" => Some(Format::Json),
+            "g" => Some(Format::Olsak),
+            "csv" | "tsv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    /// Whether writing then reading this format keeps `metadata`
+    ///
+    /// | format | unclued lines | goal | givens |
+    /// |--------|---------------|------|--------|
+    /// | Non    | yes           | yes  | yes    |
+    /// | Json   | yes           | yes  | yes    |
+    /// | Webpbn | no, empty     | yes  | yes    |
+    /// | Olsak  | no, empty     | no   | no     |
+    /// | Clues  | no, empty     | no   | no     |
+    /// | Csv    | no, empty     | no   | no     |
+    pub fn preserves(self, metadata: Metadata) -> bool {
+        match self {
+            Format::Non | Format::Json => true,
+            Format::Webpbn => metadata != Metadata::UncluedLines,
+            Format::Olsak | Format::Clues | Format::Csv => false,
+        }
+    }
+
+    /// Guess the format from the text itself
+    pub fn sniff(input: &str) -> Option<Format> {
+        let text = input.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('<') {
+            return Some(Format::Webpbn);
+        }
+        if text.starts_with('{') {
+            return Some(Format::Json);
+        }
+        let mut lines = text.lines().map(str::trim);
+        let first = lines
+            .clone()
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+        if first.starts_with(':') || text.starts_with("#d") {
+            return Some(Format::Olsak);
+        }
+        let keyword = |line: &str| {
+            let word = line.split_whitespace().next().unwrap_or("");
+            ["width", "height", "rows", "columns"].contains(&word)
+        };
+        if lines.clone().any(keyword) {
+            return Some(Format::Non);
+        }
+        let clue_text = |c: char| c.is_ascii_digit() || c == ',' || c.is_whitespace();
+        let cell_text = |c: char| clue_text(c) || c == ';' || c == '"';
+        if !lines.clone().all(|line| line.chars().all(cell_text)) {
+            return None;
+        }
+        // Spreadsheets quote, use tabs or semicolons, or leave whole rows of
+        // empty cells
+        let spreadsheet = |line: &str| {
+            line.contains([';', '"', '\t'])
+                || (!line.is_empty() && !line.contains(|c: char| c.is_ascii_digit()))
+        };
+        if lines.clone().any(spreadsheet) {
+            return Some(Format::Csv);
+        }
+        if lines.all(|line| line.chars().all(clue_text)) {
+            return Some(Format::Clues);
+        }
+        None
+    }
+
+    pub fn parse(self, input: &str) -> Result<Puzzle, ParseError> {
+        match self {
+            Format::Non => non::parse(input),
+            Format::Webpbn => webpbn::parse(input),
+            Format::Json => json::parse(input),
+            Format::Olsak => olsak::parse(input),
+            Format::Clues => clues::parse(input),
+            Format::Csv => csv::parse(input),
+        }
+    }
+
+    /// Parse, reporting every problem found with its position
+    ///
+    /// The `.non` format carries on past bad lines to report them all, and
+    /// webpbn reports every bad clue and image of well-formed XML. JSON stops
+    /// at its first problem, as do the clue-only formats.
+    pub fn diagnose(self, input: &str) -> Result<Puzzle, Vec<ParseDiagnostic>> {
+        match self {
+            Format::Non => non::diagnose(input),
+            Format::Webpbn => webpbn::diagnose(input),
+            Format::Json => json::diagnose(input),
+            Format::Olsak | Format::Clues | Format::Csv => self
+                .parse(input)
+                .map_err(|error| vec![ParseDiagnostic::from_error(input, error)]),
+        }
+    }
+
+    pub fn write(self, puzzle: &Puzzle) -> String {
+        match self {
+            Format::Non => non::write(puzzle),
+            Format::Webpbn => webpbn::write(puzzle),
+            Format::Json => json::write(puzzle),
+            Format::Olsak => olsak::write(puzzle),
+            Format::Clues => clues::write(puzzle),
+            Format::Csv => csv::write(puzzle),
+        }
+    }
+}
+
+/// Rewrite a puzzle from one format in another
+///
+/// Clues always survive, other metadata only where both formats preserve it
+/// (see `Format::preserves`), so converting to a format that preserves
+/// everything the input could hold and back gives the same puzzle.
+pub fn convert(input: &str, from: Format, to: Format) -> Result<String, ParseError> {
+    from.parse(input).map(|puzzle| to.write(&puzzle))
+}
+
+/// Read a puzzle in any supported format, telling which from the content
+pub fn load_any(mut reader: impl Read) -> Result<Puzzle, ParseError> {
+    let mut input = String::new();
+    reader
+        .read_to_string(&mut input)
+        .map_err(|err| ParseError::new(0, err.to_string()))?;
+    Format::sniff(&input)
+        .ok_or_else(|| ParseError::new(0, "unrecognised puzzle format"))?
+        .parse(&input)
+}
+
+// Comma or space separated clue numbers, a lone 0 meaning an empty line,
+// erring with the byte offset of the bad clue in `text`
+pub(crate) fn parse_clues_at(text: &str) -> Result<Vec<usize>, (usize, String)> {
+    let mut clues = Vec::new();
+    let separator = |c: char| c == ',' || c.is_whitespace();
+    let mut at = 0;
+    for part in text.split(separator) {
+        let start = at;
+        at += part.len() + 1;
+        if part.is_empty() {
+            continue;
+        }
+        let clue = part
+            .parse::<usize>()
+            .map_err(|_| (start, format!("invalid clue `{}`", part)))?;
+        if clue > 0 {
+            clues.push(clue);
+        }
+    }
+    Ok(clues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::{from_art, CellPos};
+    use crate::spaces::node::CellState;
+    use crate::spaces::LineId;
+
+    #[test]
+    fn clue_lists() {
+        assert_eq!(parse_clues_at("1, 2,3"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_clues_at("4 1"), Ok(vec![4, 1]));
+        assert_eq!(parse_clues_at("0"), Ok(vec![]));
+        assert_eq!(parse_clues_at("1,x").unwrap_err().0, 2);
+    }
+
+    #[test]
+    fn diagnostic_positions() {
+        let input = "ab\ncdé\nf";
+        let at = ParseDiagnostic::at(input, 7, "x");
+        assert_eq!((at.line, at.column, at.offset), (2, 4, 7));
+        assert_eq!(ParseDiagnostic::at(input, 0, "x").column, 1);
+
+        let line = ParseDiagnostic::from_error(input, ParseError::new(3, "y"));
+        assert_eq!((line.line, line.column, line.offset), (3, 0, 8));
+        assert_eq!(line.to_string(), "line 3: y");
+        assert_eq!(
+            parse_clues_at("1, 2,x"),
+            Err((5, "invalid clue `x`".to_string()))
+        );
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(Format::from_path(Path::new("a/b.NON")), Some(Format::Non));
+        assert_eq!(Format::from_path(Path::new("x.xml")), Some(Format::Webpbn));
+        assert_eq!(Format::from_path(Path::new("x.tsv")), Some(Format::Csv));
+        assert_eq!(Format::from_path(Path::new("x.txt")), None);
+    }
+
+    const FORMATS: [Format; 6] = [
+        Format::Non,
+        Format::Webpbn,
+        Format::Json,
+        Format::Olsak,
+        Format::Clues,
+        Format::Csv,
+    ];
+
+    fn everything() -> Puzzle {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.#", "##.", "..#"]));
+        puzzle.remove_clues(LineId::Row(1));
+        puzzle.set_given(CellPos::new(0, 0), CellState::Filled);
+        puzzle
+    }
+
+    // The puzzle as it reads back from a format that drops what `format`
+    // cannot hold
+    fn reduced(puzzle: &Puzzle, format: Format) -> Puzzle {
+        let lines = |lines: &[Option<Vec<usize>>]| -> Vec<Option<Vec<usize>>> {
+            lines
+                .iter()
+                .map(|clues| {
+                    if format.preserves(Metadata::UncluedLines) {
+                        clues.clone()
+                    } else {
+                        Some(clues.clone().unwrap_or_default())
+                    }
+                })
+                .collect()
+        };
+        let mut reduced = Puzzle::from_lines(lines(puzzle.rows()), lines(puzzle.cols()));
+        if format.preserves(Metadata::Goal) {
+            reduced.set_goal(puzzle.goal().cloned());
+        }
+        if format.preserves(Metadata::Givens) {
+            for pos in puzzle.givens().positions() {
+                reduced.set_given(pos, puzzle.givens().get(pos));
+            }
+        }
+        reduced
+    }
+
+    #[test]
+    fn conversion_matrix() {
+        let puzzle = everything();
+        for &from in &FORMATS {
+            let input = from.write(&puzzle);
+            let read = reduced(&puzzle, from);
+            assert_eq!(from.parse(&input), Ok(read.clone()), "{:?}", from);
+            for &to in &FORMATS {
+                let output = convert(&input, from, to).unwrap();
+                assert_eq!(to.parse(&output), Ok(reduced(&read, to)), "{:?}", to);
+
+                let back = convert(&output, to, from).unwrap();
+                let lossless = [Metadata::UncluedLines, Metadata::Goal, Metadata::Givens]
+                    .iter()
+                    .all(|&metadata| !from.preserves(metadata) || to.preserves(metadata));
+                if lossless {
+                    assert_eq!(back, input, "{:?} -> {:?}", from, to);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_clues_mean_empty_lines() {
+        let expected = Puzzle::new(vec![vec![1], vec![]], vec![vec![1], vec![]]);
+        let inputs = [
+            (Format::Non, "width 2\nheight 2\nrows\n1\n0\ncolumns\n1\n0, 0\n"),
+            (
+                Format::Json,
+                r#"{"rows": [[1], [0]], "columns": [[1, 0], []]}"#,
+            ),
+            (
+                Format::Webpbn,
+                "<puzzleset><puzzle>\
+                 <clue type=\"columns\"><line><count>1</count></line><line><count>0</count></line></clue>\
+                 <clue type=\"rows\"><line><count>1</count></line><line></line></clue>\
+                 </puzzle></puzzleset>",
+            ),
+            (Format::Olsak, ": rows\n1\n0\n: columns\n0 1\n0\n"),
+            (Format::Clues, "1\n0\n\n1\n0\n"),
+            (Format::Csv, "1,\n0,0\n,\n1,0\n0,\n"),
+        ];
+        for &(format, input) in &inputs {
+            assert_eq!(format.parse(input), Ok(expected.clone()), "{:?}", format);
+        }
+        let bytes = binary::write(&Puzzle::new(vec![vec![1], vec![0]], vec![vec![1], vec![]]));
+        assert_eq!(binary::parse(&bytes), Ok(expected.clone()));
+
+        let mut puzzle = expected.clone();
+        puzzle.set_clues(LineId::Row(1), vec![0]);
+        assert_eq!(puzzle, expected);
+    }
+
+    #[test]
+    fn sniffing() {
+        let puzzle = Puzzle::new(vec![vec![1, 1], vec![2]], vec![vec![2], vec![1], vec![1]]);
+        for &format in &FORMATS {
+            let text = format.write(&puzzle);
+            assert_eq!(Format::sniff(&text), Some(format));
+            assert_eq!(load_any(text.as_bytes()), Ok(puzzle.clone()));
+        }
+        assert_eq!(Format::sniff("1, 1\n2\n\n2\n1\n1\n"), Some(Format::Clues));
+        assert_eq!(Format::sniff("1\t1\n2\n\n2\n1\n1\n"), Some(Format::Csv));
+        assert_eq!(Format::sniff("title \"x\"\nwidth 3\n"), Some(Format::Non));
+        assert_eq!(Format::sniff("hello"), None);
+        assert!(load_any("".as_bytes()).is_err());
+    }
+}
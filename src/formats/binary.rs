@@ -0,0 +1,279 @@
+//! Compact binary encoding for shipping many puzzles at once
+//!
+//! Layout, all numbers varints unless noted:
+//!
+//! - magic `GRDP`, a version byte, then the number of puzzles
+//! - per puzzle: a flags byte, width, height, then every row and column as
+//!   its clue count plus one (`0` for an unclued line) followed by its clues
+//! - a goal, if flagged: one bit per cell row-major, least significant bit
+//!   first, or two bits per cell when it has unknown cells
+//! - givens, if flagged: two bits per cell, `0` unknown, `1` empty, `2` filled
+
+use crate::bytes::{write_varint, ByteReader};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::Grid;
+use crate::spaces::node::CellState;
+use std::convert::TryFrom;
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"GRDP";
+const VERSION: u8 = 1;
+
+const GOAL: u8 = 1;
+const PARTIAL_GOAL: u8 = 2;
+const GIVENS: u8 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Corrupt,
+    /// `parse` found this many puzzles rather than one
+    NotSingle(usize),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinaryError::BadMagic => write!(f, "not a binary puzzle file"),
+            BinaryError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            BinaryError::Truncated => write!(f, "file ended early"),
+            BinaryError::Corrupt => write!(f, "file contains invalid data"),
+            BinaryError::NotSingle(n) => write!(f, "expected one puzzle, found {}", n),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+fn code(state: CellState) -> u8 {
+    match state {
+        CellState::Unknown => 0,
+        CellState::Empty => 1,
+        CellState::Filled => 2,
+    }
+}
+
+// Cells packed `bits` to a cell, least significant first
+fn write_cells(out: &mut Vec<u8>, grid: &Grid, bits: usize) {
+    let mut byte = 0;
+    for (i, pos) in grid.positions().enumerate() {
+        let value = match bits {
+            1 => (grid.get(pos) == CellState::Filled) as u8,
+            _ => code(grid.get(pos)),
+        };
+        byte |= value << (i * bits % 8);
+        if ((i + 1) * bits).is_multiple_of(8) {
+            out.push(byte);
+            byte = 0;
+        }
+    }
+    if !(grid.width() * grid.height() * bits).is_multiple_of(8) {
+        out.push(byte);
+    }
+}
+
+fn read_cells(
+    r: &mut ByteReader<'_>,
+    width: usize,
+    height: usize,
+    bits: usize,
+) -> Result<Grid, BinaryError> {
+    let cells = width * height;
+    let bytes = r
+        .take((cells * bits).div_ceil(8))
+        .ok_or(BinaryError::Truncated)?;
+    let mut grid = Grid::new(width, height);
+    let positions: Vec<_> = grid.positions().collect();
+    for (i, pos) in positions.into_iter().enumerate() {
+        let value = bytes[i * bits / 8] >> (i * bits % 8) & ((1 << bits) - 1);
+        let state = match (bits, value) {
+            (1, 1) | (2, 2) => CellState::Filled,
+            (1, 0) | (2, 1) => CellState::Empty,
+            (2, 0) => CellState::Unknown,
+            _ => return Err(BinaryError::Corrupt),
+        };
+        grid.set_state(pos, state);
+    }
+    Ok(grid)
+}
+
+fn write_puzzle(out: &mut Vec<u8>, puzzle: &Puzzle) {
+    let mut flags = 0;
+    if let Some(goal) = puzzle.goal() {
+        flags |= if goal.is_complete() {
+            GOAL
+        } else {
+            PARTIAL_GOAL
+        };
+    }
+    if puzzle.has_givens() {
+        flags |= GIVENS;
+    }
+    out.push(flags);
+    write_varint(out, puzzle.width() as u64);
+    write_varint(out, puzzle.height() as u64);
+    for clues in puzzle.rows().iter().chain(puzzle.cols()) {
+        match clues {
+            Some(clues) => {
+                write_varint(out, clues.len() as u64 + 1);
+                for &clue in clues {
+                    write_varint(out, clue as u64);
+                }
+            }
+            None => write_varint(out, 0),
+        }
+    }
+    if let Some(goal) = puzzle.goal() {
+        write_cells(out, goal, if flags & GOAL != 0 { 1 } else { 2 });
+    }
+    if flags & GIVENS != 0 {
+        write_cells(out, puzzle.givens(), 2);
+    }
+}
+
+fn read_puzzle(r: &mut ByteReader<'_>) -> Result<Puzzle, BinaryError> {
+    let flags = r.u8().ok_or(BinaryError::Truncated)?;
+    if flags & !(GOAL | PARTIAL_GOAL | GIVENS) != 0
+        || flags & GOAL != 0 && flags & PARTIAL_GOAL != 0
+    {
+        return Err(BinaryError::Corrupt);
+    }
+    let mut varint = || {
+        let value = r.varint().ok_or(BinaryError::Truncated)?;
+        usize::try_from(value).map_err(|_| BinaryError::Corrupt)
+    };
+    let (width, height) = (varint()?, varint()?);
+    width.checked_mul(height).ok_or(BinaryError::Corrupt)?;
+
+    let mut lines = Vec::new();
+    for _ in 0..width.checked_add(height).ok_or(BinaryError::Corrupt)? {
+        lines.push(match varint()? {
+            0 => None,
+            count => Some(
+                (1..count)
+                    .map(|_| varint())
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        });
+    }
+    let cols = lines.split_off(height);
+    let mut puzzle = Puzzle::from_lines(lines, cols);
+
+    if flags & (GOAL | PARTIAL_GOAL) != 0 {
+        let bits = if flags & GOAL != 0 { 1 } else { 2 };
+        puzzle.set_goal(Some(read_cells(r, width, height, bits)?));
+    }
+    if flags & GIVENS != 0 {
+        let givens = read_cells(r, width, height, 2)?;
+        for pos in givens.positions() {
+            puzzle.set_given(pos, givens.get(pos));
+        }
+    }
+    Ok(puzzle)
+}
+
+/// Encode a collection of puzzles
+pub fn write_all(puzzles: &[Puzzle]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    write_varint(&mut out, puzzles.len() as u64);
+    for puzzle in puzzles {
+        write_puzzle(&mut out, puzzle);
+    }
+    out
+}
+
+pub fn parse_all(bytes: &[u8]) -> Result<Vec<Puzzle>, BinaryError> {
+    let mut r = ByteReader::new(bytes);
+    if r.take(4) != Some(&MAGIC[..]) {
+        return Err(BinaryError::BadMagic);
+    }
+    match r.u8().ok_or(BinaryError::Truncated)? {
+        VERSION => {}
+        version => return Err(BinaryError::UnsupportedVersion(version)),
+    }
+    let count = r.varint().ok_or(BinaryError::Truncated)?;
+    let mut puzzles = Vec::new();
+    for _ in 0..count {
+        puzzles.push(read_puzzle(&mut r)?);
+    }
+    if !r.is_done() {
+        return Err(BinaryError::Corrupt);
+    }
+    Ok(puzzles)
+}
+
+/// Encode one puzzle, as a collection of one
+pub fn write(puzzle: &Puzzle) -> Vec<u8> {
+    write_all(std::slice::from_ref(puzzle))
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Puzzle, BinaryError> {
+    let mut puzzles = parse_all(bytes)?;
+    match puzzles.len() {
+        1 => Ok(puzzles.remove(0)),
+        n => Err(BinaryError::NotSingle(n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::json;
+    use crate::spaces::grid::{from_art, CellPos};
+    use crate::spaces::LineId;
+
+    fn samples() -> Vec<Puzzle> {
+        let full = Puzzle::from_goal(from_art(&["#.#.#", "#####", "..#..", "#...#"]));
+        let mut partial = full.clone();
+        partial.remove_clues(LineId::Column(3));
+        partial.set_given(CellPos::new(2, 2), CellState::Filled);
+        let mut goal = from_art(&["#.#.#", "#####", "..#..", "#...#"]);
+        goal.set_state(CellPos::new(0, 0), CellState::Unknown);
+        partial.set_goal(Some(goal));
+        vec![
+            full,
+            partial,
+            Puzzle::new(vec![vec![300]], vec![vec![1]; 300]),
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        let puzzles = samples();
+        assert_eq!(parse_all(&write_all(&puzzles)), Ok(puzzles.clone()));
+        assert_eq!(parse(&write(&puzzles[1])), Ok(puzzles[1].clone()));
+        assert_eq!(parse_all(&write_all(&[])), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn smaller_than_json() {
+        let puzzle = &samples()[0];
+        let bytes = write(puzzle);
+
+        // Header, flags, size, 9 lines of clues and 20 goal bits
+        assert_eq!(bytes.len(), 6 + 3 + 23 + 3);
+        assert!(bytes.len() * 4 < json::write(puzzle).len());
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = write_all(&samples());
+        assert_eq!(parse_all(b"GRDR\x01"), Err(BinaryError::BadMagic));
+        assert_eq!(
+            parse_all(b"GRDP\x07\x00"),
+            Err(BinaryError::UnsupportedVersion(7))
+        );
+        for len in 0..bytes.len() {
+            assert!(parse_all(&bytes[..len]).is_err());
+        }
+        assert_eq!(parse(&bytes), Err(BinaryError::NotSingle(3)));
+
+        let mut trailing = write(&samples()[0]);
+        trailing.push(0);
+        assert_eq!(parse_all(&trailing), Err(BinaryError::Corrupt));
+        assert_eq!(parse_all(b"GRDP\x01\x01\x08"), Err(BinaryError::Corrupt));
+    }
+}
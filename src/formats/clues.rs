@@ -0,0 +1,78 @@
+//! Bare clue text as pasted from a web page or forum post: one line per row,
+//! a blank line, then one line per column, with clues separated by commas or
+//! spaces and `0` marking an empty line.
+
+use super::{parse_clues_at, ParseError};
+use crate::puzzle::Puzzle;
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    let mut blocks = Vec::new();
+    let mut block = Vec::new();
+    for (i, raw) in input.lines().enumerate() {
+        let text = raw.trim();
+        if text.is_empty() {
+            if !block.is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+            continue;
+        }
+        let clues = parse_clues_at(text).map_err(|(_, message)| ParseError::new(i + 1, message))?;
+        block.push(clues);
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+
+    let mut blocks = blocks.into_iter();
+    match (blocks.next(), blocks.next(), blocks.next()) {
+        (Some(rows), Some(cols), None) => Ok(Puzzle::new(rows, cols)),
+        _ => Err(ParseError::new(
+            0,
+            "expected rows and columns separated by one blank line",
+        )),
+    }
+}
+
+/// Write the clues, unclued lines becoming empty ones
+pub fn write(puzzle: &Puzzle) -> String {
+    let mut out = String::new();
+    write_lines(&mut out, puzzle.rows());
+    out.push('\n');
+    write_lines(&mut out, puzzle.cols());
+    out
+}
+
+// Space separated clues per line, `0` for an empty or unclued line
+pub(super) fn write_lines(out: &mut String, lines: &[Option<Vec<usize>>]) {
+    for clues in lines {
+        match clues.as_deref() {
+            Some(clues) if !clues.is_empty() => {
+                let text: Vec<String> = clues.iter().map(usize::to_string).collect();
+                out.push_str(&text.join(" "));
+            }
+            _ => out.push('0'),
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_text() {
+        let puzzle = parse("\n1, 1\n2\n\n\n2\n1\n0\n\n").unwrap();
+
+        assert_eq!(puzzle.rows(), &[Some(vec![1, 1]), Some(vec![2])]);
+        assert_eq!(puzzle.cols()[2], Some(vec![]));
+        assert_eq!(parse(&write(&puzzle)), Ok(puzzle));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("1\n1\n").unwrap_err().line, 0);
+        assert_eq!(parse("1\n\n1\n\n1\n").unwrap_err().line, 0);
+        assert_eq!(parse("1\n\nx\n").unwrap_err().line, 3);
+    }
+}
@@ -0,0 +1,77 @@
+//! Clues as a spreadsheet saves them: the layout of the clue text format
+//! with cells separated by commas, tabs or semicolons and optionally quoted.
+//! A row of empty cells counts as the blank line between rows and columns.
+
+use super::{clues, ParseError};
+use crate::puzzle::Puzzle;
+
+fn is_separator(c: char) -> bool {
+    c == ',' || c == ';' || c == '\t' || c == '"'
+}
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    // Keep one line per input line so errors point at the right one
+    let text: Vec<String> = input
+        .lines()
+        .map(|line| {
+            let line = line.trim_matches(|c: char| c.is_whitespace() || is_separator(c));
+            line.replace(is_separator, " ")
+        })
+        .collect();
+    clues::parse(&text.join("\n"))
+}
+
+/// Write one clue per cell, padding lines to the same number of cells
+pub fn write(puzzle: &Puzzle) -> String {
+    let cells = puzzle
+        .rows()
+        .iter()
+        .chain(puzzle.cols())
+        .map(|clues| clues.as_ref().map_or(1, |clues| clues.len().max(1)))
+        .max()
+        .unwrap_or(1);
+    let mut out = String::new();
+    write_lines(&mut out, puzzle.rows(), cells);
+    out.push_str(&",".repeat(cells - 1));
+    out.push('\n');
+    write_lines(&mut out, puzzle.cols(), cells);
+    out
+}
+
+fn write_lines(out: &mut String, lines: &[Option<Vec<usize>>], cells: usize) {
+    for clues in lines {
+        let mut row: Vec<String> = match clues.as_deref() {
+            Some(clues) if !clues.is_empty() => clues.iter().map(usize::to_string).collect(),
+            _ => vec!["0".to_string()],
+        };
+        row.resize(cells, String::new());
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreadsheet_exports() {
+        let expected = Puzzle::new(vec![vec![1, 1], vec![2]], vec![vec![2], vec![1], vec![1]]);
+        let csv = "1,1\r\n2,\r\n,\r\n2,\r\n1,\r\n1,\r\n";
+        let tsv = "1\t1\n2\t\n\t\n2\n1\n1\n";
+        let quoted = "\"1,1\";\n\"2\";\n;\n\"2\";\n1;\n1;\n";
+        for input in &[csv, tsv, quoted] {
+            assert_eq!(parse(input), Ok(expected.clone()), "{:?}", input);
+        }
+
+        let written = write(&expected);
+        assert_eq!(written, "1,1\n2,\n,\n2,\n1,\n1,\n");
+        assert_eq!(parse(&written), Ok(expected));
+    }
+
+    #[test]
+    fn errors_keep_their_line() {
+        assert_eq!(parse("1,1\n,\n2,x\n").unwrap_err().line, 3);
+        assert_eq!(parse("1\n2\n").unwrap_err().line, 0);
+    }
+}
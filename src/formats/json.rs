@@ -0,0 +1,437 @@
+//! A JSON format: `width`, `height`, `rows` and `columns` arrays of clue lists
+//! (`null` for an unclued line), an optional `goal` of `#`/`.` strings and
+//! optional `givens` strings using `?` for cells that are not given.
+
+use super::{ParseDiagnostic, ParseError};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::fmt::Write;
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    at: usize,
+    line: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, message)
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(&c) = self.input.get(self.at) {
+            match c {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => return,
+            }
+            self.at += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_space();
+        self.input.get(self.at).cloned()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), ParseError> {
+        if self.peek() == Some(c) {
+            self.at += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c as char)))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, ParseError> {
+        if self.input[self.at..].starts_with(word.as_bytes()) {
+            self.at += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected token"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.at += 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b'}') => {
+                            self.at += 1;
+                            return Ok(Value::Object(fields));
+                        }
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.at += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b']') => {
+                            self.at += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'n') => self.keyword("null", Value::Null),
+            Some(b't') => self.keyword("true", Value::Bool(true)),
+            Some(b'f') => self.keyword("false", Value::Bool(false)),
+            Some(c) if c == b'-' || c.is_ascii_digit() => {
+                let start = self.at;
+                while self
+                    .input
+                    .get(self.at)
+                    .is_some_and(|c| b"+-.eE".contains(c) || c.is_ascii_digit())
+                {
+                    self.at += 1;
+                }
+                let text = std::str::from_utf8(&self.input[start..self.at]).unwrap();
+                text.parse()
+                    .map(Value::Number)
+                    .map_err(|_| self.error(format!("invalid number `{}`", text)))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let c = *self
+                .input
+                .get(self.at)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.at += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self
+                        .input
+                        .get(self.at)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.at += 1;
+                    out.push(match escaped {
+                        b'n' => b'\n',
+                        b't' => b'\t',
+                        b'r' => b'\r',
+                        b'"' | b'\\' | b'/' => escaped,
+                        _ => return Err(self.error("unsupported escape")),
+                    });
+                }
+                b'\n' => return Err(self.error("newline in string")),
+                _ => out.push(c),
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+}
+
+pub(crate) fn field<'a>(fields: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+    fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+pub(crate) fn number(value: &Value, what: &str) -> Result<usize, ParseError> {
+    match value {
+        Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as usize),
+        _ => Err(ParseError::new(
+            0,
+            format!("{} must be a whole number", what),
+        )),
+    }
+}
+
+fn clue_lines(value: Option<&Value>, what: &str) -> Result<Vec<Option<Vec<usize>>>, ParseError> {
+    let lines = match value {
+        Some(Value::Array(lines)) => lines,
+        _ => return Err(ParseError::new(0, format!("missing `{}` array", what))),
+    };
+    lines
+        .iter()
+        .map(|line| match line {
+            Value::Null => Ok(None),
+            Value::Array(clues) => clues
+                .iter()
+                .map(|clue| number(clue, "clue"))
+                .filter(|clue| *clue != Ok(0))
+                .collect::<Result<_, _>>()
+                .map(Some),
+            _ => Err(ParseError::new(
+                0,
+                format!("`{}` entries must be arrays or null", what),
+            )),
+        })
+        .collect()
+}
+
+// A whole document holding one value, erring with the byte offset reached
+fn document(input: &str) -> Result<Value, (usize, ParseError)> {
+    let mut reader = Reader {
+        input: input.as_bytes(),
+        at: 0,
+        line: 1,
+    };
+    let value = reader.value().map_err(|error| (reader.at, error))?;
+    if reader.peek().is_some() {
+        return Err((reader.at, reader.error("trailing characters")));
+    }
+    Ok(value)
+}
+
+pub(crate) fn parse_value(input: &str) -> Result<Value, ParseError> {
+    document(input).map_err(|(_, error)| error)
+}
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    match parse_value(input)? {
+        Value::Object(fields) => puzzle_from(&fields),
+        _ => Err(ParseError::new(1, "expected an object")),
+    }
+}
+
+/// Parse, locating syntax errors down to the byte
+///
+/// Parsing stops at the first problem, so there is at most one diagnostic.
+pub fn diagnose(input: &str) -> Result<Puzzle, Vec<ParseDiagnostic>> {
+    let value = document(input)
+        .map_err(|(at, error)| vec![ParseDiagnostic::at(input, at, error.message)])?;
+    let puzzle = match value {
+        Value::Object(fields) => puzzle_from(&fields),
+        _ => Err(ParseError::new(1, "expected an object")),
+    };
+    puzzle.map_err(|error| vec![ParseDiagnostic::from_error(input, error)])
+}
+
+pub(crate) fn puzzle_from(fields: &[(String, Value)]) -> Result<Puzzle, ParseError> {
+    let rows = clue_lines(field(fields, "rows"), "rows")?;
+    let cols = clue_lines(field(fields, "columns"), "columns")?;
+    for (key, len) in [("height", rows.len()), ("width", cols.len())].iter() {
+        if let Some(value) = field(fields, key) {
+            if number(value, key)? != *len {
+                return Err(ParseError::new(
+                    0,
+                    format!("`{}` does not match the clues", key),
+                ));
+            }
+        }
+    }
+
+    let mut puzzle = Puzzle::from_lines(rows, cols);
+    if let Some(goal) = cells(fields, "goal", &puzzle)? {
+        if !goal.is_complete() {
+            return Err(ParseError::new(0, "`goal` has unknown cells"));
+        }
+        puzzle.set_goal(Some(goal));
+    }
+    if let Some(givens) = cells(fields, "givens", &puzzle)? {
+        for pos in givens.positions() {
+            puzzle.set_given(pos, givens.get(pos));
+        }
+    }
+    Ok(puzzle)
+}
+
+// An array of row strings: `#` filled, `.` empty and `?` unknown
+fn cells(
+    fields: &[(String, Value)],
+    key: &str,
+    puzzle: &Puzzle,
+) -> Result<Option<Grid>, ParseError> {
+    let rows = match field(fields, key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Array(rows)) => rows,
+        Some(_) => {
+            return Err(ParseError::new(
+                0,
+                format!("`{}` must be an array of strings", key),
+            ))
+        }
+    };
+    let fits = rows.len() == puzzle.height()
+        && rows.iter().all(
+            |row| matches!(row, Value::String(text) if text.chars().count() == puzzle.width()),
+        );
+    if !fits {
+        return Err(ParseError::new(
+            0,
+            format!("`{}` does not match puzzle size", key),
+        ));
+    }
+
+    let mut grid = Grid::new(puzzle.width(), puzzle.height());
+    for (row, text) in rows.iter().enumerate() {
+        if let Value::String(text) = text {
+            for (col, c) in text.chars().enumerate() {
+                let pos = CellPos::new(row, col);
+                match c {
+                    '#' => grid.solve(pos, true),
+                    '.' => grid.solve(pos, false),
+                    '?' => {}
+                    _ => {
+                        return Err(ParseError::new(
+                            0,
+                            format!("invalid `{}` cell `{}`", key, c),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+    Ok(Some(grid))
+}
+
+/// Quote a string, escaping what JSON requires
+pub(crate) fn write_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_cells(out: &mut String, key: &str, grid: &Grid) {
+    let _ = write!(out, ",\n  \"{}\": [", key);
+    for row in 0..grid.height() {
+        if row > 0 {
+            out.push_str(", ");
+        }
+        let text: String = grid
+            .line_states(LineId::Row(row))
+            .into_iter()
+            .map(|cell| match cell {
+                CellState::Filled => '#',
+                CellState::Empty => '.',
+                CellState::Unknown => '?',
+            })
+            .collect();
+        let _ = write!(out, "\"{}\"", text);
+    }
+    out.push(']');
+}
+
+fn write_lines(out: &mut String, key: &str, lines: &[Option<Vec<usize>>]) {
+    let _ = write!(out, "  \"{}\": [", key);
+    for (i, clues) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        match clues {
+            Some(clues) => {
+                let list: Vec<String> = clues.iter().map(|c| c.to_string()).collect();
+                let _ = write!(out, "[{}]", list.join(", "));
+            }
+            None => out.push_str("null"),
+        }
+    }
+    out.push(']');
+}
+
+pub fn write(puzzle: &Puzzle) -> String {
+    let mut out = String::from("{\n");
+    let _ = writeln!(out, "  \"width\": {},", puzzle.width());
+    let _ = writeln!(out, "  \"height\": {},", puzzle.height());
+    write_lines(&mut out, "rows", puzzle.rows());
+    out.push_str(",\n");
+    write_lines(&mut out, "columns", puzzle.cols());
+
+    if let Some(goal) = puzzle.goal() {
+        write_cells(&mut out, "goal", goal);
+    }
+    if puzzle.has_givens() {
+        write_cells(&mut out, "givens", puzzle.givens());
+    }
+    out.push_str("\n}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn parse_sample() {
+        let puzzle = parse(
+            r#"{"width": 2, "height": 2,
+                "rows": [[1], null],
+                "columns": [[0], [1]],
+                "title": "a \"small\" one"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(puzzle.rows(), &[Some(vec![1]), None]);
+        assert_eq!(puzzle.cols(), &[Some(vec![]), Some(vec![1])]);
+        assert_eq!(puzzle.goal(), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.#", "###"]));
+        puzzle.remove_clues(LineId::Column(1));
+        puzzle.set_given(CellPos::new(1, 2), CellState::Filled);
+        let text = write(&puzzle);
+
+        assert!(text.contains(r#""givens": ["???", "??#"]"#));
+        assert_eq!(parse(&text), Ok(puzzle));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("{\n\"rows\": [1,]\n}").unwrap_err().line, 2);
+        assert!(parse(r#"{"rows": [], "columns": [], "width": 3}"#).is_err());
+        assert!(parse(r#"{"rows": [[-1]], "columns": [[1]]}"#).is_err());
+        assert!(parse("[] x").is_err());
+    }
+
+    #[test]
+    fn syntax_error_position() {
+        let diagnostics = diagnose("{\n  \"rows\": [1,]\n}").unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 14));
+        let whole = diagnose(r#"{"rows": [], "columns": [], "width": 3}"#).unwrap_err();
+        assert_eq!(whole[0].line, 0);
+    }
+}
@@ -0,0 +1,295 @@
+//! The `.non` text format: `width`/`height` keywords, `rows` and `columns`
+//! sections with one comma separated clue list per line (`-` for an unclued
+//! line), an optional `goal` string of 0s and 1s and an optional `given`
+//! string using `?` for cells that are not given.
+
+use super::{parse_clues_at, ParseDiagnostic, ParseError};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::fmt::Write;
+
+enum Section {
+    Header,
+    Rows,
+    Columns,
+}
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    diagnose(input).map_err(|mut diagnostics| diagnostics.remove(0).into())
+}
+
+/// Parse, carrying on past bad lines to report every problem found
+pub fn diagnose(input: &str) -> Result<Puzzle, Vec<ParseDiagnostic>> {
+    let mut width = None;
+    let mut height = None;
+    let mut rows = Vec::new();
+    let mut cols = Vec::new();
+    let mut goal = None;
+    let mut given = None;
+    let mut section = Section::Header;
+    let mut diagnostics = Vec::new();
+
+    let mut offset = 0;
+    for (i, raw) in input.split('\n').enumerate() {
+        let line = i + 1;
+        let start = offset;
+        offset += raw.len() + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        // Byte offset of the trimmed text and of what follows its keyword
+        let text_at = start + (raw.len() - raw.trim_start().len());
+        let (keyword, rest) = match text.find(char::is_whitespace) {
+            Some(at) => (&text[..at], text[at..].trim()),
+            None => (text, ""),
+        };
+        let rest_at = text_at + (text.len() - rest.len()).min(text.len());
+        let mut report = |at: usize, message: String| {
+            diagnostics.push(ParseDiagnostic::at(input, at, message));
+        };
+
+        match keyword {
+            "width" | "height" => match rest.parse() {
+                Ok(n) if keyword == "width" => width = Some(n),
+                Ok(n) => height = Some(n),
+                Err(_) => report(rest_at, format!("invalid number `{}`", rest)),
+            },
+            "rows" => section = Section::Rows,
+            "columns" => section = Section::Columns,
+            "goal" => goal = Some((rest.trim_matches('"').to_string(), line)),
+            "given" => given = Some((rest.trim_matches('"').to_string(), line)),
+            _ if text == "-" || text.starts_with(|c: char| c.is_ascii_digit()) => {
+                let clues = match text {
+                    "-" => None,
+                    _ => match parse_clues_at(text) {
+                        Ok(clues) => Some(clues),
+                        Err((at, message)) => {
+                            report(text_at + at, message);
+                            None
+                        }
+                    },
+                };
+                match section {
+                    Section::Rows => rows.push(clues),
+                    Section::Columns => cols.push(clues),
+                    Section::Header => {
+                        report(text_at, "clues before `rows` or `columns`".to_string())
+                    }
+                }
+            }
+            // Metadata such as `title` and `author`
+            _ => {}
+        }
+    }
+
+    let mut whole = |message: String| {
+        diagnostics.push(ParseDiagnostic::from_error(
+            input,
+            ParseError::new(0, message),
+        ));
+    };
+    if width.is_none() {
+        whole("missing `width`".to_string());
+    }
+    if height.is_none() {
+        whole("missing `height`".to_string());
+    }
+    if let Some(height) = height.filter(|&height| rows.len() != height) {
+        whole(format!("expected {} rows, found {}", height, rows.len()));
+    }
+    if let Some(width) = width.filter(|&width| cols.len() != width) {
+        whole(format!("expected {} columns, found {}", width, cols.len()));
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    let (width, height) = (width.unwrap_or(0), height.unwrap_or(0));
+
+    let mut puzzle = Puzzle::from_lines(rows, cols);
+    let located = |error| vec![ParseDiagnostic::from_error(input, error)];
+    if let Some((goal, line)) = goal {
+        let goal = parse_cells(&goal, width, height, line, "goal").map_err(located)?;
+        if !goal.is_complete() {
+            return Err(located(ParseError::new(line, "goal has unknown cells")));
+        }
+        puzzle.set_goal(Some(goal));
+    }
+    if let Some((given, line)) = given {
+        let given = parse_cells(&given, width, height, line, "given").map_err(located)?;
+        for pos in given.positions() {
+            puzzle.set_given(pos, given.get(pos));
+        }
+    }
+    Ok(puzzle)
+}
+
+// Row-major cells: `1` filled, `0` empty and `?` unknown
+fn parse_cells(
+    text: &str,
+    width: usize,
+    height: usize,
+    line: usize,
+    what: &str,
+) -> Result<Grid, ParseError> {
+    if text.chars().count() != width * height {
+        return Err(ParseError::new(
+            line,
+            format!("{} does not match puzzle size", what),
+        ));
+    }
+    let mut grid = Grid::new(width, height);
+    for (i, c) in text.chars().enumerate() {
+        let filled = match c {
+            '1' => true,
+            '0' => false,
+            '?' => continue,
+            _ => {
+                return Err(ParseError::new(
+                    line,
+                    format!("invalid {} cell `{}`", what, c),
+                ))
+            }
+        };
+        grid.solve(CellPos::new(i / width, i % width), filled);
+    }
+    Ok(grid)
+}
+
+fn cell_text(grid: &Grid) -> String {
+    grid.positions()
+        .map(|pos| match grid.get(pos) {
+            CellState::Filled => '1',
+            CellState::Empty => '0',
+            CellState::Unknown => '?',
+        })
+        .collect()
+}
+
+fn write_lines(out: &mut String, lines: &[Option<Vec<usize>>]) {
+    for clues in lines {
+        let clues = match clues {
+            Some(clues) => clues,
+            None => {
+                out.push_str("-\n");
+                continue;
+            }
+        };
+        if clues.is_empty() {
+            out.push_str("0\n");
+        } else {
+            let text: Vec<String> = clues.iter().map(usize::to_string).collect();
+            out.push_str(&text.join(","));
+            out.push('\n');
+        }
+    }
+}
+
+pub fn write(puzzle: &Puzzle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "width {}", puzzle.width());
+    let _ = writeln!(out, "height {}", puzzle.height());
+    out.push_str("\nrows\n");
+    write_lines(&mut out, puzzle.rows());
+    out.push_str("\ncolumns\n");
+    write_lines(&mut out, puzzle.cols());
+
+    if let Some(goal) = puzzle.goal() {
+        let _ = writeln!(out, "\ngoal \"{}\"", cell_text(goal));
+    }
+    if puzzle.has_givens() {
+        let _ = writeln!(out, "\ngiven \"{}\"", cell_text(puzzle.givens()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+    use crate::spaces::LineId;
+
+    const SAMPLE: &str = "\
+catalogue \"sample\"
+title \"Corner\"
+width 3
+height 2
+
+rows
+2
+0
+
+columns
+1
+1
+0
+";
+
+    #[test]
+    fn parse_sample() {
+        let puzzle = parse(SAMPLE).unwrap();
+
+        assert_eq!(puzzle.width(), 3);
+        assert_eq!(puzzle.rows(), &[Some(vec![2]), Some(vec![])]);
+        assert_eq!(puzzle.cols()[2], Some(vec![]));
+        assert!(puzzle.goal().is_none());
+    }
+
+    #[test]
+    fn round_trip_with_goal() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "##."]));
+        let text = write(&puzzle);
+
+        assert!(text.contains("goal \"101110\""));
+        assert_eq!(parse(&text), Ok(puzzle));
+    }
+
+    #[test]
+    fn unclued_lines() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.", "##"]));
+        puzzle.remove_clues(LineId::Row(1));
+        let text = write(&puzzle);
+
+        assert!(text.contains("rows\n1\n-\n"));
+        assert_eq!(parse(&text), Ok(puzzle));
+    }
+
+    #[test]
+    fn givens() {
+        let mut puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        puzzle.set_given(CellPos::new(1, 0), CellState::Filled);
+        let text = write(&puzzle);
+
+        assert!(text.contains("given \"??1?\""));
+        assert_eq!(parse(&text), Ok(puzzle));
+        assert!(parse("width 1\nheight 1\nrows\n1\ncolumns\n1\ngoal \"?\"").is_err());
+    }
+
+    #[test]
+    fn every_problem_reported() {
+        let diagnostics = diagnose("width x\nrows\n1\n 2,y\ncolumns\n1\n").unwrap_err();
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.column, d.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (1, 7, "invalid number `x`"),
+                (4, 4, "invalid clue `y`"),
+                (0, 0, "missing `width`"),
+                (0, 0, "missing `height`"),
+            ]
+        );
+        assert_eq!(diagnostics[1].offset, 18);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("width 3\nrows\n1,x\n").unwrap_err().line, 3);
+        assert!(parse("width 1\nheight 1\nrows\n1\n").is_err());
+        assert_eq!(parse("1,2\n").unwrap_err().line, 1);
+    }
+}
@@ -0,0 +1,118 @@
+//! The format read by Mirek Olšák's solver: an optional `#d` color table
+//! followed by `: rows` and `: columns` sections of space separated clues,
+//! `0` marking an empty line. Only black and white puzzles are read, and
+//! blank lines and `#` comments are skipped.
+
+use super::clues::write_lines;
+use super::{parse_clues_at, ParseError};
+use crate::puzzle::Puzzle;
+
+enum Section {
+    Header,
+    Colors,
+    Rows,
+    Columns,
+}
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    let mut rows = Vec::new();
+    let mut cols = Vec::new();
+    let mut section = Section::Header;
+
+    for (i, raw) in input.lines().enumerate() {
+        let line = i + 1;
+        let text = raw.trim();
+        if let Some(name) = text.strip_prefix(':') {
+            section = match name.trim() {
+                "rows" => Section::Rows,
+                "columns" => Section::Columns,
+                other => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("unknown section `{}`", other),
+                    ))
+                }
+            };
+            continue;
+        }
+        if text == "#d" {
+            section = Section::Colors;
+            continue;
+        }
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let lines = match section {
+            Section::Rows => &mut rows,
+            Section::Columns => &mut cols,
+            Section::Colors => continue,
+            Section::Header => {
+                return Err(ParseError::new(
+                    line,
+                    "clues before `: rows` or `: columns`",
+                ))
+            }
+        };
+        let clues = parse_clues_at(text).map_err(|(_, message)| ParseError::new(line, message))?;
+        lines.push(clues);
+    }
+
+    if rows.is_empty() || cols.is_empty() {
+        return Err(ParseError::new(0, "missing `: rows` or `: columns`"));
+    }
+    Ok(Puzzle::new(rows, cols))
+}
+
+/// Write the clues, unclued lines becoming empty ones
+pub fn write(puzzle: &Puzzle) -> String {
+    let mut out = String::from(": rows\n");
+    write_lines(&mut out, puzzle.rows());
+    out.push_str(": columns\n");
+    write_lines(&mut out, puzzle.cols());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+#d
+   0:   #FFFFFF   white
+   1:   #000000   black
+: rows
+2
+0
+: columns
+1
+1
+
+0
+";
+
+    #[test]
+    fn parse_sample() {
+        let puzzle = parse(SAMPLE).unwrap();
+
+        assert_eq!(puzzle.rows(), &[Some(vec![2]), Some(vec![])]);
+        assert_eq!(puzzle.width(), 3);
+        assert_eq!(puzzle.cols()[2], Some(vec![]));
+    }
+
+    #[test]
+    fn round_trip() {
+        let puzzle = Puzzle::new(vec![vec![1, 1], vec![2]], vec![vec![2], vec![1], vec![1]]);
+        let text = write(&puzzle);
+
+        assert!(text.starts_with(": rows\n1 1\n2\n: columns\n"));
+        assert_eq!(parse(&text), Ok(puzzle));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("1 2\n").unwrap_err().line, 1);
+        assert_eq!(parse(": rows\n1a\n").unwrap_err().line, 2);
+        assert_eq!(parse(": blocks\n").unwrap_err().line, 1);
+        assert_eq!(parse(": rows\n1\n").unwrap_err().line, 0);
+    }
+}
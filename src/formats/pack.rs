@@ -0,0 +1,242 @@
+//! Puzzle packs: many puzzles in one JSON document, each with its metadata
+//! and the puzzles that must be solved before it unlocks.
+//!
+//! ```json
+//! {"title": "Starter", "author": "Ann",
+//!  "puzzles": [{"id": "cup", "title": "Cup", "difficulty": 1,
+//!               "requires": [], "puzzle": {"rows": [[1]], "columns": [[1]]}}]}
+//! ```
+//!
+//! Puzzles keep the order they are listed in, and may only require puzzles
+//! listed before them.
+
+use super::json::{self, field, number, Value};
+use super::ParseError;
+use crate::puzzle::Puzzle;
+use std::collections::HashSet;
+
+/// One puzzle of a pack
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackEntry {
+    /// Unique within the pack
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub difficulty: Option<usize>,
+    /// Ids of the puzzles to solve before this one unlocks
+    pub requires: Vec<String>,
+    pub puzzle: Puzzle,
+}
+
+/// An ordered collection of puzzles
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Pack {
+    pub title: String,
+    pub author: Option<String>,
+    pub entries: Vec<PackEntry>,
+}
+
+fn string(value: Option<&Value>, what: &str) -> Result<Option<String>, ParseError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) => Ok(Some(text.clone())),
+        Some(_) => Err(ParseError::new(0, format!("`{}` must be a string", what))),
+    }
+}
+
+fn entry(value: &Value, index: usize) -> Result<PackEntry, ParseError> {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => {
+            return Err(ParseError::new(
+                0,
+                format!("puzzle {} must be an object", index),
+            ))
+        }
+    };
+    let id = string(field(fields, "id"), "id")?
+        .ok_or_else(|| ParseError::new(0, format!("puzzle {} has no `id`", index)))?;
+    let requires = match field(fields, "requires") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(ids)) => ids
+            .iter()
+            .map(|id| string(Some(id), "requires").map(Option::unwrap_or_default))
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(ParseError::new(0, "`requires` must be an array")),
+    };
+    let puzzle = match field(fields, "puzzle") {
+        Some(Value::Object(puzzle)) => json::puzzle_from(puzzle)?,
+        _ => {
+            return Err(ParseError::new(
+                0,
+                format!("puzzle `{}` has no `puzzle` object", id),
+            ))
+        }
+    };
+
+    Ok(PackEntry {
+        title: string(field(fields, "title"), "title")?.unwrap_or_else(|| id.clone()),
+        author: string(field(fields, "author"), "author")?,
+        difficulty: field(fields, "difficulty")
+            .filter(|value| **value != Value::Null)
+            .map(|value| number(value, "difficulty"))
+            .transpose()?,
+        requires,
+        puzzle,
+        id,
+    })
+}
+
+impl Pack {
+    pub fn parse(input: &str) -> Result<Pack, ParseError> {
+        let fields = match json::parse_value(input)? {
+            Value::Object(fields) => fields,
+            _ => return Err(ParseError::new(1, "expected an object")),
+        };
+        let entries = match field(&fields, "puzzles") {
+            Some(Value::Array(puzzles)) => puzzles
+                .iter()
+                .enumerate()
+                .map(|(i, value)| entry(value, i))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(ParseError::new(0, "missing `puzzles` array")),
+        };
+
+        let mut seen = HashSet::new();
+        for entry in &entries {
+            if let Some(missing) = entry.requires.iter().find(|id| !seen.contains(id)) {
+                return Err(ParseError::new(
+                    0,
+                    format!(
+                        "`{}` requires `{}`, not listed before it",
+                        entry.id, missing
+                    ),
+                ));
+            }
+            if !seen.insert(&entry.id) {
+                return Err(ParseError::new(0, format!("duplicate id `{}`", entry.id)));
+            }
+        }
+
+        Ok(Pack {
+            title: string(field(&fields, "title"), "title")?.unwrap_or_default(),
+            author: string(field(&fields, "author"), "author")?,
+            entries,
+        })
+    }
+
+    pub fn write(&self) -> String {
+        let mut out = String::from("{\n\"title\": ");
+        json::write_string(&mut out, &self.title);
+        if let Some(author) = &self.author {
+            out.push_str(",\n\"author\": ");
+            json::write_string(&mut out, author);
+        }
+        out.push_str(",\n\"puzzles\": [");
+        for (i, entry) in self.entries.iter().enumerate() {
+            out.push_str(if i == 0 { "\n{" } else { ",\n{" });
+            out.push_str("\"id\": ");
+            json::write_string(&mut out, &entry.id);
+            out.push_str(", \"title\": ");
+            json::write_string(&mut out, &entry.title);
+            if let Some(author) = &entry.author {
+                out.push_str(", \"author\": ");
+                json::write_string(&mut out, author);
+            }
+            if let Some(difficulty) = entry.difficulty {
+                out.push_str(&format!(", \"difficulty\": {}", difficulty));
+            }
+            out.push_str(", \"requires\": [");
+            for (j, id) in entry.requires.iter().enumerate() {
+                if j > 0 {
+                    out.push_str(", ");
+                }
+                json::write_string(&mut out, id);
+            }
+            out.push_str("],\n\"puzzle\": ");
+            out.push_str(json::write(&entry.puzzle).trim_end());
+            out.push('}');
+        }
+        out.push_str("\n]\n}\n");
+        out
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PackEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Entries whose requirements are all among `solved`, in pack order
+    pub fn unlocked<'a>(
+        &'a self,
+        solved: &'a HashSet<String>,
+    ) -> impl Iterator<Item = &'a PackEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.requires.iter().all(|id| solved.contains(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    fn sample() -> Pack {
+        let entry = |id: &str, art: &[&str], requires: &[&str]| PackEntry {
+            id: id.to_string(),
+            title: format!("The \"{}\"", id),
+            author: None,
+            difficulty: Some(id.len()),
+            requires: requires.iter().map(|id| id.to_string()).collect(),
+            puzzle: Puzzle::from_goal(from_art(art)),
+        };
+        Pack {
+            title: "Starter".to_string(),
+            author: Some("Ann".to_string()),
+            entries: vec![
+                entry("cup", &["#.#", "###"], &[]),
+                entry("key", &["##", ".#"], &[]),
+                entry("house", &[".#.", "###", "#.#"], &["cup", "key"]),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let pack = sample();
+        assert_eq!(Pack::parse(&pack.write()), Ok(pack));
+    }
+
+    #[test]
+    fn unlock_progression() {
+        let pack = sample();
+        let mut solved = HashSet::new();
+        let ids = |solved: &HashSet<String>| -> Vec<String> {
+            pack.unlocked(solved)
+                .map(|entry| entry.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&solved), ["cup", "key"]);
+        solved.insert("cup".to_string());
+        solved.insert("key".to_string());
+        assert_eq!(ids(&solved), ["cup", "key", "house"]);
+        assert_eq!(pack.get("house").unwrap().difficulty, Some(5));
+    }
+
+    #[test]
+    fn ordering_errors() {
+        let puzzle = r#"{"rows": [[1]], "columns": [[1]]}"#;
+        let early = format!(
+            r#"{{"puzzles": [{{"id": "a", "requires": ["b"], "puzzle": {0}}},
+                            {{"id": "b", "puzzle": {0}}}]}}"#,
+            puzzle
+        );
+        assert!(Pack::parse(&early).is_err());
+        let twice = format!(
+            r#"{{"puzzles": [{{"id": "a", "puzzle": {0}}}, {{"id": "a", "puzzle": {0}}}]}}"#,
+            puzzle
+        );
+        assert!(Pack::parse(&twice).is_err());
+        assert!(Pack::parse(r#"{"puzzles": [{"id": "a"}]}"#).is_err());
+    }
+}
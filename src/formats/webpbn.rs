@@ -0,0 +1,275 @@
+//! The webpbn XML format, reading black and white `grid` puzzles with an
+//! optional goal `<solution>` image and an optional `type="given"` image of
+//! pre-filled cells, `?` marking those not given.
+
+use super::xml::{self, Event};
+use super::{ParseDiagnostic, ParseError};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::fmt::Write;
+
+pub fn parse(input: &str) -> Result<Puzzle, ParseError> {
+    collect(input).map_err(|mut errors| errors.remove(0))
+}
+
+/// Parse, reporting every bad clue and image rather than only the first
+///
+/// Malformed XML still ends the parse at the first problem.
+pub fn diagnose(input: &str) -> Result<Puzzle, Vec<ParseDiagnostic>> {
+    collect(input).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|error| ParseDiagnostic::from_error(input, error))
+            .collect()
+    })
+}
+
+fn collect(input: &str) -> Result<Puzzle, Vec<ParseError>> {
+    let mut errors = Vec::new();
+    let mut rows = None;
+    let mut cols = None;
+    let mut goal = None;
+    let mut given = None;
+
+    // Which clue block, line and count we are inside
+    let mut clue_type: Option<(String, usize)> = None;
+    let mut lines: Vec<Option<Vec<usize>>> = Vec::new();
+    let mut in_line = false;
+    let mut in_count = false;
+    // Type of the `<solution>` being read
+    let mut solution: Option<String> = None;
+    let mut in_image = false;
+
+    for (event, line) in xml::events(input).map_err(|error| vec![error])? {
+        match event {
+            Event::Start { name, attrs } => match name.as_str() {
+                "clue" => {
+                    let kind = xml::attr(&attrs, "type").unwrap_or("").to_string();
+                    clue_type = Some((kind, line));
+                    lines.clear();
+                }
+                "line" if clue_type.is_some() => {
+                    in_line = true;
+                    lines.push(Some(Vec::new()));
+                }
+                "count" if in_line => in_count = true,
+                "solution" => {
+                    solution = Some(xml::attr(&attrs, "type").unwrap_or("goal").to_string())
+                }
+                "image" if solution.is_some() => in_image = true,
+                _ => {}
+            },
+            Event::End { name } => match name.as_str() {
+                "clue" => {
+                    let (kind, line) = clue_type.take().unwrap_or_default();
+                    let parsed = std::mem::take(&mut lines);
+                    match kind.as_str() {
+                        "rows" => rows = Some(parsed),
+                        "columns" => cols = Some(parsed),
+                        _ => errors.push(ParseError::new(
+                            line,
+                            format!("unknown clue type `{}`", kind),
+                        )),
+                    }
+                }
+                "line" => in_line = false,
+                "count" => in_count = false,
+                "image" => in_image = false,
+                "solution" => solution = None,
+                _ => {}
+            },
+            Event::Text(text) if in_count => match text.trim().parse::<usize>() {
+                Ok(0) => {}
+                Ok(count) => {
+                    if let Some(Some(clues)) = lines.last_mut() {
+                        clues.push(count);
+                    }
+                }
+                Err(_) => errors.push(ParseError::new(
+                    line,
+                    format!("invalid count `{}`", text.trim()),
+                )),
+            },
+            Event::Text(text) if in_image => match solution.as_deref() {
+                Some("goal") if goal.is_none() => goal = Some((text, line)),
+                Some("given") => given = Some((text, line)),
+                _ => {}
+            },
+            Event::Text(_) => {}
+        }
+    }
+
+    if rows.is_none() {
+        errors.push(ParseError::new(0, "missing row clues"));
+    }
+    if cols.is_none() {
+        errors.push(ParseError::new(0, "missing column clues"));
+    }
+    let (rows, cols) = match (rows, cols) {
+        (Some(rows), Some(cols)) => (rows, cols),
+        _ => return Err(errors),
+    };
+    let mut puzzle = Puzzle::from_lines(rows, cols);
+    if let Some((image, line)) = goal {
+        match parse_image(&image, &puzzle, line) {
+            Ok(goal) if goal.is_complete() => puzzle.set_goal(Some(goal)),
+            Ok(_) => errors.push(ParseError::new(line, "goal image has unknown cells")),
+            Err(error) => errors.push(error),
+        }
+    }
+    if let Some((image, line)) = given {
+        match parse_image(&image, &puzzle, line) {
+            Ok(given) => {
+                for pos in given.positions() {
+                    puzzle.set_given(pos, given.get(pos));
+                }
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(puzzle)
+    } else {
+        Err(errors)
+    }
+}
+
+// Image rows are written between pipes, `X` or `#` for filled cells and `?`
+// for unknown ones
+fn parse_image(image: &str, puzzle: &Puzzle, line: usize) -> Result<Grid, ParseError> {
+    let rows: Vec<&str> = image
+        .lines()
+        .map(|row| row.trim().trim_matches('|'))
+        .filter(|row| !row.is_empty())
+        .collect();
+    let fits = rows.len() == puzzle.height()
+        && rows.iter().all(|row| row.chars().count() == puzzle.width());
+    if !fits {
+        return Err(ParseError::new(
+            line,
+            "solution image does not match puzzle size",
+        ));
+    }
+
+    let mut grid = Grid::new(puzzle.width(), puzzle.height());
+    for (row, text) in rows.iter().enumerate() {
+        for (col, c) in text.chars().enumerate() {
+            match c {
+                '?' => {}
+                _ => grid.solve(CellPos::new(row, col), c == 'X' || c == '#'),
+            }
+        }
+    }
+    Ok(grid)
+}
+
+fn write_image(out: &mut String, kind: &str, grid: &Grid) {
+    let _ = writeln!(out, "<solution type=\"{}\">\n<image>", kind);
+    for row in 0..grid.height() {
+        out.push('|');
+        for cell in grid.line_states(LineId::Row(row)) {
+            out.push(match cell {
+                CellState::Filled => 'X',
+                CellState::Empty => '.',
+                CellState::Unknown => '?',
+            });
+        }
+        out.push_str("|\n");
+    }
+    out.push_str("</image>\n</solution>\n");
+}
+
+fn write_clues(out: &mut String, kind: &str, lines: &[Option<Vec<usize>>]) {
+    let _ = writeln!(out, "<clue type=\"{}\">", kind);
+    for clues in lines {
+        out.push_str("<line>");
+        for clue in clues.as_deref().unwrap_or(&[]) {
+            let _ = write!(out, "<count>{}</count>", clue);
+        }
+        out.push_str("</line>\n");
+    }
+    out.push_str("</clue>\n");
+}
+
+pub fn write(puzzle: &Puzzle) -> String {
+    let mut out = String::from("<?xml version=\"1.0\"?>\n<puzzleset>\n");
+    out.push_str("<puzzle type=\"grid\" defaultcolor=\"black\">\n");
+    out.push_str("<color name=\"white\" char=\".\">fff</color>\n");
+    out.push_str("<color name=\"black\" char=\"X\">000</color>\n");
+    write_clues(&mut out, "columns", puzzle.cols());
+    write_clues(&mut out, "rows", puzzle.rows());
+
+    if let Some(goal) = puzzle.goal() {
+        write_image(&mut out, "goal", goal);
+    }
+    if puzzle.has_givens() {
+        write_image(&mut out, "given", puzzle.givens());
+    }
+    out.push_str("</puzzle>\n</puzzleset>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    const SAMPLE: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE pbn SYSTEM "https://webpbn.com/pbn-0.3.dtd">
+<puzzleset>
+<puzzle type="grid" defaultcolor="black">
+<source>webpbn.com</source>
+<title>Tiny</title>
+<clue type="columns">
+<line><count>2</count></line>
+<line></line>
+</clue>
+<clue type="rows">
+<line><count>1</count></line>
+<line><count>1</count></line>
+</clue>
+<solution type="goal">
+<image>
+|X.|
+|X.|
+</image>
+</solution>
+</puzzle>
+</puzzleset>"#;
+
+    #[test]
+    fn parse_sample() {
+        let puzzle = parse(SAMPLE).unwrap();
+
+        assert_eq!(puzzle.cols(), &[Some(vec![2]), Some(vec![])]);
+        assert_eq!(puzzle.rows(), &[Some(vec![1]), Some(vec![1])]);
+        assert_eq!(puzzle.goal(), Some(&from_art(&["#.", "#."])));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["##.#", "...#", "#.##"]));
+        puzzle.set_given(CellPos::new(1, 1), CellState::Empty);
+        assert_eq!(parse(&write(&puzzle)), Ok(puzzle));
+    }
+
+    #[test]
+    fn every_bad_count() {
+        let input = "<clue type=\"rows\">\n<line><count>q</count></line>\n\
+                     <line><count>2</count><count>-</count></line></clue>";
+        let diagnostics = diagnose(input).unwrap_err();
+        let lines: Vec<_> = diagnostics.iter().map(|d| d.line).collect();
+
+        assert_eq!(lines, vec![2, 3, 0]);
+        assert_eq!(diagnostics[1].offset, input.find("<line><count>2").unwrap());
+        assert_eq!(diagnostics[2].message, "missing column clues");
+    }
+
+    #[test]
+    fn bad_count() {
+        let err = parse("<clue type=\"rows\">\n<line><count>q</count></line></clue>").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
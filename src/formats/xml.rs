@@ -0,0 +1,156 @@
+// Just enough XML to read webpbn files: tags, attributes, text and entities
+
+use super::ParseError;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Event {
+    Start {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    End {
+        name: String,
+    },
+    Text(String),
+}
+
+/// Events with the line each one started on
+pub(crate) fn events(input: &str) -> Result<Vec<(Event, usize)>, ParseError> {
+    let mut events = Vec::new();
+    let mut rest = input;
+    let mut line = 1;
+
+    while !rest.is_empty() {
+        if let Some(body) = rest.strip_prefix('<') {
+            let end = if body.starts_with("!--") {
+                body.find("-->").map(|i| i + 3)
+            } else {
+                body.find('>').map(|i| i + 1)
+            };
+            let end = end.ok_or_else(|| ParseError::new(line, "unterminated tag"))?;
+            let tag = &body[..end - 1];
+            let start_line = line;
+            line += tag.matches('\n').count();
+            rest = &body[end..];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                events.push((
+                    Event::End {
+                        name: name.trim().to_string(),
+                    },
+                    start_line,
+                ));
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attrs) = parse_tag(tag, start_line)?;
+            events.push((
+                Event::Start {
+                    name: name.clone(),
+                    attrs,
+                },
+                start_line,
+            ));
+            if self_closing {
+                events.push((Event::End { name }, start_line));
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            if !text.trim().is_empty() {
+                events.push((Event::Text(unescape(text)), line));
+            }
+            line += text.matches('\n').count();
+            rest = &rest[end..];
+        }
+    }
+
+    Ok(events)
+}
+
+fn parse_tag(tag: &str, line: usize) -> Result<(String, Vec<(String, String)>), ParseError> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_string();
+    if name.is_empty() {
+        return Err(ParseError::new(line, "tag without a name"));
+    }
+
+    let mut attrs = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let eq = rest
+            .find('=')
+            .ok_or_else(|| ParseError::new(line, format!("malformed attribute in <{}>", name)))?;
+        let key = rest[..eq].trim().to_string();
+        let value = rest[eq + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or_else(|| ParseError::new(line, format!("unquoted attribute `{}`", key)))?;
+        let close = value[1..]
+            .find(quote)
+            .ok_or_else(|| ParseError::new(line, format!("unterminated attribute `{}`", key)))?;
+        attrs.push((key, unescape(&value[1..close + 1])));
+        rest = value[close + 2..].trim_start();
+    }
+    Ok((name, attrs))
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn attr<'a>(attrs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_tags_and_text() {
+        let events =
+            events("<?xml version=\"1.0\"?>\n<a x='1' y=\"&lt;\">\n hi &amp; <b/></a>").unwrap();
+
+        assert_eq!(
+            events[0],
+            (
+                Event::Start {
+                    name: "a".to_string(),
+                    attrs: vec![
+                        ("x".to_string(), "1".to_string()),
+                        ("y".to_string(), "<".to_string())
+                    ]
+                },
+                2
+            )
+        );
+        assert_eq!(events[1], (Event::Text("\n hi & ".to_string()), 2));
+        assert_eq!(
+            events[3].0,
+            Event::End {
+                name: "b".to_string()
+            }
+        );
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(events("<a>\n<b").unwrap_err().line, 2);
+    }
+}
@@ -0,0 +1,381 @@
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod text;
+
+use crate::difficulty::DifficultyReport;
+use crate::puzzle::Puzzle;
+use crate::rng::Rng;
+use crate::solver;
+use crate::solver::technique::Technique;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::Symmetry;
+
+/// Flip as few cells of a picture as possible to make its puzzle unique
+///
+/// Flips are searched breadth first among the cells two of the current
+/// solutions disagree on, up to `max_flips` of them. Returns the repaired
+/// puzzle with the cells flipped, or `None` if no repair was found.
+pub fn make_unique(goal: Grid, max_flips: usize) -> Option<(Puzzle, Vec<CellPos>)> {
+    let mut goal = goal;
+    let mut flips = Vec::new();
+    (0..=max_flips).find_map(|depth| {
+        repair_flips(&mut goal, &mut flips, depth)?;
+        Some((Puzzle::from_goal(goal.clone()), flips.clone()))
+    })
+}
+
+fn flip(goal: &mut Grid, pos: CellPos) {
+    let state = match goal.get(pos) {
+        CellState::Filled => CellState::Empty,
+        _ => CellState::Filled,
+    };
+    goal.set_state(pos, state);
+}
+
+// Depth-limited search, leaving `goal` and `flips` at the repair if found
+fn repair_flips(goal: &mut Grid, flips: &mut Vec<CellPos>, depth: usize) -> Option<()> {
+    let puzzle = Puzzle::from_goal(goal.clone());
+    let found = solver::Solver::new(&puzzle).solutions(2);
+    if found.len() < 2 {
+        return Some(());
+    }
+    if depth == 0 {
+        return None;
+    }
+
+    let candidates: Vec<CellPos> = goal
+        .positions()
+        .filter(|&pos| found[0].get(pos) != found[1].get(pos))
+        .filter(|pos| !flips.contains(pos))
+        .collect();
+    for pos in candidates {
+        flip(goal, pos);
+        flips.push(pos);
+        if repair_flips(goal, flips, depth - 1).is_some() {
+            return Some(());
+        }
+        flips.pop();
+        flip(goal, pos);
+    }
+    None
+}
+
+/// Limits on the random pictures puzzles are generated from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Constraints {
+    /// Lowest and highest share of filled cells, from 0 to 1
+    pub fill: (f64, f64),
+    /// Most clues allowed on any row or column
+    pub max_clues: Option<usize>,
+    /// Shortest and longest block allowed
+    pub block_len: (usize, usize),
+    /// Symmetry the picture, and so its clues, must have
+    pub symmetry: Option<Symmetry>,
+}
+
+impl Default for Constraints {
+    fn default() -> Constraints {
+        Constraints {
+            fill: (0.0, 1.0),
+            max_clues: None,
+            block_len: (1, usize::MAX),
+            symmetry: None,
+        }
+    }
+}
+
+impl Constraints {
+    pub fn accepts(&self, goal: &Grid) -> bool {
+        let cells = goal.width() * goal.height();
+        let filled = goal
+            .positions()
+            .filter(|&pos| goal.get(pos) == CellState::Filled)
+            .count();
+        let ratio = if cells == 0 {
+            0.0
+        } else {
+            filled as f64 / cells as f64
+        };
+        if ratio < self.fill.0 || ratio > self.fill.1 {
+            return false;
+        }
+        if let Some(symmetry) = self.symmetry {
+            if symmetry.swaps_axes() && goal.width() != goal.height()
+                || goal.transformed(symmetry) != *goal
+            {
+                return false;
+            }
+        }
+
+        let puzzle = Puzzle::from_goal(goal.clone());
+        puzzle.lines().all(|line| {
+            let clues = puzzle.clues(line).unwrap_or(&[]);
+            self.max_clues.is_none_or(|max| clues.len() <= max)
+                && clues
+                    .iter()
+                    .all(|&len| len >= self.block_len.0 && len <= self.block_len.1)
+        })
+    }
+
+    // Break rows' overlong blocks, which is often all a candidate needs
+    fn repair(&self, goal: &mut Grid) {
+        for row in 0..goal.height() {
+            let mut run = 0;
+            for col in 0..goal.width() {
+                let pos = CellPos::new(row, col);
+                if goal.get(pos) != CellState::Filled {
+                    run = 0;
+                } else if run == self.block_len.1 {
+                    goal.set_state(pos, CellState::Empty);
+                    run = 0;
+                } else {
+                    run += 1;
+                }
+            }
+        }
+    }
+}
+
+// Where `symmetry` takes a cell, the inverse being in the same orbit
+fn image_of(symmetry: Symmetry, pos: CellPos, width: usize, height: usize) -> CellPos {
+    let (row, col) = (pos.row, pos.col);
+    match symmetry {
+        Symmetry::MirrorH => CellPos::new(row, width - 1 - col),
+        Symmetry::MirrorV => CellPos::new(height - 1 - row, col),
+        Symmetry::Rotate90 | Symmetry::Rotate270 => CellPos::new(col, width - 1 - row),
+        Symmetry::Rotate180 => CellPos::new(height - 1 - row, width - 1 - col),
+        Symmetry::Transpose => CellPos::new(col, row),
+        Symmetry::AntiTranspose => CellPos::new(width - 1 - col, height - 1 - row),
+    }
+}
+
+// Copy each cell across its orbit, unless the grid can't have the symmetry
+fn symmetrize(goal: &mut Grid, symmetry: Symmetry) {
+    let (width, height) = (goal.width(), goal.height());
+    if symmetry.swaps_axes() && width != height {
+        return;
+    }
+    let mut seen = vec![false; width * height];
+    let positions: Vec<CellPos> = goal.positions().collect();
+    for pos in positions {
+        let state = goal.get(pos);
+        let mut at = pos;
+        while !seen[at.row * width + at.col] {
+            seen[at.row * width + at.col] = true;
+            goal.set_state(at, state);
+            at = image_of(symmetry, at, width, height);
+        }
+    }
+}
+
+/// Draw a random picture meeting `constraints`
+///
+/// Candidates are filled at the middle of the allowed fill range, repaired
+/// where that is cheap and otherwise rejected, giving up after `attempts`.
+pub fn random_goal(
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+    constraints: &Constraints,
+    attempts: usize,
+) -> Option<Grid> {
+    let density = (constraints.fill.0 + constraints.fill.1) / 2.0;
+    (0..attempts).find_map(|_| {
+        let image: Vec<Vec<bool>> = (0..height)
+            .map(|_| (0..width).map(|_| rng.chance(density)).collect())
+            .collect();
+        let mut goal = Grid::from_image(&image);
+        constraints.repair(&mut goal);
+        if let Some(symmetry) = constraints.symmetry {
+            symmetrize(&mut goal, symmetry);
+        }
+        Some(goal).filter(|goal| constraints.accepts(goal))
+    })
+}
+
+/// Techniques a generated puzzle must need and may not go beyond
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TechniqueTarget {
+    /// Needed for at least one cell
+    pub required: Technique,
+    /// The hardest technique any cell may need
+    pub hardest: Technique,
+}
+
+impl TechniqueTarget {
+    pub fn accepts(&self, report: &DifficultyReport) -> bool {
+        report.count(self.required) > 0
+            && Technique::ALL
+                .iter()
+                .filter(|&&technique| technique > self.hardest)
+                .all(|&technique| report.count(technique) == 0)
+    }
+}
+
+/// Derive a puzzle for a picture with as many line clues dropped as possible
+///
+/// Lines are tried in order and a line's clues stay dropped whenever the
+/// remaining clues still pin down the picture uniquely. Returns `None` if the
+/// full clue set is already ambiguous.
+pub fn minimal_clues(goal: Grid) -> Option<Puzzle> {
+    let mut puzzle = Puzzle::from_goal(goal);
+    if !solver::is_unique(&puzzle) {
+        return None;
+    }
+
+    let lines: Vec<_> = puzzle.lines().collect();
+    for line in lines {
+        let clues = puzzle.clues(line).map(<[usize]>::to_vec);
+        puzzle.remove_clues(line);
+        if !solver::is_unique(&puzzle) {
+            if let Some(clues) = clues {
+                puzzle.set_clues(line, clues);
+            }
+        }
+    }
+
+    Some(puzzle)
+}
+
+/// Generate a uniquely solvable puzzle whose solve meets `target`
+///
+/// Random pictures are tried until one fits, giving up after `attempts`.
+pub fn targeting(
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+    target: TechniqueTarget,
+    attempts: usize,
+) -> Option<Puzzle> {
+    let constraints = Constraints {
+        fill: (0.45, 0.65),
+        ..Constraints::default()
+    };
+    (0..attempts).find_map(|_| {
+        let puzzle = Puzzle::from_goal(random_goal(rng, width, height, &constraints, 1)?);
+        if !solver::is_unique(&puzzle) {
+            return None;
+        }
+        let report = DifficultyReport::analyse(&puzzle)?;
+        Some(puzzle).filter(|_| target.accepts(&report))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn drops_redundant_lines() {
+        let goal = from_art(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let puzzle = minimal_clues(goal.clone()).unwrap();
+
+        let dropped = puzzle
+            .lines()
+            .filter(|&l| puzzle.clues(l).is_none())
+            .count();
+        assert!(dropped > 0);
+        assert!(solver::is_unique(&puzzle));
+        assert_eq!(solver::solve(&puzzle), Some(goal));
+    }
+
+    #[test]
+    fn constrained_goals() {
+        let mut rng = Rng::new(3);
+        let constraints = Constraints {
+            fill: (0.3, 0.5),
+            max_clues: Some(3),
+            block_len: (1, 3),
+            symmetry: None,
+        };
+        for _ in 0..10 {
+            let goal = random_goal(&mut rng, 8, 8, &constraints, 1000).unwrap();
+            let puzzle = Puzzle::from_goal(goal);
+            for line in puzzle.lines() {
+                let clues = puzzle.clues(line).unwrap();
+                assert!(clues.len() <= 3);
+                assert!(clues.iter().all(|&len| len <= 3));
+            }
+        }
+
+        let impossible = Constraints {
+            block_len: (9, 9),
+            ..Constraints::default()
+        };
+        assert_eq!(random_goal(&mut rng, 8, 8, &impossible, 20), None);
+        assert!(!impossible.accepts(&from_art(&["#."])));
+        assert!(Constraints::default().accepts(&from_art(&["#."])));
+    }
+
+    #[test]
+    fn symmetric_goals() {
+        let mut rng = Rng::new(11);
+        for &symmetry in Symmetry::ALL.iter() {
+            let constraints = Constraints {
+                symmetry: Some(symmetry),
+                ..Constraints::default()
+            };
+            let goal = random_goal(&mut rng, 7, 7, &constraints, 1).unwrap();
+            assert!(Puzzle::from_goal(goal).symmetries().contains(&symmetry));
+        }
+
+        let rotated = Constraints {
+            symmetry: Some(Symmetry::Rotate90),
+            ..Constraints::default()
+        };
+        assert_eq!(random_goal(&mut rng, 6, 4, &rotated, 5), None);
+        let mirrored = Constraints {
+            symmetry: Some(Symmetry::MirrorH),
+            ..Constraints::default()
+        };
+        assert!(random_goal(&mut rng, 6, 4, &mirrored, 1).is_some());
+    }
+
+    #[test]
+    fn targets_techniques() {
+        let mut rng = Rng::new(7);
+        let target = TechniqueTarget {
+            required: Technique::EdgeLogic,
+            hardest: Technique::EdgeLogic,
+        };
+        let puzzle = targeting(&mut rng, 8, 8, target, 200).unwrap();
+        let report = DifficultyReport::analyse(&puzzle).unwrap();
+        assert!(report.count(Technique::EdgeLogic) > 0);
+        assert_eq!(report.count(Technique::CrossLine), 0);
+        assert_eq!(report.count(Technique::Probing), 0);
+        assert_eq!(report.count(Technique::Guess), 0);
+
+        let target = TechniqueTarget {
+            required: Technique::CrossLine,
+            hardest: Technique::Probing,
+        };
+        let puzzle = targeting(&mut rng, 8, 8, target, 1000).unwrap();
+        let report = DifficultyReport::analyse(&puzzle).unwrap();
+        assert!(report.count(Technique::CrossLine) > 0);
+        assert_eq!(report.count(Technique::Guess), 0);
+    }
+
+    #[test]
+    fn repairs_ambiguous_pictures() {
+        let goal = from_art(&["#.", ".#"]);
+        let (puzzle, flips) = make_unique(goal.clone(), 2).unwrap();
+        assert_eq!(flips.len(), 1);
+        assert!(solver::is_unique(&puzzle));
+        let changed = goal
+            .positions()
+            .filter(|&pos| puzzle.goal().unwrap().get(pos) != goal.get(pos))
+            .count();
+        assert_eq!(changed, 1);
+
+        let unique = from_art(&["##", ".#"]);
+        assert_eq!(make_unique(unique.clone(), 0).unwrap().1, vec![]);
+        assert_eq!(make_unique(from_art(&["#.", ".#"]), 0), None);
+    }
+
+    #[test]
+    fn ambiguous_goal() {
+        assert!(minimal_clues(from_art(&["#.", ".#"])).is_none());
+    }
+}
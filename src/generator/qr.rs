@@ -0,0 +1,132 @@
+//! Puzzles whose picture is a QR code, from a matrix of dark modules
+//!
+//! Any encoder will do: pass its modules row by row, `true` being dark. A quiet
+//! zone around the code is trimmed and can be added back as a margin.
+
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::Grid;
+use std::fmt;
+
+const FINDER: usize = 7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QrError {
+    /// Rows of differing lengths, or not as many rows as columns
+    NotSquare,
+    /// Not the size of any QR version, 21 to 177 modules a side
+    BadSize(usize),
+    /// A corner lacks its finder pattern
+    MissingFinder,
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QrError::NotSquare => write!(f, "QR matrix is not square"),
+            QrError::BadSize(size) => write!(f, "no QR version is {} modules wide", size),
+            QrError::MissingFinder => write!(f, "QR matrix lacks a finder pattern"),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+// Whether the 7 by 7 block at `top`, `left` is a ring around a 3 by 3 square
+fn is_finder(modules: &[Vec<bool>], top: usize, left: usize) -> bool {
+    (0..FINDER).all(|dy| {
+        (0..FINDER).all(|dx| {
+            let ring = dy.min(dx).min(FINDER - 1 - dy).min(FINDER - 1 - dx);
+            modules[top + dy][left + dx] == (ring != 1)
+        })
+    })
+}
+
+/// The QR code as a puzzle, surrounded by `margin` empty cells
+///
+/// Clues come from the code, so the puzzle may not be unique; large codes
+/// are slow to check, which is left to the caller.
+pub fn puzzle(modules: &[Vec<bool>], margin: usize) -> Result<Puzzle, QrError> {
+    let width = modules.first().map_or(0, Vec::len);
+    if modules.len() != width || modules.iter().any(|row| row.len() != width) {
+        return Err(QrError::NotSquare);
+    }
+
+    // Trim the quiet zone, which is the same on every side
+    let quiet = (0..width / 2)
+        .take_while(|&i| {
+            let far = width - 1 - i;
+            (i..=far)
+                .all(|j| !modules[i][j] && !modules[far][j] && !modules[j][i] && !modules[j][far])
+        })
+        .count();
+    let size = width - 2 * quiet;
+    if !(21..=177).contains(&size) || !(size - 17).is_multiple_of(4) {
+        return Err(QrError::BadSize(size));
+    }
+    let far = quiet + size - FINDER;
+    if ![(quiet, quiet), (quiet, far), (far, quiet)]
+        .iter()
+        .all(|&(top, left)| is_finder(modules, top, left))
+    {
+        return Err(QrError::MissingFinder);
+    }
+
+    let side = size + 2 * margin;
+    let mut image = vec![vec![false; side]; side];
+    for (row, cells) in modules[quiet..quiet + size].iter().enumerate() {
+        image[margin + row][margin..margin + size].copy_from_slice(&cells[quiet..quiet + size]);
+    }
+    Ok(Puzzle::from_goal(Grid::from_image(&image)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::CellPos;
+    use crate::spaces::node::CellState;
+
+    // A version 1 code's fixed patterns inside a quiet zone of `quiet` modules
+    fn version1(quiet: usize) -> Vec<Vec<bool>> {
+        let side = 21 + 2 * quiet;
+        let mut modules = vec![vec![false; side]; side];
+        for &(top, left) in &[(0, 0), (0, 14), (14, 0)] {
+            for dy in 0..FINDER {
+                for dx in 0..FINDER {
+                    let ring = dy.min(dx).min(FINDER - 1 - dy).min(FINDER - 1 - dx);
+                    modules[quiet + top + dy][quiet + left + dx] = ring != 1;
+                }
+            }
+        }
+        for i in (8..13).step_by(2) {
+            modules[quiet + 6][quiet + i] = true;
+            modules[quiet + i][quiet + 6] = true;
+        }
+        modules
+    }
+
+    #[test]
+    fn imports_code() {
+        let puzzle = puzzle(&version1(4), 1).unwrap();
+        let goal = puzzle.goal().unwrap();
+
+        assert_eq!((puzzle.width(), puzzle.height()), (23, 23));
+        assert_eq!(goal.get(CellPos::new(0, 0)), CellState::Empty);
+        assert_eq!(goal.get(CellPos::new(1, 1)), CellState::Filled);
+        assert_eq!(puzzle.rows()[1], Some(vec![7, 7]));
+        assert_eq!(puzzle.rows()[7], Some(vec![7, 1, 1, 1, 7]));
+    }
+
+    #[test]
+    fn rejects_non_codes() {
+        let mut modules = version1(0);
+        modules[20].pop();
+        assert_eq!(puzzle(&modules, 0), Err(QrError::NotSquare));
+
+        let small = vec![vec![true; 20]; 20];
+        assert_eq!(puzzle(&small, 0), Err(QrError::BadSize(20)));
+
+        let mut modules = version1(2);
+        modules[2 + 16][2 + 2] = false;
+        assert_eq!(puzzle(&modules, 0), Err(QrError::MissingFinder));
+    }
+}
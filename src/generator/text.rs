@@ -0,0 +1,227 @@
+//! Puzzles spelling out short words or initials in a built-in 3 by 5 font
+
+use super::make_unique;
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use std::fmt;
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+// Rows top to bottom, the high of three bits being the left column
+const FONT: &[(char, [u8; GLYPH_HEIGHT])] = &[
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b110, 0b001, 0b010, 0b100, 0b111]),
+    ('3', [0b110, 0b001, 0b010, 0b001, 0b110]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b110, 0b001, 0b110]),
+    ('6', [0b011, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b110]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+];
+
+/// How text is laid out on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextOptions {
+    /// Each pixel of the font becomes a `scale` by `scale` block
+    pub scale: usize,
+    /// Font pixels between letters and between lines of text
+    pub spacing: usize,
+    /// Empty cells around the text
+    pub margin: usize,
+    /// Cells that may be flipped to make an ambiguous picture unique
+    pub max_flips: usize,
+}
+
+impl Default for TextOptions {
+    fn default() -> TextOptions {
+        TextOptions {
+            scale: 1,
+            spacing: 1,
+            margin: 0,
+            max_flips: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextError {
+    /// The font has no glyph for the character
+    UnknownChar(char),
+    /// Nothing would be filled
+    Blank,
+    /// No repair within `max_flips` makes the puzzle unique
+    NotUnique,
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextError::UnknownChar(c) => write!(f, "no glyph for {:?}", c),
+            TextError::Blank => write!(f, "text draws nothing"),
+            TextError::NotUnique => write!(f, "text does not make a unique puzzle"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT]> {
+    let c = c.to_ascii_uppercase();
+    FONT.iter()
+        .find(|&&(glyph, _)| glyph == c)
+        .map(|(_, rows)| rows)
+}
+
+/// Draw the text, one line of letters per line of input, aligned left
+pub fn render(text: &str, options: &TextOptions) -> Result<Grid, TextError> {
+    let lines = text
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| glyph(c).ok_or(TextError::UnknownChar(c)))
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<_>>, _>>()?;
+
+    let span = |count: usize, size: usize| match count {
+        0 => 0,
+        _ => count * size + (count - 1) * options.spacing,
+    };
+    let columns = lines
+        .iter()
+        .map(|line| span(line.len(), GLYPH_WIDTH))
+        .max()
+        .unwrap_or(0);
+    let rows = span(lines.len(), GLYPH_HEIGHT);
+    let mut image = vec![vec![false; columns]; rows];
+    for (i, line) in lines.iter().enumerate() {
+        let top = i * (GLYPH_HEIGHT + options.spacing);
+        for (j, rows) in line.iter().enumerate() {
+            let left = j * (GLYPH_WIDTH + options.spacing);
+            for (dy, bits) in rows.iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    image[top + dy][left + dx] = bits >> (GLYPH_WIDTH - 1 - dx) & 1 == 1;
+                }
+            }
+        }
+    }
+    if !image.iter().flatten().any(|&filled| filled) {
+        return Err(TextError::Blank);
+    }
+
+    let (scale, margin) = (options.scale.max(1), options.margin);
+    let width = columns * scale + 2 * margin;
+    let height = rows * scale + 2 * margin;
+    let mut scaled = vec![vec![false; width]; height];
+    for (row, cells) in scaled
+        .iter_mut()
+        .enumerate()
+        .skip(margin)
+        .take(rows * scale)
+    {
+        for (col, cell) in cells
+            .iter_mut()
+            .enumerate()
+            .skip(margin)
+            .take(columns * scale)
+        {
+            *cell = image[(row - margin) / scale][(col - margin) / scale];
+        }
+    }
+    Ok(Grid::from_image(&scaled))
+}
+
+/// A uniquely solvable puzzle spelling out the text, with any cells flipped to get there
+pub fn puzzle(text: &str, options: &TextOptions) -> Result<(Puzzle, Vec<CellPos>), TextError> {
+    let goal = render(text, options)?;
+    make_unique(goal, options.max_flips).ok_or(TextError::NotUnique)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver;
+    use crate::spaces::node::CellState;
+
+    #[test]
+    fn font_layout() {
+        let grid = render("Hi\nl", &TextOptions::default()).unwrap();
+        assert_eq!((grid.width(), grid.height()), (7, 11));
+        assert_eq!(
+            crate::render::text(&grid)
+                .lines()
+                .take(5)
+                .collect::<Vec<_>>(),
+            vec!["#.#.###", "#.#..#.", "###..#.", "#.#..#.", "#.#.###"]
+        );
+
+        let big = TextOptions {
+            scale: 2,
+            margin: 1,
+            ..TextOptions::default()
+        };
+        let grid = render("L", &big).unwrap();
+        assert_eq!((grid.width(), grid.height()), (8, 12));
+        assert_eq!(grid.get(CellPos::new(1, 1)), CellState::Filled);
+        assert_eq!(grid.get(CellPos::new(0, 1)), CellState::Empty);
+    }
+
+    #[test]
+    fn errors() {
+        let options = TextOptions::default();
+        assert_eq!(render("a~", &options), Err(TextError::UnknownChar('~')));
+        assert_eq!(render("  ", &options), Err(TextError::Blank));
+        assert_eq!(render("", &options), Err(TextError::Blank));
+    }
+
+    #[test]
+    fn puzzles_are_unique() {
+        let options = TextOptions::default();
+        let (puzzle, flips) = puzzle("LT", &options).unwrap();
+        assert!(flips.is_empty());
+        assert!(solver::is_unique(&puzzle));
+
+        assert_eq!(super::puzzle("S", &options), Err(TextError::NotUnique));
+        let repair = TextOptions {
+            max_flips: 2,
+            ..options
+        };
+        let (puzzle, flips) = super::puzzle("S", &repair).unwrap();
+        assert!(!flips.is_empty());
+        assert!(solver::is_unique(&puzzle));
+    }
+}
@@ -1,4 +1,21 @@
+mod bytes;
+pub mod difficulty;
+pub mod editor;
+pub mod explain;
+pub mod flat;
+pub mod formats;
+pub mod generator;
+pub mod oracle;
+pub mod puzzle;
+pub mod render;
+pub mod rng;
+pub mod session;
+pub mod solver;
 pub mod spaces;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 #[cfg(test)]
 mod tests {
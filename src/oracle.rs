@@ -0,0 +1,271 @@
+//! Exhaustive reference solver for small boards
+//!
+//! Enumerates every arrangement of each row's clues and keeps the combinations
+//! whose columns match. Nothing here shares code with the line solver, so its
+//! answers can be trusted as ground truth: it is slow, but simple enough to
+//! check by reading, and serves for differential testing and for checking a
+//! result from the main solver by hand.
+
+use crate::puzzle::{derive_clues, Puzzle};
+use crate::spaces::grid::Grid;
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::fmt;
+
+/// Largest boards the oracle agrees to enumerate
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Limits {
+    /// Longest clued row or column
+    pub line: usize,
+    /// Widest unclued row, each of which tries every pattern
+    pub unclued: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            line: 25,
+            unclued: 16,
+        }
+    }
+}
+
+/// A line longer than the oracle's limits allow
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TooLarge {
+    pub line: LineId,
+    pub len: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} has {} cells, more than the oracle's limit of {}",
+            self.line, self.len, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Every way to lay out `clues` in a line of `length` cells
+pub fn arrangements(clues: &[usize], length: usize) -> Vec<Vec<bool>> {
+    let mut found = Vec::new();
+    let mut line = vec![false; length];
+    place(clues, &mut line, 0, &mut found);
+    found
+}
+
+fn place(clues: &[usize], line: &mut Vec<bool>, from: usize, found: &mut Vec<Vec<bool>>) {
+    let (&clue, rest) = match clues.split_first() {
+        Some(split) => split,
+        None => {
+            found.push(line.clone());
+            return;
+        }
+    };
+    // Room the remaining clues need after this one
+    let needed: usize = rest.iter().map(|c| c + 1).sum();
+    let mut start = from;
+    while start + clue + needed <= line.len() {
+        line[start..start + clue].iter_mut().for_each(|c| *c = true);
+        place(rest, line, start + clue + 1, found);
+        line[start..start + clue]
+            .iter_mut()
+            .for_each(|c| *c = false);
+        start += 1;
+    }
+}
+
+// Every pattern of a line, for lines without clues
+fn all_patterns(length: usize) -> Vec<Vec<bool>> {
+    (0..1u64 << length)
+        .map(|bits| (0..length).map(|i| bits >> i & 1 == 1).collect())
+        .collect()
+}
+
+// Whether the first `rows` cells of a column could still grow into its clues
+fn column_prefix_ok(clues: &[usize], cells: &[bool], complete: bool) -> bool {
+    let states: Vec<CellState> = cells
+        .iter()
+        .map(|&f| {
+            if f {
+                CellState::Filled
+            } else {
+                CellState::Empty
+            }
+        })
+        .collect();
+    let runs = derive_clues(&states);
+    if complete {
+        return runs == clues;
+    }
+    if runs.len() > clues.len() {
+        return false;
+    }
+    let open_run = cells.last() == Some(&true);
+    let closed = if open_run { runs.len() - 1 } else { runs.len() };
+    runs[..closed] == clues[..closed] && (!open_run || runs[closed] <= clues[closed])
+}
+
+/// Find up to `limit` solutions by enumeration
+///
+/// Panics if the board is beyond the default `Limits`, where enumeration stops
+/// being practical.
+pub fn solutions(puzzle: &Puzzle, limit: usize) -> Vec<Grid> {
+    match solutions_within(puzzle, limit, Limits::default()) {
+        Ok(found) => found,
+        Err(err) => panic!("Board too large for the oracle: {}", err),
+    }
+}
+
+/// Find up to `limit` solutions by enumeration, refusing boards beyond `limits`
+pub fn solutions_within(
+    puzzle: &Puzzle,
+    limit: usize,
+    limits: Limits,
+) -> Result<Vec<Grid>, TooLarge> {
+    let (width, height) = (puzzle.width(), puzzle.height());
+    for line in puzzle.lines() {
+        let len = puzzle.givens().line_len(line);
+        let limit = match (line, puzzle.clues(line)) {
+            (_, Some(_)) => limits.line,
+            (LineId::Row(_), None) => limits.unclued,
+            (LineId::Column(_), None) => continue,
+        };
+        if len > limit {
+            return Err(TooLarge { line, len, limit });
+        }
+    }
+
+    let givens = puzzle.givens();
+    let options: Vec<Vec<Vec<bool>>> = puzzle
+        .rows()
+        .iter()
+        .enumerate()
+        .map(|(row, clues)| {
+            let options = match clues {
+                Some(clues) => arrangements(clues, width),
+                None => all_patterns(width),
+            };
+            // Drop arrangements contradicting a given cell
+            let given = givens.line_states(LineId::Row(row));
+            options
+                .into_iter()
+                .filter(|option| {
+                    option.iter().zip(&given).all(|(&filled, &state)| {
+                        state == CellState::Unknown || filled == (state == CellState::Filled)
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut found = Vec::new();
+    let mut rows = Vec::with_capacity(height);
+    search(puzzle, &options, &mut rows, limit, &mut found);
+    Ok(found)
+}
+
+fn search(
+    puzzle: &Puzzle,
+    options: &[Vec<Vec<bool>>],
+    rows: &mut Vec<Vec<bool>>,
+    limit: usize,
+    found: &mut Vec<Grid>,
+) {
+    if found.len() >= limit {
+        return;
+    }
+    let complete = rows.len() == options.len();
+    for (col, clues) in puzzle.cols().iter().enumerate() {
+        if let Some(clues) = clues {
+            let cells: Vec<bool> = rows.iter().map(|row| row[col]).collect();
+            if !column_prefix_ok(clues, &cells, complete) {
+                return;
+            }
+        }
+    }
+    if complete {
+        found.push(Grid::from_image(rows));
+        return;
+    }
+
+    for option in &options[rows.len()] {
+        rows.push(option.clone());
+        search(puzzle, options, rows, limit, found);
+        rows.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+    use crate::strategies;
+
+    #[test]
+    fn arrangement_counts() {
+        assert_eq!(arrangements(&[1], 3).len(), 3);
+        assert_eq!(arrangements(&[2, 1], 5).len(), 3);
+        assert_eq!(arrangements(&[], 4), vec![vec![false; 4]]);
+        assert!(arrangements(&[3, 3], 6).is_empty());
+    }
+
+    #[test]
+    fn counts_permutation_puzzle() {
+        let puzzle = Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4]);
+        assert_eq!(solutions(&puzzle, 100).len(), 24);
+    }
+
+    #[test]
+    fn configurable_limits() {
+        let puzzle = Puzzle::from_lines(vec![None, Some(vec![3])], vec![Some(vec![1]); 3]);
+        let small = Limits {
+            line: 3,
+            unclued: 2,
+        };
+
+        assert_eq!(
+            solutions_within(&puzzle, 10, small),
+            Err(TooLarge {
+                line: LineId::Row(0),
+                len: 3,
+                limit: 2
+            })
+        );
+        let found = solutions_within(&puzzle, 10, Limits::default()).unwrap();
+        assert_eq!(
+            found,
+            vec![Grid::from_image(&[vec![false; 3], vec![true; 3]])]
+        );
+
+        let wide = Puzzle::new(vec![vec![]], vec![vec![]; 4]);
+        let err = solutions_within(
+            &wide,
+            1,
+            Limits {
+                line: 3,
+                unclued: 3,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.line, LineId::Row(0));
+    }
+
+    #[test]
+    fn agrees_with_solver() {
+        for mut rng in strategies::seeds(4, 60) {
+            let puzzle = strategies::puzzle(&mut rng, 6, 6);
+            let key = |grid: &Grid| format!("{:?}", grid);
+
+            let mut expected = solutions(&puzzle, 50);
+            let mut found = Solver::new(&puzzle).solutions(50);
+            expected.sort_by_key(key);
+            found.sort_by_key(key);
+            assert_eq!(found, expected);
+        }
+    }
+}
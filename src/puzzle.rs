@@ -0,0 +1,758 @@
+mod layout;
+
+use crate::solver::technique::Technique;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::hint::{HSoln, Hint};
+use crate::spaces::node::{CellState, Node};
+use crate::spaces::{slack, Line, LineId, Symmetry};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Clue lists for every row and column, `None` marking a line without clues
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    rows: Vec<Option<Vec<usize>>>,
+    cols: Vec<Option<Vec<usize>>>,
+    goal: Option<Grid>,
+    // Cells known from the start, `Unknown` everywhere else
+    givens: Grid,
+}
+
+/// Lengths of the filled runs in a line, in order
+pub fn derive_clues(cells: &[CellState]) -> Vec<usize> {
+    let mut clues = Vec::new();
+    let mut run = 0;
+    for &cell in cells {
+        if cell == CellState::Filled {
+            run += 1;
+        } else if run > 0 {
+            clues.push(run);
+            run = 0;
+        }
+    }
+    if run > 0 {
+        clues.push(run);
+    }
+    clues
+}
+
+/// Static metrics of a puzzle's clues, cheap enough for catalogue metadata
+#[derive(Clone, Debug, PartialEq)]
+pub struct PuzzleStats {
+    /// Share of filled cells from the clue sums, `None` unless every row or
+    /// every column is clued
+    pub fill_ratio: Option<f64>,
+    /// How many clued lines have each number of clues
+    pub clue_counts: BTreeMap<usize, usize>,
+    pub longest_clue: usize,
+    /// Mean over clued lines of the cells left over after blocks and gaps
+    pub average_slack: f64,
+    /// Geometric mean over clued lines of the ways to place their blocks
+    pub branching_factor: f64,
+}
+
+/// Estimated bytes a solver needs for a puzzle, before any search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryFootprint {
+    /// The clue lists, givens and goal held by the puzzle itself
+    pub puzzle: usize,
+    /// The solver's board
+    pub cells: usize,
+    /// Each clue's window of feasible positions
+    pub ranges: usize,
+    /// Line solving buffers and the propagation queue
+    pub caches: usize,
+    /// Copy of the ranges kept for every open guess, plus its journal of
+    /// the cells solved under it, at worst the whole board
+    pub per_guess: usize,
+}
+
+impl MemoryFootprint {
+    /// Total with `depth` guesses open
+    pub fn total(&self, depth: usize) -> usize {
+        self.puzzle + self.cells + self.ranges + self.caches + self.per_guess * depth
+    }
+}
+
+/// A problem with a line's clues that makes the puzzle unsolvable or malformed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClueError {
+    /// The blocks and the gaps between them need more cells than the line has
+    TooLong {
+        line: LineId,
+        needed: usize,
+        length: usize,
+    },
+    /// The puzzle's goal does not satisfy the line
+    GoalMismatch { line: LineId },
+    /// A given cell disagrees with the goal
+    GivenMismatch { pos: CellPos },
+    /// The row clues fill a different number of cells than the column clues
+    SumMismatch { rows: usize, cols: usize },
+}
+
+impl fmt::Display for ClueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClueError::TooLong {
+                line,
+                needed,
+                length,
+            } => write!(
+                f,
+                "clues of {:?} need {} cells but it has {}",
+                line, needed, length
+            ),
+            ClueError::GoalMismatch { line } => write!(f, "goal does not match {:?}", line),
+            ClueError::GivenMismatch { pos } => {
+                write!(f, "given cell {:?} does not match the goal", pos)
+            }
+            ClueError::SumMismatch { rows, cols } => write!(
+                f,
+                "row clues fill {} cells but column clues fill {}, {} apart",
+                rows,
+                cols,
+                rows.abs_diff(*cols)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ClueError {}
+
+// A literal 0 clue means the same as no clues: an empty line
+fn without_zeros(mut clues: Vec<usize>) -> Vec<usize> {
+    clues.retain(|&clue| clue > 0);
+    clues
+}
+
+// Size then every line's clue count and clues, `u64::MAX` marking an unclued line
+fn clue_key(puzzle: &Puzzle) -> Vec<u64> {
+    let mut key = vec![puzzle.width() as u64, puzzle.height() as u64];
+    for clues in puzzle.rows.iter().chain(&puzzle.cols) {
+        match clues {
+            Some(clues) => {
+                key.push(clues.len() as u64);
+                key.extend(clues.iter().map(|&clue| clue as u64));
+            }
+            None => key.push(u64::MAX),
+        }
+    }
+    key
+}
+
+fn reversed(clues: &Option<Vec<usize>>) -> Option<Vec<usize>> {
+    clues
+        .as_ref()
+        .map(|clues| clues.iter().rev().cloned().collect())
+}
+
+impl Puzzle {
+    pub fn new(rows: Vec<Vec<usize>>, cols: Vec<Vec<usize>>) -> Puzzle {
+        Puzzle::from_lines(
+            rows.into_iter().map(Some).collect(),
+            cols.into_iter().map(Some).collect(),
+        )
+    }
+
+    /// A puzzle with no clues at all, to be filled in line by line
+    pub fn unclued(width: usize, height: usize) -> Puzzle {
+        Puzzle::from_lines(vec![None; height], vec![None; width])
+    }
+
+    /// Build from clue lists where `None` marks an unclued line
+    ///
+    /// Zero clues are dropped, so `[0]` and `[]` both make an empty line.
+    pub fn from_lines(rows: Vec<Option<Vec<usize>>>, cols: Vec<Option<Vec<usize>>>) -> Puzzle {
+        let givens = Grid::new(cols.len(), rows.len());
+        let normalized = |lines: Vec<Option<Vec<usize>>>| -> Vec<Option<Vec<usize>>> {
+            lines
+                .into_iter()
+                .map(|clues| clues.map(without_zeros))
+                .collect()
+        };
+        Puzzle {
+            rows: normalized(rows),
+            cols: normalized(cols),
+            goal: None,
+            givens,
+        }
+    }
+
+    /// Derive the clues of a solved picture, keeping the picture as the goal
+    pub fn from_goal(goal: Grid) -> Puzzle {
+        let rows = (0..goal.height())
+            .map(|row| Some(derive_clues(&goal.line_states(LineId::Row(row)))))
+            .collect();
+        let cols = (0..goal.width())
+            .map(|col| Some(derive_clues(&goal.line_states(LineId::Column(col)))))
+            .collect();
+
+        Puzzle {
+            rows,
+            cols,
+            givens: Grid::new(goal.width(), goal.height()),
+            goal: Some(goal),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn rows(&self) -> &[Option<Vec<usize>>] {
+        &self.rows
+    }
+
+    pub fn cols(&self) -> &[Option<Vec<usize>>] {
+        &self.cols
+    }
+
+    pub fn goal(&self) -> Option<&Grid> {
+        self.goal.as_ref()
+    }
+
+    /// Attach the intended picture, which must match the puzzle's size
+    pub fn set_goal(&mut self, goal: Option<Grid>) {
+        if let Some(goal) = &goal {
+            assert!(goal.width() == self.width() && goal.height() == self.height());
+        }
+        self.goal = goal;
+    }
+
+    /// Cells whose state is given up front, `Unknown` where nothing is given
+    pub fn givens(&self) -> &Grid {
+        &self.givens
+    }
+
+    pub fn has_givens(&self) -> bool {
+        self.givens.unknown_count() < self.width() * self.height()
+    }
+
+    /// Give a cell's state up front, `Unknown` taking the given away
+    pub fn set_given(&mut self, pos: CellPos, state: CellState) {
+        self.givens.set_state(pos, state);
+    }
+
+    pub fn clues(&self, line: LineId) -> Option<&[usize]> {
+        match line {
+            LineId::Row(row) => self.rows[row].as_deref(),
+            LineId::Column(col) => self.cols[col].as_deref(),
+        }
+    }
+
+    pub fn is_clued(&self, line: LineId) -> bool {
+        self.clues(line).is_some()
+    }
+
+    /// Lines whose contents are left unconstrained
+    pub fn unclued_lines(&self) -> impl Iterator<Item = LineId> + '_ {
+        self.lines().filter(move |&line| !self.is_clued(line))
+    }
+
+    /// Drop the clues of a line, leaving its contents unconstrained
+    pub fn remove_clues(&mut self, line: LineId) {
+        match line {
+            LineId::Row(row) => self.rows[row] = None,
+            LineId::Column(col) => self.cols[col] = None,
+        }
+    }
+
+    /// Replace a line's clues, dropping zero clues as `from_lines` does
+    pub fn set_clues(&mut self, line: LineId, clues: Vec<usize>) {
+        let clues = without_zeros(clues);
+        match line {
+            LineId::Row(row) => self.rows[row] = Some(clues),
+            LineId::Column(col) => self.cols[col] = Some(clues),
+        }
+    }
+
+    pub fn line_len(&self, line: LineId) -> usize {
+        match line {
+            LineId::Row(_) => self.width(),
+            LineId::Column(_) => self.height(),
+        }
+    }
+
+    /// Problems with a single line's clues
+    pub fn line_errors(&self, line: LineId) -> Vec<ClueError> {
+        let clues = match self.clues(line) {
+            Some(clues) => clues,
+            None => return Vec::new(),
+        };
+        let mut errors = Vec::new();
+
+        let needed = slack::min_length(clues);
+        let length = self.line_len(line);
+        if needed > length {
+            errors.push(ClueError::TooLong {
+                line,
+                needed,
+                length,
+            });
+        }
+        if let Some(goal) = &self.goal {
+            if derive_clues(&goal.line_states(line)) != clues {
+                errors.push(ClueError::GoalMismatch { line });
+            }
+        }
+        errors
+    }
+
+    /// Check every line, collecting all problems found
+    ///
+    /// When every line is clued the row and column clues must also add up
+    /// to the same number of filled cells.
+    pub fn validate(&self) -> Result<(), Vec<ClueError>> {
+        let mut errors: Vec<ClueError> = self
+            .lines()
+            .flat_map(|line| self.line_errors(line))
+            .collect();
+        let total = |lines: &[Option<Vec<usize>>]| -> Option<usize> {
+            lines
+                .iter()
+                .map(|clues| clues.as_ref().map(|clues| clues.iter().sum::<usize>()))
+                .sum()
+        };
+        if let (Some(rows), Some(cols)) = (total(&self.rows), total(&self.cols)) {
+            if rows != cols {
+                errors.push(ClueError::SumMismatch { rows, cols });
+            }
+        }
+        if let Some(goal) = &self.goal {
+            errors.extend(
+                self.givens
+                    .positions()
+                    .filter(|&pos| {
+                        let given = self.givens.get(pos);
+                        given != CellState::Unknown && given != goal.get(pos)
+                    })
+                    .map(|pos| ClueError::GivenMismatch { pos }),
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = LineId> {
+        (0..self.height())
+            .map(LineId::Row)
+            .chain((0..self.width()).map(LineId::Column))
+    }
+
+    /// Swap rows and columns
+    pub fn transposed(&self) -> Puzzle {
+        Puzzle {
+            rows: self.cols.clone(),
+            cols: self.rows.clone(),
+            goal: self.goal.as_ref().map(Grid::transposed),
+            givens: self.givens.transposed(),
+        }
+    }
+
+    /// Mirror left to right, reversing every row's clues
+    pub fn mirrored_h(&self) -> Puzzle {
+        Puzzle {
+            rows: self.rows.iter().map(reversed).collect(),
+            cols: self.cols.iter().rev().cloned().collect(),
+            goal: self.goal.as_ref().map(Grid::mirrored_h),
+            givens: self.givens.mirrored_h(),
+        }
+    }
+
+    /// Mirror top to bottom, reversing every column's clues
+    pub fn mirrored_v(&self) -> Puzzle {
+        Puzzle {
+            rows: self.rows.iter().rev().cloned().collect(),
+            cols: self.cols.iter().map(reversed).collect(),
+            goal: self.goal.as_ref().map(Grid::mirrored_v),
+            givens: self.givens.mirrored_v(),
+        }
+    }
+
+    /// Rotate a quarter turn clockwise
+    pub fn rotated90(&self) -> Puzzle {
+        self.transposed().mirrored_h()
+    }
+
+    pub fn transformed(&self, symmetry: Symmetry) -> Puzzle {
+        match symmetry {
+            Symmetry::MirrorH => self.mirrored_h(),
+            Symmetry::MirrorV => self.mirrored_v(),
+            Symmetry::Rotate90 => self.rotated90(),
+            Symmetry::Rotate180 => self.mirrored_h().mirrored_v(),
+            Symmetry::Rotate270 => self.transposed().mirrored_v(),
+            Symmetry::Transpose => self.transposed(),
+            Symmetry::AntiTranspose => self.rotated90().mirrored_v(),
+        }
+    }
+
+    /// Symmetries leaving every clue and given cell unchanged, the goal aside
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::ALL
+            .iter()
+            .cloned()
+            .filter(|&symmetry| {
+                let other = self.transformed(symmetry);
+                other.rows == self.rows && other.cols == self.cols && other.givens == self.givens
+            })
+            .collect()
+    }
+
+    /// Hash of the clues, shared by every mirrored, rotated and transposed copy
+    ///
+    /// Goal and givens are ignored. The hash is FNV-1a over a fixed encoding, so
+    /// it is stable between runs and can be stored to find duplicates later.
+    pub fn canonical_hash(&self) -> u64 {
+        let key = Symmetry::ALL
+            .iter()
+            .map(|&symmetry| clue_key(&self.transformed(symmetry)))
+            .chain(std::iter::once(clue_key(self)))
+            .min()
+            .unwrap_or_default();
+        key.iter()
+            .flat_map(|word| word.to_le_bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    pub fn stats(&self) -> PuzzleStats {
+        let cells = (self.width() * self.height()) as f64;
+        let sum = |lines: &[Option<Vec<usize>>]| -> Option<usize> {
+            lines
+                .iter()
+                .map(|clues| clues.as_ref().map(|clues| clues.iter().sum::<usize>()))
+                .sum()
+        };
+        let fill_ratio = sum(&self.rows)
+            .or_else(|| sum(&self.cols))
+            .filter(|_| cells > 0.0)
+            .map(|filled| filled as f64 / cells);
+
+        let mut clue_counts = BTreeMap::new();
+        let mut longest_clue = 0;
+        let mut slack = 0;
+        let mut log_ways = 0.0;
+        let mut clued = 0;
+        for line in self.lines() {
+            let clues = match self.clues(line) {
+                Some(clues) => clues,
+                None => continue,
+            };
+            clued += 1;
+            *clue_counts.entry(clues.len()).or_insert(0) += 1;
+            longest_clue = longest_clue.max(clues.iter().cloned().max().unwrap_or(0));
+            let free = self.line_len(line).saturating_sub(slack::min_length(clues));
+            slack += free;
+            // ln of (free + k choose k), the placements of k blocks
+            log_ways += (1..=clues.len())
+                .map(|i| ((free + i) as f64 / i as f64).ln())
+                .sum::<f64>();
+        }
+        let mean = |total: f64| {
+            if clued == 0 {
+                0.0
+            } else {
+                total / clued as f64
+            }
+        };
+
+        PuzzleStats {
+            fill_ratio,
+            clue_counts,
+            longest_clue,
+            average_slack: mean(slack as f64),
+            branching_factor: mean(log_ways).exp(),
+        }
+    }
+
+    /// Estimate the memory solving this puzzle takes, in bytes
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        use std::mem::size_of;
+        let cells = self.width() * self.height();
+        let grid = size_of::<Grid>() + cells * size_of::<Node>();
+        let lines = self.rows.len() + self.cols.len();
+        let clue_lists = self.rows.iter().chain(&self.cols).flatten();
+
+        let mut puzzle = size_of::<Puzzle>() + lines * size_of::<Option<Vec<usize>>>() + grid;
+        puzzle += clue_lists.clone().map(Vec::len).sum::<usize>() * size_of::<usize>();
+        if self.goal.is_some() {
+            puzzle += grid;
+        }
+
+        // Every clue starts with one window, which later solves may split
+        let clues = clue_lists.clone().map(Vec::len).sum::<usize>();
+        let ranges =
+            lines * size_of::<Option<Line>>() + clues * (size_of::<Hint>() + size_of::<HSoln>());
+
+        // Line scratch is sized by the longest line and the most clues in one
+        let n = self.width().max(self.height());
+        let k = clue_lists.map(Vec::len).max().unwrap_or(0);
+        let tables = 2 * (k + 1) * (n + 1);
+        let caches = tables
+            + k * size_of::<usize>() * (1 + n)
+            + n * (size_of::<CellState>() + size_of::<Option<Technique>>())
+            + n * size_of::<(CellPos, Technique)>()
+            + 3 * n.div_ceil(64) * size_of::<u64>()
+            + lines * (size_of::<LineId>() + size_of::<bool>());
+
+        // A guess saves the ranges and its cell, and journals every cell
+        // solved under it so backtracking can unsolve them
+        let branch = ranges + size_of::<CellPos>() + 2 * size_of::<usize>();
+        let journal = cells * size_of::<CellPos>();
+        MemoryFootprint {
+            puzzle,
+            cells: grid,
+            ranges,
+            caches,
+            per_guess: branch + size_of::<(CellPos, bool)>() + journal,
+        }
+    }
+
+    /// Check a solved grid against every clued line and given cell
+    pub fn is_solved_by(&self, grid: &Grid) -> bool {
+        grid.width() == self.width()
+            && grid.height() == self.height()
+            && grid.is_complete()
+            && self.givens.positions().all(|pos| {
+                let given = self.givens.get(pos);
+                given == CellState::Unknown || given == grid.get(pos)
+            })
+            && self.lines().all(|line| match self.clues(line) {
+                Some(clues) => derive_clues(&grid.line_states(line)) == clues,
+                None => true,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn static_stats() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["##.", "..#", "#.#"]));
+        let stats = puzzle.stats();
+
+        assert_eq!(stats.fill_ratio, Some(5.0 / 9.0));
+        assert_eq!(stats.clue_counts.get(&1), Some(&4));
+        assert_eq!(stats.clue_counts.get(&2), Some(&2));
+        assert_eq!(stats.longest_clue, 2);
+        // Slacks: rows 1, 2, 0 and columns 0, 2, 1
+        assert_eq!(stats.average_slack, 1.0);
+        // Placements: rows 2, 3, 1 and columns 1, 3, 2
+        assert!((stats.branching_factor - 36f64.powf(1.0 / 6.0)).abs() < 1e-9);
+
+        puzzle.remove_clues(LineId::Row(0));
+        assert_eq!(puzzle.stats().fill_ratio, Some(5.0 / 9.0));
+        puzzle.remove_clues(LineId::Column(0));
+        assert_eq!(puzzle.stats().fill_ratio, None);
+        assert_eq!(Puzzle::unclued(2, 2).stats().branching_factor, 1.0);
+    }
+
+    #[test]
+    fn derive_line_clues() {
+        use CellState::*;
+        assert_eq!(
+            derive_clues(&[Filled, Filled, Empty, Filled, Empty, Empty, Filled]),
+            vec![2, 1, 1]
+        );
+        assert!(derive_clues(&[Empty, Empty]).is_empty());
+    }
+
+    #[test]
+    fn clues_from_goal() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.", "..#", "#.#"]));
+
+        assert_eq!(puzzle.rows()[0], Some(vec![2]));
+        assert_eq!(puzzle.rows()[2], Some(vec![1, 1]));
+        assert_eq!(puzzle.cols()[0], Some(vec![1, 1]));
+        assert_eq!(puzzle.cols()[2], Some(vec![2]));
+        assert!(puzzle.is_solved_by(puzzle.goal().unwrap()));
+    }
+
+    #[test]
+    fn transforms_keep_clues_consistent() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.#", "#...", "..##"]));
+
+        for transformed in &[
+            puzzle.transposed(),
+            puzzle.mirrored_h(),
+            puzzle.mirrored_v(),
+            puzzle.rotated90(),
+        ] {
+            let goal = transformed.goal().unwrap();
+            assert_eq!(transformed, &Puzzle::from_goal(goal.clone()));
+        }
+    }
+
+    #[test]
+    fn transforms_without_goal() {
+        let puzzle = Puzzle::new(
+            vec![vec![2, 1], vec![1]],
+            vec![vec![2], vec![1], vec![], vec![1]],
+        );
+
+        assert_eq!(puzzle.mirrored_h().rows()[0], Some(vec![1, 2]));
+        assert_eq!(puzzle.rotated90().width(), 2);
+        assert_eq!(puzzle.rotated90().cols()[1], Some(vec![2, 1]));
+        assert_eq!(puzzle.transposed().transposed(), puzzle);
+    }
+
+    #[test]
+    fn transforms_match_grid() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.#", "#...", "..##"]));
+        for &symmetry in Symmetry::ALL.iter() {
+            let goal = puzzle.goal().unwrap().transformed(symmetry);
+            assert_eq!(goal.width() != 4, symmetry.swaps_axes());
+            assert_eq!(puzzle.transformed(symmetry), Puzzle::from_goal(goal));
+        }
+    }
+
+    #[test]
+    fn canonical_hash_ignores_orientation() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.#", "#...", "..##"]));
+        let hash = puzzle.canonical_hash();
+        for &symmetry in Symmetry::ALL.iter() {
+            assert_eq!(puzzle.transformed(symmetry).canonical_hash(), hash);
+        }
+
+        let other = Puzzle::from_goal(from_art(&["##.#", "#...", ".##."]));
+        assert_ne!(other.canonical_hash(), hash);
+        let mut unclued = puzzle.clone();
+        unclued.remove_clues(LineId::Row(1));
+        assert_ne!(unclued.canonical_hash(), hash);
+        let mut bare = puzzle.clone();
+        bare.set_goal(None);
+        assert_eq!(bare.canonical_hash(), hash);
+    }
+
+    #[test]
+    fn detects_symmetries() {
+        let plus = Puzzle::from_goal(from_art(&[".#.", "###", ".#."]));
+        assert_eq!(plus.symmetries(), Symmetry::ALL.to_vec());
+
+        // Clues alone cannot tell the two diagonals apart
+        let diagonal = Puzzle::from_goal(from_art(&["#..", ".#.", "..#"]));
+        assert_eq!(diagonal.symmetries().len(), 7);
+
+        let ell = Puzzle::from_goal(from_art(&["#..", "#..", "###"]));
+        assert_eq!(ell.symmetries(), vec![Symmetry::AntiTranspose]);
+        let bar = Puzzle::from_goal(from_art(&["###", "..."]));
+        assert_eq!(bar.symmetries(), vec![Symmetry::MirrorH]);
+    }
+
+    #[test]
+    fn validation() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.#", "..."]));
+        assert_eq!(puzzle.validate(), Ok(()));
+
+        puzzle.set_clues(LineId::Row(0), vec![2, 0, 2]);
+        puzzle.set_clues(LineId::Column(2), vec![]);
+        assert_eq!(puzzle.clues(LineId::Row(0)), Some(&[2, 2][..]));
+        let errors = puzzle.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ClueError::TooLong {
+                    line: LineId::Row(0),
+                    needed: 5,
+                    length: 3
+                },
+                ClueError::GoalMismatch {
+                    line: LineId::Row(0)
+                },
+                ClueError::GoalMismatch {
+                    line: LineId::Column(2)
+                },
+                ClueError::SumMismatch { rows: 4, cols: 1 },
+            ]
+        );
+        assert_eq!(
+            errors[3].to_string(),
+            "row clues fill 4 cells but column clues fill 1, 3 apart"
+        );
+
+        // Unclued lines could hold any number of cells
+        puzzle.remove_clues(LineId::Row(1));
+        assert!(!puzzle
+            .validate()
+            .unwrap_err()
+            .iter()
+            .any(|error| matches!(error, ClueError::SumMismatch { .. })));
+    }
+
+    #[test]
+    fn unclued_puzzle() {
+        let mut puzzle = Puzzle::unclued(3, 2);
+        assert_eq!(puzzle.unclued_lines().count(), 5);
+        assert!(puzzle.is_solved_by(&from_art(&["#.#", "..#"])));
+
+        puzzle.set_clues(LineId::Column(1), vec![2]);
+        assert!(puzzle.is_clued(LineId::Column(1)));
+        assert_eq!(puzzle.unclued_lines().count(), 4);
+        assert!(!puzzle.is_solved_by(&from_art(&["#.#", "..#"])));
+    }
+
+    #[test]
+    fn givens() {
+        let mut puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        assert!(!puzzle.has_givens());
+        puzzle.set_given(CellPos::new(0, 1), CellState::Filled);
+
+        assert!(puzzle.has_givens());
+        assert!(puzzle.is_solved_by(&from_art(&[".#", "#."])));
+        assert!(!puzzle.is_solved_by(&from_art(&["#.", ".#"])));
+        assert_eq!(puzzle.symmetries(), vec![Symmetry::AntiTranspose]);
+        assert_eq!(
+            puzzle.rotated90().givens().get(CellPos::new(1, 1)),
+            CellState::Filled
+        );
+
+        puzzle.set_goal(Some(from_art(&["#.", ".#"])));
+        assert_eq!(
+            puzzle.validate(),
+            Err(vec![ClueError::GivenMismatch {
+                pos: CellPos::new(0, 1)
+            }])
+        );
+    }
+
+    #[test]
+    fn memory_grows_with_size() {
+        let small = Puzzle::from_goal(from_art(&["##.", "..#", "#.#"])).memory_footprint();
+        let large = Puzzle::new(vec![vec![1, 1]; 20], vec![vec![1, 1]; 20]).memory_footprint();
+
+        assert!(small.cells < large.cells);
+        assert!(small.ranges < large.ranges);
+        assert!(small.caches < large.caches);
+        assert!(large.total(0) < large.total(5));
+        assert_eq!(large.total(1) - large.total(0), large.per_guess);
+
+        // Guesses journal solved cells rather than copying the board
+        let journal = 400 * std::mem::size_of::<CellPos>();
+        assert!(large.per_guess > large.ranges + journal);
+        assert!(large.per_guess < large.ranges + journal + large.cells);
+    }
+
+    #[test]
+    fn unclued_line_accepts_anything() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["##", ".#"]));
+        let other = from_art(&["##", "#."]);
+        assert!(!puzzle.is_solved_by(&other));
+
+        puzzle.remove_clues(LineId::Row(1));
+        puzzle.remove_clues(LineId::Column(0));
+        puzzle.remove_clues(LineId::Column(1));
+        assert!(puzzle.is_solved_by(&other));
+    }
+}
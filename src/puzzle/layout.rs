@@ -0,0 +1,232 @@
+//! Cropping, padding, resizing and tiling puzzles
+//!
+//! Empty lines can be added or removed without touching the other clues, so
+//! those work on any puzzle. Other changes rederive the clues from the goal.
+
+use super::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+
+// Clue lists of lines known to be empty
+fn empty_lines(count: usize) -> Vec<Option<Vec<usize>>> {
+    vec![Some(Vec::new()); count]
+}
+
+// How many lines at each end of `lines` are known to be empty, without
+// counting any line twice
+fn empty_ends(lines: &[Option<Vec<usize>>]) -> (usize, usize) {
+    let empty = |clues: &&Option<Vec<usize>>| clues.as_ref().is_some_and(Vec::is_empty);
+    let start = lines.iter().take_while(empty).count();
+    let end = lines[start..].iter().rev().take_while(empty).count();
+    (start, end)
+}
+
+// Place `second` to the right of `first`, or below it if not `horizontal`
+fn tile(first: &Grid, second: &Grid, horizontal: bool) -> Grid {
+    let (width, height, offset) = match horizontal {
+        true => (
+            first.width() + second.width(),
+            first.height(),
+            CellPos::new(0, first.width()),
+        ),
+        false => (
+            first.width(),
+            first.height() + second.height(),
+            CellPos::new(first.height(), 0),
+        ),
+    };
+    let mut grid = Grid::new(width, height);
+    for pos in first.positions() {
+        grid.set_state(pos, first.get(pos));
+    }
+    for pos in second.positions() {
+        let shifted = CellPos::new(offset.row + pos.row, offset.col + pos.col);
+        grid.set_state(shifted, second.get(pos));
+    }
+    grid
+}
+
+impl Puzzle {
+    /// Remove the empty rows and columns around the picture
+    pub fn cropped(&self) -> Puzzle {
+        let (top, bottom) = empty_ends(&self.rows);
+        let (left, right) = empty_ends(&self.cols);
+        let origin = CellPos::new(top, left);
+        let width = self.width() - left - right;
+        let height = self.height() - top - bottom;
+
+        Puzzle {
+            rows: self.rows[top..top + height].to_vec(),
+            cols: self.cols[left..left + width].to_vec(),
+            goal: self
+                .goal
+                .as_ref()
+                .map(|goal| goal.cropped(origin, width, height)),
+            givens: self.givens.cropped(origin, width, height),
+        }
+    }
+
+    /// Surround the puzzle with margins of empty lines
+    pub fn padded(&self, top: usize, right: usize, bottom: usize, left: usize) -> Puzzle {
+        let pad = |before: usize, lines: &[Option<Vec<usize>>], after: usize| {
+            let mut padded = empty_lines(before);
+            padded.extend_from_slice(lines);
+            padded.extend(empty_lines(after));
+            padded
+        };
+
+        Puzzle {
+            rows: pad(top, &self.rows, bottom),
+            cols: pad(left, &self.cols, right),
+            goal: self
+                .goal
+                .as_ref()
+                .map(|goal| goal.padded(top, right, bottom, left, CellState::Empty)),
+            givens: self
+                .givens
+                .padded(top, right, bottom, left, CellState::Unknown),
+        }
+    }
+
+    /// Change the size keeping the top left corner in place
+    ///
+    /// With a goal the cut-off cells are dropped and the clues rederived.
+    /// Without one the puzzle can only lose lines that are known to be empty,
+    /// `None` otherwise.
+    pub fn resized(&self, width: usize, height: usize) -> Option<Puzzle> {
+        let kept_width = width.min(self.width());
+        let kept_height = height.min(self.height());
+        let right = width - kept_width;
+        let bottom = height - kept_height;
+        let reframe = |grid: &Grid, fill| {
+            grid.cropped(CellPos::new(0, 0), kept_width, kept_height)
+                .padded(0, right, bottom, 0, fill)
+        };
+        let givens = reframe(&self.givens, CellState::Unknown);
+
+        if let Some(goal) = &self.goal {
+            let mut puzzle = Puzzle::from_goal(reframe(goal, CellState::Empty));
+            puzzle.givens = givens;
+            return Some(puzzle);
+        }
+
+        let removable = |lines: &[Option<Vec<usize>>], kept: usize| {
+            lines[kept..]
+                .iter()
+                .all(|clues| clues.as_ref().is_some_and(Vec::is_empty))
+        };
+        if !removable(&self.rows, kept_height) || !removable(&self.cols, kept_width) {
+            return None;
+        }
+        let mut rows = self.rows[..kept_height].to_vec();
+        rows.extend(empty_lines(bottom));
+        let mut cols = self.cols[..kept_width].to_vec();
+        cols.extend(empty_lines(right));
+        Some(Puzzle {
+            rows,
+            cols,
+            goal: None,
+            givens,
+        })
+    }
+
+    /// Join `other` on the right, rederiving the clues from both goals
+    ///
+    /// `None` unless both puzzles have goals and the same height.
+    pub fn concat_horizontal(&self, other: &Puzzle) -> Option<Puzzle> {
+        self.concat(other, true)
+    }
+
+    /// Join `other` underneath, rederiving the clues from both goals
+    ///
+    /// `None` unless both puzzles have goals and the same width.
+    pub fn concat_vertical(&self, other: &Puzzle) -> Option<Puzzle> {
+        self.concat(other, false)
+    }
+
+    fn concat(&self, other: &Puzzle, horizontal: bool) -> Option<Puzzle> {
+        let fits = match horizontal {
+            true => self.height() == other.height(),
+            false => self.width() == other.width(),
+        };
+        if !fits {
+            return None;
+        }
+        let goal = tile(self.goal.as_ref()?, other.goal.as_ref()?, horizontal);
+        let mut puzzle = Puzzle::from_goal(goal);
+        puzzle.givens = tile(&self.givens, &other.givens, horizontal);
+        Some(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn crop_and_pad() {
+        let puzzle = Puzzle::from_goal(from_art(&[".....", "..#..", "..##.", "....."]));
+        let cropped = puzzle.cropped();
+
+        assert_eq!(cropped, Puzzle::from_goal(from_art(&["#.", "##"])));
+        assert_eq!(cropped.padded(1, 1, 1, 2), puzzle);
+
+        let mut unknown = cropped.clone();
+        unknown.set_goal(None);
+        assert_eq!(unknown.padded(0, 3, 1, 0).cropped(), unknown);
+    }
+
+    #[test]
+    fn crop_blank_puzzle() {
+        let blank = Puzzle::from_goal(from_art(&["...", "..."]));
+        let cropped = blank.cropped();
+
+        assert_eq!((cropped.width(), cropped.height()), (0, 0));
+    }
+
+    #[test]
+    fn tiling() {
+        let left = Puzzle::from_goal(from_art(&["#.", ".#"]));
+        let right = Puzzle::from_goal(from_art(&["#", "#"]));
+
+        assert_eq!(
+            left.concat_horizontal(&right),
+            Some(Puzzle::from_goal(from_art(&["#.#", ".##"])))
+        );
+        assert_eq!(
+            left.concat_vertical(&left),
+            Some(Puzzle::from_goal(from_art(&["#.", ".#", "#.", ".#"])))
+        );
+        assert_eq!(left.concat_vertical(&right), None);
+
+        let mut unknown = right.clone();
+        unknown.set_goal(None);
+        assert_eq!(left.concat_horizontal(&unknown), None);
+    }
+
+    #[test]
+    fn resize_rederives_clues() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.", ".##", "#.#"]));
+        let resized = puzzle.resized(2, 4).unwrap();
+
+        assert_eq!(
+            resized,
+            Puzzle::from_goal(from_art(&["##", ".#", "#.", ".."]))
+        );
+    }
+
+    #[test]
+    fn resize_without_goal() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#..", "...", "..."]));
+        puzzle.set_goal(None);
+
+        let shrunk = puzzle.resized(1, 1).unwrap();
+        assert_eq!(shrunk, Puzzle::new(vec![vec![1]], vec![vec![1]]));
+        assert_eq!(shrunk.resized(3, 3), Some(puzzle));
+        assert_eq!(
+            Puzzle::new(vec![vec![2]], vec![vec![1], vec![1]]).resized(1, 1),
+            None
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Text pictures of grids and puzzles, plain or colored for terminals
+
+#[cfg(feature = "image")]
+pub mod gif;
+pub mod html;
+#[cfg(feature = "image")]
+pub mod sheet;
+pub mod tikz;
+
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::fmt::Write;
+
+fn symbol(state: CellState) -> char {
+    match state {
+        CellState::Filled => '#',
+        CellState::Empty => '.',
+        CellState::Unknown => '?',
+    }
+}
+
+/// One line of text per row: `#` filled, `.` empty, `?` unknown
+pub fn text(grid: &Grid) -> String {
+    let mut out = String::with_capacity((grid.width() + 1) * grid.height());
+    for row in 0..grid.height() {
+        for col in 0..grid.width() {
+            out.push(symbol(grid.get(CellPos::new(row, col))));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// How far a terminal can be trusted with escape codes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Style {
+    /// The glyphs of `text`
+    Plain,
+    /// Colored backgrounds: black filled, white empty and grey unknown
+    Ansi,
+}
+
+impl Style {
+    /// ANSI when stdout is a terminal that isn't `dumb` and `NO_COLOR` is unset
+    pub fn detect() -> Style {
+        use std::io::IsTerminal;
+        Style::detect_from(
+            std::env::var("TERM").ok().as_deref(),
+            std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+            std::io::stdout().is_terminal(),
+        )
+    }
+
+    fn detect_from(term: Option<&str>, no_color: bool, terminal: bool) -> Style {
+        match term {
+            Some(term) if terminal && !no_color && term != "dumb" => Style::Ansi,
+            _ => Style::Plain,
+        }
+    }
+}
+
+// Background color code and the glyph kept on it for copy and paste
+fn ansi_cell(state: CellState) -> (u8, char) {
+    match state {
+        CellState::Filled => (40, '#'),
+        CellState::Empty => (107, ' '),
+        CellState::Unknown => (100, '?'),
+    }
+}
+
+/// The grid in `style`, each cell two columns wide when colored
+pub fn styled(grid: &Grid, style: Style) -> String {
+    if style == Style::Plain {
+        return text(grid);
+    }
+    let mut out = String::new();
+    for row in 0..grid.height() {
+        for col in 0..grid.width() {
+            let (background, glyph) = ansi_cell(grid.get(CellPos::new(row, col)));
+            let _ = write!(out, "\x1b[{}m{}{}", background, glyph, glyph);
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// The grid in the style the terminal on stdout supports
+pub fn for_terminal(grid: &Grid) -> String {
+    styled(grid, Style::detect())
+}
+
+// Braille dot bits by row then column within a 2x4 character cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// The grid packed two columns by four rows per braille character, a raised
+/// dot for each filled cell
+///
+/// Empty and unknown cells look alike, but a 100x100 board fits in 50x25
+/// characters.
+pub fn braille(grid: &Grid) -> String {
+    let mut out = String::new();
+    for top in (0..grid.height()).step_by(4) {
+        for left in (0..grid.width()).step_by(2) {
+            let mut bits = 0;
+            for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, &dot) in dots.iter().enumerate() {
+                    let (row, col) = (top + dy, left + dx);
+                    if row < grid.height()
+                        && col < grid.width()
+                        && grid.get(CellPos::new(row, col)) == CellState::Filled
+                    {
+                        bits |= dot;
+                    }
+                }
+            }
+            out.push(char::from_u32(0x2800 + bits).unwrap_or(' '));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// Clues as strings, `-` standing for an unclued line
+fn clue_strings(clues: &Option<Vec<usize>>) -> Vec<String> {
+    match clues {
+        Some(clues) if clues.is_empty() => vec!["0".to_string()],
+        Some(clues) => clues.iter().map(|c| c.to_string()).collect(),
+        None => vec!["-".to_string()],
+    }
+}
+
+/// The grid with row clues on the left and column clues stacked above
+pub fn with_clues(puzzle: &Puzzle, grid: &Grid) -> String {
+    assert!(grid.width() == puzzle.width() && grid.height() == puzzle.height());
+
+    let rows: Vec<String> = puzzle
+        .rows()
+        .iter()
+        .map(|clues| clue_strings(clues).join(" "))
+        .collect();
+    let cols: Vec<Vec<String>> = puzzle.cols().iter().map(clue_strings).collect();
+    let gutter = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let cell = cols.iter().flatten().map(|c| c.len()).max().unwrap_or(1);
+    let depth = cols.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for level in 0..depth {
+        out.push_str(&" ".repeat(gutter + 1));
+        for clues in &cols {
+            // Bottom align each column's clues against the grid
+            let at = (level + clues.len()).checked_sub(depth);
+            let clue = at.map_or("", |at| clues[at].as_str());
+            out.push_str(&format!(" {:>width$}", clue, width = cell));
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+    for (row, clues) in rows.iter().enumerate() {
+        out.push_str(&format!("{:>width$} ", clues, width = gutter));
+        for col in 0..grid.width() {
+            let state = symbol(grid.get(CellPos::new(row, col)));
+            out.push_str(&format!(" {:>width$}", state, width = cell));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn plain_text() {
+        let mut grid = from_art(&["#.", ".#"]);
+        grid.set_state(CellPos::new(0, 1), CellState::Unknown);
+        assert_eq!(text(&grid), "#?\n.#\n");
+    }
+
+    #[test]
+    fn ansi_cells() {
+        let mut grid = from_art(&["#."]);
+        grid.set_state(CellPos::new(0, 1), CellState::Unknown);
+        assert_eq!(styled(&grid, Style::Plain), "#?\n");
+        assert_eq!(styled(&grid, Style::Ansi), "\x1b[40m##\x1b[100m??\x1b[0m\n");
+    }
+
+    #[test]
+    fn braille_blocks() {
+        let grid = from_art(&["#..", ".#.", "..#", "##.", "#.#"]);
+        assert_eq!(braille(&grid), "\u{28d1}\u{2804}\n\u{2801}\u{2801}\n");
+        assert_eq!(braille(&Grid::new(0, 0)), "");
+    }
+
+    #[test]
+    fn terminal_fallback() {
+        assert_eq!(
+            Style::detect_from(Some("xterm-256color"), false, true),
+            Style::Ansi
+        );
+        assert_eq!(Style::detect_from(Some("xterm"), true, true), Style::Plain);
+        assert_eq!(Style::detect_from(Some("dumb"), false, true), Style::Plain);
+        assert_eq!(Style::detect_from(None, false, true), Style::Plain);
+        assert_eq!(
+            Style::detect_from(Some("xterm"), false, false),
+            Style::Plain
+        );
+    }
+
+    #[test]
+    fn clue_gutters() {
+        let goal = from_art(&["#.", ".#", "#."]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let expected = "   1\n   1 1\n1  # .\n1  . #\n1  # .\n";
+        assert_eq!(with_clues(&puzzle, &goal), expected);
+    }
+}
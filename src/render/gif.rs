@@ -0,0 +1,297 @@
+//! Animated GIFs of a solve, one frame per few deductions
+//!
+//! Each frame is the whole board, one square per cell on a grey grid:
+//! filled cells black, empty ones white and unknown ones light grey.
+
+use crate::puzzle::Puzzle;
+use crate::solver::replay::Replay;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::collections::HashMap;
+
+// Colors by palette index: unknown, empty, filled, grid lines
+const PALETTE: [[u8; 3]; 4] = [[0xd0, 0xd0, 0xd0], [0xff; 3], [0x00; 3], [0x80; 3]];
+const GRID_LINE: u8 = 3;
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODE: u16 = 4096;
+
+/// Size and pacing of an animation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GifOptions {
+    /// Pixels across each cell, not counting the grid lines
+    pub cell_size: usize,
+    /// Deductions shown per frame
+    pub steps_per_frame: usize,
+    /// Hundredths of a second per frame
+    pub delay: u16,
+    /// Hundredths of a second the solved board stays up before looping
+    pub final_delay: u16,
+}
+
+impl Default for GifOptions {
+    fn default() -> GifOptions {
+        GifOptions {
+            cell_size: 12,
+            steps_per_frame: 1,
+            delay: 10,
+            final_delay: 200,
+        }
+    }
+}
+
+/// Record a solve of `puzzle` and animate it from the givens to the solution
+///
+/// `None` if the puzzle has no solution.
+pub fn solve_animation(puzzle: &Puzzle, options: &GifOptions) -> Option<Vec<u8>> {
+    let replay = Replay::record(puzzle)?;
+    let mut board = puzzle.givens().clone();
+    let mut frames = vec![board.clone()];
+    for steps in replay.steps().chunks(options.steps_per_frame.max(1)) {
+        for step in steps {
+            board.set_state(step.pos, step.state);
+        }
+        frames.push(board.clone());
+    }
+    Some(encode(&frames, options))
+}
+
+/// Encode boards of one size as a looping animated GIF
+///
+/// Panics if there are no frames or the image would be wider or taller
+/// than a GIF allows.
+pub fn encode(frames: &[Grid], options: &GifOptions) -> Vec<u8> {
+    let first = frames.first().expect("an animation needs a frame");
+    let cell = options.cell_size.max(1);
+    let width = first.width() * (cell + 1) + 1;
+    let height = first.height() * (cell + 1) + 1;
+    assert!(width <= usize::from(u16::MAX) && height <= usize::from(u16::MAX));
+
+    let mut out = b"GIF89a".to_vec();
+    push_u16(&mut out, width as u16);
+    push_u16(&mut out, height as u16);
+    // Global color table of four entries, background index 0
+    out.extend_from_slice(&[0xf1, 0, 0]);
+    for color in &PALETTE {
+        out.extend_from_slice(color);
+    }
+    // Loop forever
+    out.extend_from_slice(&[0x21, 0xff, 0x0b]);
+    out.extend_from_slice(b"NETSCAPE2.0");
+    out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for (i, frame) in frames.iter().enumerate() {
+        assert!(frame.width() == first.width() && frame.height() == first.height());
+        let delay = if i + 1 == frames.len() {
+            options.final_delay
+        } else {
+            options.delay
+        };
+        out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        push_u16(&mut out, delay);
+        out.extend_from_slice(&[0x00, 0x00]);
+
+        out.push(0x2c);
+        push_u16(&mut out, 0);
+        push_u16(&mut out, 0);
+        push_u16(&mut out, width as u16);
+        push_u16(&mut out, height as u16);
+        out.push(0);
+        out.push(MIN_CODE_SIZE);
+        let data = compress(&pixels(frame, cell, width, height));
+        for block in data.chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+    out.push(0x3b);
+    out
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+// Palette indices row by row, with a one pixel line around every cell
+fn pixels(grid: &Grid, cell: usize, width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (row, col) = (y / (cell + 1), x / (cell + 1));
+            if y % (cell + 1) == 0 || x % (cell + 1) == 0 {
+                pixels.push(GRID_LINE);
+                continue;
+            }
+            pixels.push(match grid.get(CellPos::new(row, col)) {
+                CellState::Unknown => 0,
+                CellState::Empty => 1,
+                CellState::Filled => 2,
+            });
+        }
+    }
+    pixels
+}
+
+// Variable width LZW codes packed least significant bit first
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl Bits {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= u32::from(code) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// GIF flavoured LZW, clearing the table when it fills up
+fn compress(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = Bits {
+        bytes: Vec::new(),
+        buffer: 0,
+        count: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+
+    // Widen codes once the decoder's table will have outgrown them
+    let emit = |bits: &mut Bits, code: u16, size: &mut u8, next: u16| {
+        bits.write(code, *size);
+        if next >= 1 << *size && *size < 12 {
+            *size += 1;
+        }
+    };
+
+    bits.write(clear, size);
+    let mut prefix = match pixels.first() {
+        Some(&pixel) => u16::from(pixel),
+        None => {
+            bits.write(end, size);
+            return bits.finish();
+        }
+    };
+    for &pixel in &pixels[1..] {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+        emit(&mut bits, prefix, &mut size, next);
+        if next < MAX_CODE {
+            table.insert((prefix, pixel), next);
+            next += 1;
+        } else {
+            bits.write(clear, size);
+            table.clear();
+            next = end + 1;
+            size = MIN_CODE_SIZE + 1;
+        }
+        prefix = u16::from(pixel);
+    }
+    emit(&mut bits, prefix, &mut size, next);
+    bits.write(end, size);
+    bits.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    // Undo `compress`, to check it against a straightforward decoder
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let clear = 1usize << MIN_CODE_SIZE;
+        let fresh: Vec<Vec<u8>> = (0..clear + 2).map(|code| vec![code as u8]).collect();
+        let mut table = fresh.clone();
+        let mut size = MIN_CODE_SIZE + 1;
+        let (mut at, mut out) = (0usize, Vec::new());
+        let mut prev: Option<Vec<u8>> = None;
+        loop {
+            let mut code = 0;
+            for bit in 0..size as usize {
+                let byte = data[(at + bit) / 8];
+                code |= usize::from((byte >> ((at + bit) % 8)) & 1) << bit;
+            }
+            at += size as usize;
+            if code == clear {
+                table = fresh.clone();
+                size = MIN_CODE_SIZE + 1;
+                prev = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+            let entry = match table.get(code) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let mut entry = prev.clone().unwrap();
+                    entry.push(entry[0]);
+                    entry
+                }
+            };
+            out.extend_from_slice(&entry);
+            if let Some(mut prev) = prev.take() {
+                if table.len() < usize::from(MAX_CODE) {
+                    prev.push(entry[0]);
+                    table.push(prev);
+                    if table.len() == 1 << size && size < 12 {
+                        size += 1;
+                    }
+                }
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let mut rng = crate::rng::Rng::new(640);
+        let noisy: Vec<u8> = (0..20_000).map(|_| rng.below(4) as u8).collect();
+        let runs: Vec<u8> = (0..20_000).map(|i| (i / 300 % 4) as u8).collect();
+        for pixels in &[noisy, runs, vec![2], vec![]] {
+            assert_eq!(&decompress(&compress(pixels)), pixels);
+        }
+    }
+
+    #[test]
+    fn frame_layout() {
+        let grid = from_art(&["#.", ".#"]);
+        let pixels = pixels(&grid, 2, 7, 7);
+        assert_eq!(&pixels[..7], &[GRID_LINE; 7]);
+        assert_eq!(&pixels[7..14], &[3, 2, 2, 3, 1, 1, 3]);
+    }
+
+    #[test]
+    fn animation_frames() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "###", "..#"]));
+        let options = GifOptions {
+            steps_per_frame: 4,
+            ..GifOptions::default()
+        };
+        let gif = solve_animation(&puzzle, &options).unwrap();
+
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(gif.last(), Some(&0x3b));
+        assert_eq!(&gif[6..10], &[40, 0, 40, 0]);
+        let frames = gif.windows(2).filter(|w| w == &[0x21, 0xf9]).count();
+        // Blank board, then nine cells four at a time
+        assert!(frames >= 4);
+    }
+}
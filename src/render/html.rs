@@ -0,0 +1,87 @@
+//! HTML tables of puzzles for embedding in pages
+//!
+//! The snippet carries its own styles, scoped to the `gurrido` class, so it
+//! can be pasted into a page as it is.
+
+use super::clue_strings;
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::fmt::Write;
+
+const STYLE: &str = "\
+.gurrido table{border-collapse:collapse;font-family:sans-serif;font-size:12px}\
+.gurrido td{width:18px;height:18px;border:1px solid #888;padding:0}\
+.gurrido th{font-weight:normal;color:#333;padding:2px}\
+.gurrido th.col{vertical-align:bottom;line-height:1.2}\
+.gurrido th.row{text-align:right;white-space:nowrap}\
+.gurrido td.filled{background:#000}\
+.gurrido td.empty{background:#fff}\
+.gurrido td.unknown{background:#ddd}";
+
+fn class(state: CellState) -> &'static str {
+    match state {
+        CellState::Filled => "filled",
+        CellState::Empty => "empty",
+        CellState::Unknown => "unknown",
+    }
+}
+
+/// The puzzle as a table, column clues stacked above and row clues to the
+/// left of `grid`'s cells
+pub fn table(puzzle: &Puzzle, grid: &Grid) -> String {
+    assert!(grid.width() == puzzle.width() && grid.height() == puzzle.height());
+
+    let mut out = String::from("<div class=\"gurrido\">\n");
+    let _ = writeln!(out, "<style>{}</style>", STYLE);
+    out.push_str("<table>\n<thead>\n<tr><th></th>");
+    for clues in puzzle.cols() {
+        let _ = write!(
+            out,
+            "<th class=\"col\">{}</th>",
+            clue_strings(clues).join("<br>")
+        );
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+    for (row, clues) in puzzle.rows().iter().enumerate() {
+        let _ = write!(
+            out,
+            "<tr><th class=\"row\">{}</th>",
+            clue_strings(clues).join(" ")
+        );
+        for col in 0..grid.width() {
+            let state = grid.get(CellPos::new(row, col));
+            let _ = write!(out, "<td class=\"{}\"></td>", class(state));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n</div>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+    use crate::spaces::LineId;
+
+    #[test]
+    fn clue_gutters_and_cells() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["#.#", "##."]));
+        puzzle.remove_clues(LineId::Column(2));
+        let mut grid = Grid::new(3, 2);
+        grid.solve(CellPos::new(0, 0), true);
+        grid.solve(CellPos::new(0, 1), false);
+        let html = table(&puzzle, &grid);
+
+        assert!(html.starts_with("<div class=\"gurrido\">\n<style>"));
+        assert!(html.contains(
+            "<tr><th></th><th class=\"col\">2</th><th class=\"col\">1</th><th class=\"col\">-</th></tr>"
+        ));
+        assert!(html.contains(
+            "<tr><th class=\"row\">1 1</th><td class=\"filled\"></td>\
+             <td class=\"empty\"></td><td class=\"unknown\"></td></tr>"
+        ));
+        assert_eq!(html.matches("<td").count(), 6);
+    }
+}
@@ -0,0 +1,195 @@
+//! Printable sheets: a puzzle's clues around an empty grid, as a one page PDF
+//!
+//! Clues sit in squares the size of a cell, column clues above the grid and
+//! row clues to its left, with heavier lines every five cells.
+
+use super::clue_strings;
+use crate::puzzle::Puzzle;
+use std::fmt::Write;
+
+// Helvetica digits are 0.556 em wide
+const DIGIT_WIDTH: f64 = 0.556;
+
+/// Page dimensions in points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PageSize {
+    A4,
+    Letter,
+    Custom { width: f64, height: f64 },
+}
+
+impl PageSize {
+    pub fn dimensions(self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (595.0, 842.0),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+/// Layout of a sheet, lengths in points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SheetOptions {
+    pub page: PageSize,
+    /// Largest cell size; cells shrink to fit the page if need be
+    pub cell_size: f64,
+    pub margin: f64,
+}
+
+impl Default for SheetOptions {
+    fn default() -> SheetOptions {
+        SheetOptions {
+            page: PageSize::A4,
+            cell_size: 18.0,
+            margin: 36.0,
+        }
+    }
+}
+
+/// A blank sheet for solving `puzzle` on paper, centred on the page
+pub fn pdf(puzzle: &Puzzle, options: &SheetOptions) -> Vec<u8> {
+    let (page_width, page_height) = options.page.dimensions();
+    let rows: Vec<Vec<String>> = puzzle.rows().iter().map(clue_strings).collect();
+    let cols: Vec<Vec<String>> = puzzle.cols().iter().map(clue_strings).collect();
+    let gutter = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let depth = cols.iter().map(Vec::len).max().unwrap_or(0);
+
+    // Everything measured in cells, then scaled to fit inside the margins
+    let across = (gutter + puzzle.width()) as f64;
+    let down = (depth + puzzle.height()) as f64;
+    let cell = options
+        .cell_size
+        .min((page_width - 2.0 * options.margin) / across.max(1.0))
+        .min((page_height - 2.0 * options.margin) / down.max(1.0))
+        .max(1.0);
+    let left = (page_width - across * cell) / 2.0 + gutter as f64 * cell;
+    let top = (page_height + down * cell) / 2.0 - depth as f64 * cell;
+    let (right, bottom) = (
+        left + puzzle.width() as f64 * cell,
+        top - puzzle.height() as f64 * cell,
+    );
+
+    let mut content = String::new();
+    for col in 0..=puzzle.width() {
+        let x = left + col as f64 * cell;
+        let weight = if col % 5 == 0 { 1.5 } else { 0.5 };
+        let _ = writeln!(
+            content,
+            "{:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            weight, x, top, x, bottom
+        );
+    }
+    for row in 0..=puzzle.height() {
+        let y = top - row as f64 * cell;
+        let weight = if row % 5 == 0 { 1.5 } else { 0.5 };
+        let _ = writeln!(
+            content,
+            "{:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            weight, left, y, right, y
+        );
+    }
+
+    let size = cell * 0.6;
+    let mut label = |text: &str, centre_x: f64, centre_y: f64| {
+        let x = centre_x - text.len() as f64 * DIGIT_WIDTH * size / 2.0;
+        let y = centre_y - size * 0.35;
+        let _ = writeln!(
+            content,
+            "BT /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+            size, x, y, text
+        );
+    };
+    for (col, clues) in cols.iter().enumerate() {
+        let x = left + (col as f64 + 0.5) * cell;
+        for (i, clue) in clues.iter().rev().enumerate() {
+            label(clue, x, top + (i as f64 + 0.5) * cell);
+        }
+    }
+    for (row, clues) in rows.iter().enumerate() {
+        let y = top - (row as f64 + 0.5) * cell;
+        for (i, clue) in clues.iter().rev().enumerate() {
+            label(clue, left - (i as f64 + 0.5) * cell, y);
+        }
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>",
+            page_width, page_height
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+    ];
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    fn text(bytes: &[u8]) -> String {
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn cross_references_point_at_objects() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "##."]));
+        let pdf = text(&pdf(&puzzle, &SheetOptions::default()));
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+
+        let start: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[start..].starts_with("xref\n"));
+        let entries = pdf[start..].lines().skip(3).take(5);
+        for (i, entry) in entries.enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+        assert_eq!(pdf.matches(") Tj").count(), 6);
+        assert!(pdf.contains("(2) Tj"));
+    }
+
+    #[test]
+    fn shrinks_to_fit() {
+        let puzzle = Puzzle::unclued(100, 40);
+        let options = SheetOptions {
+            page: PageSize::Custom {
+                width: 300.0,
+                height: 300.0,
+            },
+            ..SheetOptions::default()
+        };
+        let pdf = text(&pdf(&puzzle, &options));
+        // 101 columns of clue and grid across 228 points
+        assert!(pdf.contains("/MediaBox [0 0 300.00 300.00]"));
+        let line = pdf.lines().find(|line| line.ends_with(" l S")).unwrap();
+        let x: f64 = line.split(' ').nth(2).unwrap().parse().unwrap();
+        assert!((x - (300.0 - 101.0 * 228.0 / 101.0) / 2.0 - 228.0 / 101.0).abs() < 0.01);
+    }
+}
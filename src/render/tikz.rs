@@ -0,0 +1,83 @@
+//! TikZ pictures of puzzles for LaTeX documents
+//!
+//! One unit is one cell, 5mm by default; scale the picture with TikZ's own
+//! `scale` option. The grid's top left corner sits at the origin.
+
+use super::clue_strings;
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::fmt::Write;
+
+/// A `tikzpicture` of the clues and grid, with the filled cells of `overlay`
+/// shaded in when given
+pub fn picture(puzzle: &Puzzle, overlay: Option<&Grid>) -> String {
+    let (width, height) = (puzzle.width(), puzzle.height());
+    let mut out = String::from("\\begin{tikzpicture}[x=5mm,y=5mm]\n");
+
+    if let Some(grid) = overlay {
+        assert!(grid.width() == width && grid.height() == height);
+        for pos in grid.positions() {
+            if grid.get(pos) == CellState::Filled {
+                let _ = writeln!(out, "  \\fill ({}) rectangle +(1,1);", corner(pos));
+            }
+        }
+    }
+    let _ = writeln!(
+        out,
+        "  \\draw[step=1,gray] (0,0) grid ({},-{});",
+        width, height
+    );
+    let _ = writeln!(
+        out,
+        "  \\draw[thick] (0,0) rectangle ({},-{});",
+        width, height
+    );
+
+    // Column clues stacked upwards from the top edge, the last nearest it
+    for (col, clues) in puzzle.cols().iter().enumerate() {
+        let clues = clue_strings(clues);
+        for (i, clue) in clues.iter().rev().enumerate() {
+            let _ = writeln!(out, "  \\node at ({}.5,{}.5) {{{}}};", col, i, clue);
+        }
+    }
+    // Row clues running leftwards from the left edge, the last nearest it
+    for (row, clues) in puzzle.rows().iter().enumerate() {
+        let clues = clue_strings(clues);
+        for (i, clue) in clues.iter().rev().enumerate() {
+            let _ = writeln!(out, "  \\node at (-{}.5,-{}.5) {{{}}};", i, row, clue);
+        }
+    }
+    out.push_str("\\end{tikzpicture}\n");
+    out
+}
+
+// Bottom left corner of a cell
+fn corner(pos: CellPos) -> String {
+    format!("{},-{}", pos.col, pos.row + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn clues_and_overlay() {
+        let goal = from_art(&["#.#", "##."]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let blank = picture(&puzzle, None);
+
+        assert!(blank.starts_with("\\begin{tikzpicture}"));
+        assert!(blank.ends_with("\\end{tikzpicture}\n"));
+        assert!(blank.contains("(0,0) grid (3,-2);"));
+        assert!(!blank.contains("\\fill"));
+        // Row 0's clues `1 1`, the second nearest the grid
+        assert!(blank.contains("\\node at (-0.5,-0.5) {1};\n  \\node at (-1.5,-0.5) {1};"));
+        assert!(blank.contains("\\node at (0.5,0.5) {2};"));
+
+        let solved = picture(&puzzle, Some(&goal));
+        assert_eq!(solved.matches("\\fill").count(), 4);
+        assert!(solved.contains("\\fill (1,-2) rectangle +(1,1);"));
+    }
+}
@@ -0,0 +1,620 @@
+pub mod compare;
+pub mod play;
+pub mod score;
+pub mod sync;
+
+use crate::explain::{self, Explanation};
+use crate::puzzle::Puzzle;
+use crate::solver::cheapest::{cheapest, CheapestDeduction};
+use crate::solver::events::SolverEvent;
+use crate::solver::line::{clue_status, ClueStatus};
+use crate::solver::technique::Technique;
+use crate::solver::{self, crossline, Solver};
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::mpsc::channel;
+
+/// A player's board for a puzzle, checked against a known solution
+#[derive(Clone, Debug)]
+pub struct Session {
+    puzzle: Puzzle,
+    solution: Grid,
+    board: Grid,
+    frozen: HashSet<CellPos>,
+    // Cells set in `Mode::Free`, unchecked against the clues
+    marks: HashSet<CellPos>,
+    // Hardest technique applied automatically after each move
+    assist: Option<Technique>,
+    assisted: Vec<Suggestion>,
+}
+
+/// How `Session::set` treats a change
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Refuse values line logic on the confirmed cells rules out
+    Strict,
+    /// Accept anything, keeping it apart from confirmed cells
+    Free,
+}
+
+/// Why a change to the board was refused
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SessionError {
+    /// The cell is frozen and cannot be changed
+    Frozen(CellPos),
+    /// A deduction disagrees with a frozen cell
+    Conflict {
+        pos: CellPos,
+        frozen: CellState,
+        deduced: CellState,
+    },
+    /// The frozen cells together break the clues of a line
+    Contradiction(LineId),
+    /// A strict change against what the confirmed cells imply
+    Deduced { pos: CellPos, deduced: CellState },
+}
+
+/// The next cell a player could work out, and how
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Suggestion {
+    pub pos: CellPos,
+    pub state: CellState,
+    pub technique: Technique,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Frozen(pos) => write!(f, "cell {:?} is frozen", pos),
+            SessionError::Conflict {
+                pos,
+                frozen,
+                deduced,
+            } => write!(
+                f,
+                "cell {:?} is frozen as {:?} but must be {:?}",
+                pos, frozen, deduced
+            ),
+            SessionError::Contradiction(line) => {
+                write!(f, "frozen cells break the clues of {:?}", line)
+            }
+            SessionError::Deduced { pos, deduced } => {
+                write!(f, "cell {:?} must be {:?}", pos, deduced)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl Session {
+    /// Start on a board holding only the given cells, `None` if the puzzle has no solution
+    ///
+    /// The puzzle's goal is the reference solution when it has one.
+    pub fn new(puzzle: Puzzle) -> Option<Session> {
+        let solution = match puzzle.goal() {
+            Some(goal) => goal.clone(),
+            None => solver::solve(&puzzle)?,
+        };
+        let board = puzzle.givens().clone();
+        Some(Session {
+            puzzle,
+            solution,
+            board,
+            frozen: HashSet::new(),
+            marks: HashSet::new(),
+            assist: None,
+            assisted: Vec::new(),
+        })
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    pub fn board(&self) -> &Grid {
+        &self.board
+    }
+
+    /// Set a cell on the board freely, `Unknown` clearing it
+    pub fn mark(&mut self, pos: CellPos, state: CellState) -> Result<(), SessionError> {
+        self.set(pos, state, Mode::Free)
+    }
+
+    /// Set a cell on the board, `Unknown` clearing it
+    ///
+    /// Strict changes are checked by line logic against the confirmed cells
+    /// and count as confirmed themselves; free ones are player marks.
+    pub fn set(&mut self, pos: CellPos, state: CellState, mode: Mode) -> Result<(), SessionError> {
+        if self.is_frozen(pos) {
+            return Err(SessionError::Frozen(pos));
+        }
+        if mode == Mode::Strict && state != CellState::Unknown {
+            let mut confirmed = self.confirmed();
+            confirmed.set_state(pos, CellState::Unknown);
+            let mut solver = Solver::from_grid(&self.puzzle, confirmed);
+            solver
+                .propagate()
+                .map_err(|err| SessionError::Contradiction(err.line))?;
+            let deduced = solver.grid().get(pos);
+            if deduced != CellState::Unknown && deduced != state {
+                return Err(SessionError::Deduced { pos, deduced });
+            }
+        }
+
+        self.board.set_state(pos, state);
+        if mode == Mode::Free && state != CellState::Unknown {
+            self.marks.insert(pos);
+        } else {
+            self.marks.remove(&pos);
+        }
+        self.assisted.clear();
+        if state != CellState::Unknown {
+            self.auto_complete();
+        }
+        Ok(())
+    }
+
+    /// Apply deductions up to `tier` automatically after each move, `None` to stop
+    ///
+    /// Guesses are never applied, whatever the tier.
+    pub fn set_assist(&mut self, tier: Option<Technique>) {
+        self.assist = tier;
+    }
+
+    pub fn assist(&self) -> Option<Technique> {
+        self.assist
+    }
+
+    /// Deductions the assist applied after the last move, in order
+    pub fn assisted(&self) -> &[Suggestion] {
+        &self.assisted
+    }
+
+    // Apply deductions while the next one is within the assist tier, stopping
+    // at one on a cell the player has already set or frozen
+    fn auto_complete(&mut self) {
+        let tier = match self.assist {
+            Some(tier) => tier,
+            None => return,
+        };
+        loop {
+            let deduced = match self.line_deductions() {
+                (_, Some(deduced)) if !deduced.is_empty() => deduced,
+                (_, Some(_)) => self.hint().into_iter().collect(),
+                (_, None) => return,
+            };
+            let before = self.assisted.len();
+            for hint in deduced {
+                if hint.technique > tier
+                    || hint.technique == Technique::Guess
+                    || self.board.get(hint.pos) != CellState::Unknown
+                    || self.apply(hint).is_err()
+                {
+                    return;
+                }
+                self.assisted.push(hint);
+            }
+            if self.assisted.len() == before {
+                return;
+            }
+        }
+    }
+
+    /// Whether the cell holds a free player mark rather than a confirmed value
+    pub fn is_marked(&self, pos: CellPos) -> bool {
+        self.marks.contains(&pos)
+    }
+
+    // The board without its free marks
+    fn confirmed(&self) -> Grid {
+        let mut confirmed = self.board.clone();
+        for &pos in &self.marks {
+            confirmed.set_state(pos, CellState::Unknown);
+        }
+        confirmed
+    }
+
+    /// Lock cells at their current state against further changes
+    pub fn freeze(&mut self, cells: impl IntoIterator<Item = CellPos>) {
+        self.frozen.extend(cells);
+    }
+
+    pub fn unfreeze(&mut self, cells: impl IntoIterator<Item = CellPos>) {
+        for pos in cells {
+            self.frozen.remove(&pos);
+        }
+    }
+
+    pub fn is_frozen(&self, pos: CellPos) -> bool {
+        self.frozen.contains(&pos)
+    }
+
+    /// Check that the frozen cells, taken as fact, are consistent with the clues
+    ///
+    /// Only the given and frozen cells are used, so this verifies a region
+    /// without relying on the rest of the board or on the solution.
+    pub fn verify_frozen(&self) -> Result<(), SessionError> {
+        let mut known = self.puzzle.givens().clone();
+        for &pos in &self.frozen {
+            let state = self.board.get(pos);
+            if state != CellState::Unknown {
+                known.set_state(pos, state);
+            }
+        }
+        Solver::from_grid(&self.puzzle, known)
+            .propagate()
+            .map_err(|err| SessionError::Contradiction(err.line))
+    }
+
+    /// Apply the current hint to the board, returning it
+    pub fn apply_hint(&mut self) -> Result<Option<Suggestion>, SessionError> {
+        let hint = match self.hint() {
+            Some(hint) => hint,
+            None => return Ok(None),
+        };
+        self.apply(hint)?;
+        Ok(Some(hint))
+    }
+
+    /// Apply up to `n` deductions and hand back control, returning them
+    ///
+    /// Each deduction is what `apply_hint` would have applied at that point,
+    /// but a single round of line logic supplies as many as it can. On an
+    /// error the deductions before it stay applied.
+    pub fn step_n(&mut self, n: usize) -> Result<Vec<Suggestion>, SessionError> {
+        let mut applied = Vec::new();
+        while applied.len() < n {
+            let deduced = match self.line_deductions() {
+                (_, Some(deduced)) if !deduced.is_empty() => deduced,
+                _ => match self.apply_hint()? {
+                    Some(hint) => {
+                        applied.push(hint);
+                        continue;
+                    }
+                    None => break,
+                },
+            };
+            for hint in deduced.into_iter().take(n - applied.len()) {
+                self.apply(hint)?;
+                applied.push(hint);
+            }
+        }
+        Ok(applied)
+    }
+
+    fn apply(&mut self, hint: Suggestion) -> Result<(), SessionError> {
+        if self.is_frozen(hint.pos) {
+            return Err(SessionError::Conflict {
+                pos: hint.pos,
+                frozen: self.board.get(hint.pos),
+                deduced: hint.state,
+            });
+        }
+        self.board.set_state(hint.pos, hint.state);
+        self.marks.remove(&hint.pos);
+        Ok(())
+    }
+
+    /// Marked cells that disagree with the solution
+    pub fn mistakes(&self) -> Vec<CellPos> {
+        self.board
+            .positions()
+            .filter(|&pos| {
+                let state = self.board.get(pos);
+                state != CellState::Unknown && state != self.solution.get(pos)
+            })
+            .collect()
+    }
+
+    /// Explain why `pos` cannot be `state`, ignoring any mistakes on the board
+    pub fn explain_forbidden(&self, pos: CellPos, state: CellState) -> Option<Explanation> {
+        explain::explain_forbidden(&self.puzzle, &self.known(), pos, state)
+    }
+
+    /// Which clues of `line` the board satisfies or pins down, `None` for an
+    /// unclued line or one the board contradicts
+    pub fn clue_status(&self, line: LineId) -> Option<Vec<ClueStatus>> {
+        let clues = self.puzzle.clues(line)?;
+        clue_status(clues, &self.board.line(line))
+    }
+
+    // The board with its mistakes cleared
+    fn known(&self) -> Grid {
+        let mut known = self.board.clone();
+        for pos in self.mistakes() {
+            known.set_state(pos, CellState::Unknown);
+        }
+        known
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.puzzle.is_solved_by(&self.board)
+    }
+
+    // Line logic run to a standstill on the known cells, with what it solved
+    // in order, or `None` if the known cells contradict the clues
+    fn line_deductions(&self) -> (Solver, Option<Vec<Suggestion>>) {
+        let (tx, rx) = channel();
+        let mut solver = Solver::from_grid(&self.puzzle, self.known());
+        solver.set_event_sender(tx);
+        if solver.propagate().is_err() {
+            return (solver, None);
+        }
+        let deduced = rx
+            .try_iter()
+            .filter_map(|event| match event {
+                SolverEvent::CellSolved {
+                    pos,
+                    state,
+                    technique,
+                    ..
+                } => Some(Suggestion {
+                    pos,
+                    state,
+                    technique,
+                }),
+                _ => None,
+            })
+            .collect();
+        (solver, Some(deduced))
+    }
+
+    /// The easiest cell to deduce next, ignoring any mistakes on the board
+    ///
+    /// Unlike `hint` this never reveals a cell from the solution, and it
+    /// ranks every deduction rather than taking the first line logic finds.
+    pub fn cheapest_hint(&self, max_depth: usize) -> Option<CheapestDeduction> {
+        cheapest(&self.puzzle, &self.known(), max_depth)
+    }
+
+    /// Suggest a cell to solve next, ignoring any mistakes on the board
+    ///
+    /// Line logic is preferred, then cross-line logic and probing, and only
+    /// then a cell is simply revealed from the solution.
+    pub fn hint(&self) -> Option<Suggestion> {
+        if self.known().is_complete() {
+            return None;
+        }
+
+        let (solver, deduced) = self.line_deductions();
+        if let Some(deduced) = deduced {
+            if let Some(&first) = deduced.first() {
+                return Some(first);
+            }
+            if let Some((pos, state)) = crossline::deduce(&self.puzzle, solver.grid()) {
+                return Some(Suggestion {
+                    pos,
+                    state,
+                    technique: Technique::CrossLine,
+                });
+            }
+            if let Some((pos, filled)) = solver.probe() {
+                return Some(Suggestion {
+                    pos,
+                    state: if filled {
+                        CellState::Filled
+                    } else {
+                        CellState::Empty
+                    },
+                    technique: Technique::Probing,
+                });
+            }
+        }
+
+        let grid = solver.grid();
+        let pos = grid.positions().find(|&pos| !grid.node(pos).is_solved())?;
+        Some(Suggestion {
+            pos,
+            state: self.solution.get(pos),
+            technique: Technique::Guess,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn explains_around_mistakes() {
+        let goal = from_art(&["#.", "##"]);
+        let mut session = Session::new(Puzzle::from_goal(goal)).unwrap();
+        session.mark(CellPos::new(0, 1), CellState::Filled).unwrap();
+
+        assert!(session
+            .explain_forbidden(CellPos::new(0, 1), CellState::Filled)
+            .is_some());
+        assert_eq!(
+            session.explain_forbidden(CellPos::new(0, 0), CellState::Filled),
+            None
+        );
+    }
+
+    #[test]
+    fn clue_strikethrough() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.#", "...."]));
+        let mut session = Session::new(puzzle).unwrap();
+        let row = LineId::Row(0);
+        assert_eq!(
+            session.clue_status(row),
+            Some(vec![ClueStatus::Placed, ClueStatus::Placed])
+        );
+
+        session.mark(CellPos::new(0, 0), CellState::Filled).unwrap();
+        session.mark(CellPos::new(0, 1), CellState::Filled).unwrap();
+        assert_eq!(
+            session.clue_status(row),
+            Some(vec![ClueStatus::Satisfied, ClueStatus::Placed])
+        );
+        session.mark(CellPos::new(0, 2), CellState::Filled).unwrap();
+        assert_eq!(session.clue_status(row), None);
+    }
+
+    #[test]
+    fn assist_fills_easy_deductions() {
+        let goal = from_art(&["###", "#..", "#.."]);
+        let mut session = Session::new(Puzzle::from_goal(goal.clone())).unwrap();
+        session.set_assist(Some(Technique::Punctuation));
+        session.mark(CellPos::new(1, 1), CellState::Empty).unwrap();
+
+        assert!(!session.assisted().is_empty());
+        assert!(session
+            .assisted()
+            .iter()
+            .all(|hint| hint.technique <= Technique::Punctuation));
+        assert!(session.mistakes().is_empty());
+        assert_eq!(session.board(), &goal);
+
+        // Clearing a cell is not followed by any assistance
+        session
+            .mark(CellPos::new(1, 1), CellState::Unknown)
+            .unwrap();
+        assert!(session.assisted().is_empty());
+
+        let mut plain = Session::new(Puzzle::from_goal(goal)).unwrap();
+        plain.mark(CellPos::new(1, 1), CellState::Empty).unwrap();
+        assert!(plain.assisted().is_empty());
+        assert_eq!(plain.board().unknown_count(), 8);
+    }
+
+    #[test]
+    fn cheapest_hint_ignores_mistakes() {
+        let goal = from_art(&["###", "...", "#.#"]);
+        let mut session = Session::new(Puzzle::from_goal(goal)).unwrap();
+        session.mark(CellPos::new(1, 0), CellState::Filled).unwrap();
+
+        let hint = session.cheapest_hint(0).unwrap();
+        assert_eq!(hint.technique, Technique::CompletedLine);
+        assert_eq!(hint.state, CellState::Empty);
+    }
+
+    #[test]
+    fn strict_and_free_changes() {
+        let goal = from_art(&["##.", "#..", "..."]);
+        let mut session = Session::new(Puzzle::from_goal(goal)).unwrap();
+        let corner = CellPos::new(2, 2);
+
+        assert_eq!(
+            session.set(corner, CellState::Filled, Mode::Strict),
+            Err(SessionError::Deduced {
+                pos: corner,
+                deduced: CellState::Empty
+            })
+        );
+        session.set(corner, CellState::Filled, Mode::Free).unwrap();
+        assert!(session.is_marked(corner));
+        assert_eq!(session.board().get(corner), CellState::Filled);
+
+        // Free marks don't feed strict checks, even wrong ones
+        let top = CellPos::new(0, 0);
+        session.set(top, CellState::Filled, Mode::Strict).unwrap();
+        assert!(!session.is_marked(top));
+        session.set(corner, CellState::Empty, Mode::Strict).unwrap();
+        assert!(!session.is_marked(corner));
+        session.set(corner, CellState::Unknown, Mode::Free).unwrap();
+        assert!(!session.is_marked(corner));
+    }
+
+    #[test]
+    fn mistakes_and_completion() {
+        let goal = from_art(&["#.", "##"]);
+        let mut session = Session::new(Puzzle::from_goal(goal.clone())).unwrap();
+
+        session.mark(CellPos::new(0, 1), CellState::Filled).unwrap();
+        session.mark(CellPos::new(1, 1), CellState::Filled).unwrap();
+        assert_eq!(session.mistakes(), vec![CellPos::new(0, 1)]);
+
+        for pos in goal.positions() {
+            session.mark(pos, goal.get(pos)).unwrap();
+        }
+        assert!(session.mistakes().is_empty());
+        assert!(session.is_solved());
+        assert_eq!(session.hint(), None);
+    }
+
+    #[test]
+    fn hints_follow_line_logic() {
+        let session = Session::new(Puzzle::from_goal(from_art(&["###", "#..", "#.."]))).unwrap();
+        let hint = session.hint().unwrap();
+
+        assert_eq!(hint.state, CellState::Filled);
+        assert!(hint.technique.is_line_technique());
+    }
+
+    #[test]
+    fn step_budget() {
+        let goal = from_art(&["###", "#..", "#.."]);
+        let mut session = Session::new(Puzzle::from_goal(goal.clone())).unwrap();
+        let mut stepped = session.clone();
+        let first = session.hint().unwrap();
+
+        assert_eq!(stepped.step_n(0), Ok(vec![]));
+        let applied = stepped.step_n(4).unwrap();
+        assert_eq!(applied.len(), 4);
+        assert_eq!(applied[0], first);
+        for _ in 0..4 {
+            session.apply_hint().unwrap();
+        }
+        assert_eq!(stepped.board(), session.board());
+
+        assert_eq!(stepped.step_n(100).unwrap().len(), 5);
+        assert_eq!(stepped.board(), &goal);
+        assert_eq!(stepped.step_n(3), Ok(vec![]));
+    }
+
+    #[test]
+    fn hints_skip_mistakes() {
+        let goal = from_art(&["#.", ".#"]);
+        let mut session = Session::new(Puzzle::from_goal(goal.clone())).unwrap();
+        session.mark(CellPos::new(0, 0), CellState::Empty).unwrap();
+
+        // Two diagonals fit the clues, so nothing is deducible until revealed
+        let hint = session.hint().unwrap();
+        assert_eq!(hint.technique, Technique::Guess);
+        assert_eq!(hint.pos, CellPos::new(0, 0));
+        assert_eq!(hint.state, CellState::Filled);
+    }
+
+    #[test]
+    fn frozen_cells() {
+        let goal = from_art(&["##.", "..#", "#.#"]);
+        let mut session = Session::new(Puzzle::from_goal(goal)).unwrap();
+        let corner = CellPos::new(0, 2);
+        session.mark(corner, CellState::Filled).unwrap();
+        session.freeze(vec![corner]);
+
+        assert_eq!(
+            session.mark(corner, CellState::Empty),
+            Err(SessionError::Frozen(corner))
+        );
+        assert!(matches!(
+            session.verify_frozen(),
+            Err(SessionError::Contradiction(_))
+        ));
+
+        // Hints run into the wrong frozen cell sooner or later
+        loop {
+            match session.apply_hint() {
+                Ok(hint) => assert!(hint.is_some()),
+                Err(err) => {
+                    assert!(matches!(err, SessionError::Conflict { pos, .. } if pos == corner));
+                    break;
+                }
+            }
+        }
+
+        session.unfreeze(vec![corner]);
+        session.mark(corner, CellState::Empty).unwrap();
+        session.freeze(vec![corner]);
+        assert_eq!(session.verify_frozen(), Ok(()));
+        while let Some(hint) = session.apply_hint().unwrap() {
+            assert_ne!(hint.pos, corner);
+        }
+        assert!(session.is_solved());
+    }
+}
@@ -0,0 +1,115 @@
+//! End of game summaries of a board against the solution
+
+use super::Session;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+
+/// How one cell of a player's board compares with the solution
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CellComparison {
+    /// Marked as in the solution
+    Correct,
+    /// Marked the opposite of the solution
+    Incorrect,
+    /// Left unknown where the solution is filled
+    Missing,
+    /// Left unknown where the solution is empty
+    Blank,
+}
+
+/// Counts of each kind of cell and the classification of every cell
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ComparisonReport {
+    pub correct: usize,
+    pub incorrect: usize,
+    pub missing: usize,
+    pub blank: usize,
+    /// Row by row, indexed `[row][col]`
+    pub cells: Vec<Vec<CellComparison>>,
+}
+
+impl ComparisonReport {
+    pub fn get(&self, pos: CellPos) -> CellComparison {
+        self.cells[pos.row][pos.col]
+    }
+
+    /// Whether every filled cell is found with no wrong marks, blanks allowed
+    pub fn is_complete(&self) -> bool {
+        self.incorrect == 0 && self.missing == 0
+    }
+}
+
+impl Session {
+    /// Grade `player_grid` cell by cell against the solution
+    ///
+    /// Panics if the grid is not the size of the puzzle.
+    pub fn compare_to_solution(&self, player_grid: &Grid) -> ComparisonReport {
+        assert!(
+            player_grid.width() == self.solution.width()
+                && player_grid.height() == self.solution.height(),
+            "grid does not match the puzzle size"
+        );
+        let mut report = ComparisonReport {
+            correct: 0,
+            incorrect: 0,
+            missing: 0,
+            blank: 0,
+            cells: vec![Vec::with_capacity(player_grid.width()); player_grid.height()],
+        };
+        for pos in player_grid.positions() {
+            let solution = self.solution.get(pos);
+            let cell = match player_grid.get(pos) {
+                CellState::Unknown if solution == CellState::Filled => CellComparison::Missing,
+                CellState::Unknown => CellComparison::Blank,
+                state if state == solution => CellComparison::Correct,
+                _ => CellComparison::Incorrect,
+            };
+            match cell {
+                CellComparison::Correct => report.correct += 1,
+                CellComparison::Incorrect => report.incorrect += 1,
+                CellComparison::Missing => report.missing += 1,
+                CellComparison::Blank => report.blank += 1,
+            }
+            report.cells[pos.row].push(cell);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn graded_board() {
+        let session = Session::new(Puzzle::from_goal(from_art(&["#.#", "##."]))).unwrap();
+        let mut board = Grid::new(3, 2);
+        board.set_state(CellPos::new(0, 0), CellState::Filled);
+        board.set_state(CellPos::new(0, 1), CellState::Filled);
+        board.set_state(CellPos::new(1, 2), CellState::Empty);
+        let report = session.compare_to_solution(&board);
+
+        assert_eq!(
+            (
+                report.correct,
+                report.incorrect,
+                report.missing,
+                report.blank
+            ),
+            (2, 1, 3, 0)
+        );
+        assert_eq!(report.get(CellPos::new(0, 1)), CellComparison::Incorrect);
+        assert_eq!(report.get(CellPos::new(1, 0)), CellComparison::Missing);
+        assert_eq!(report.cells[1][2], CellComparison::Correct);
+        assert!(!report.is_complete());
+
+        let empty = session.compare_to_solution(&Grid::new(3, 2));
+        assert_eq!((empty.missing, empty.blank), (4, 2));
+
+        let solved = session.compare_to_solution(&from_art(&["#.#", "##."]));
+        assert!(solved.is_complete());
+        assert_eq!(solved.correct, 6);
+    }
+}
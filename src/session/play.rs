@@ -0,0 +1,367 @@
+//! Game bookkeeping around a [`Session`]: the clock, counters and undo
+
+use super::{Mode, Session, SessionError, Suggestion};
+use crate::bytes::{write_varint, ByteReader};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::CellPos;
+use crate::spaces::node::CellState;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 4] = b"GRDP";
+const VERSION: u8 = 1;
+
+// A change to the board and what the cell held before it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Move {
+    pos: CellPos,
+    before: CellState,
+    was_marked: bool,
+}
+
+/// A session being played, timed and counted
+#[derive(Clone, Debug)]
+pub struct PlaySession {
+    session: Session,
+    // Time played up to `started`, which is `None` while paused
+    elapsed: Duration,
+    started: Option<Instant>,
+    moves: usize,
+    mistakes: usize,
+    hints: usize,
+    undos: usize,
+    history: Vec<Move>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Corrupt,
+    SizeMismatch,
+    /// The puzzle to restore onto has no solution
+    Unsolvable,
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayError::BadMagic => write!(f, "not a saved game"),
+            PlayError::UnsupportedVersion(v) => write!(f, "unsupported saved game version {}", v),
+            PlayError::Truncated => write!(f, "saved game ended early"),
+            PlayError::Corrupt => write!(f, "saved game contains invalid data"),
+            PlayError::SizeMismatch => write!(f, "saved game is for a board of another size"),
+            PlayError::Unsolvable => write!(f, "puzzle has no solution"),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+fn state_code(state: CellState) -> u8 {
+    match state {
+        CellState::Unknown => 0,
+        CellState::Empty => 1,
+        CellState::Filled => 2,
+    }
+}
+
+fn code_state(code: u8) -> Option<CellState> {
+    match code {
+        0 => Some(CellState::Unknown),
+        1 => Some(CellState::Empty),
+        2 => Some(CellState::Filled),
+        _ => None,
+    }
+}
+
+impl PlaySession {
+    /// Start playing, with the clock running
+    pub fn new(session: Session) -> PlaySession {
+        PlaySession {
+            session,
+            elapsed: Duration::ZERO,
+            started: Some(Instant::now()),
+            moves: 0,
+            mistakes: 0,
+            hints: 0,
+            undos: 0,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Time played, not counting pauses
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed + self.started.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.started.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(at) = self.started.take() {
+            self.elapsed += at.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Moves that put a wrong value in a cell
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    pub fn undos(&self) -> usize {
+        self.undos
+    }
+
+    fn remember(&mut self, pos: CellPos) {
+        self.history.push(Move {
+            pos,
+            before: self.session.board.get(pos),
+            was_marked: self.session.is_marked(pos),
+        });
+    }
+
+    /// Change a cell as `Session::set` does, counting the move
+    pub fn set(&mut self, pos: CellPos, state: CellState, mode: Mode) -> Result<(), SessionError> {
+        self.remember(pos);
+        if let Err(err) = self.session.set(pos, state, mode) {
+            self.history.pop();
+            return Err(err);
+        }
+        self.moves += 1;
+        if state != CellState::Unknown && state != self.session.solution.get(pos) {
+            self.mistakes += 1;
+        }
+        Ok(())
+    }
+
+    /// Apply a hint as `Session::apply_hint` does, counting it
+    pub fn hint(&mut self) -> Result<Option<Suggestion>, SessionError> {
+        let hint = match self.session.hint() {
+            Some(hint) => hint,
+            None => return Ok(None),
+        };
+        self.remember(hint.pos);
+        match self.session.apply_hint() {
+            Ok(applied) => {
+                self.hints += 1;
+                Ok(applied)
+            }
+            Err(err) => {
+                self.history.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Take back the last move or hint, `false` if there is none
+    pub fn undo(&mut self) -> Result<bool, SessionError> {
+        let last = match self.history.last() {
+            Some(&last) => last,
+            None => return Ok(false),
+        };
+        if self.session.is_frozen(last.pos) {
+            return Err(SessionError::Frozen(last.pos));
+        }
+        self.history.pop();
+        self.session.board.set_state(last.pos, last.before);
+        if last.was_marked {
+            self.session.marks.insert(last.pos);
+        } else {
+            self.session.marks.remove(&last.pos);
+        }
+        self.undos += 1;
+        Ok(true)
+    }
+
+    /// Save the board, clock and counters; the puzzle is saved separately
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let board = &self.session.board;
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_varint(&mut out, board.width() as u64);
+        write_varint(&mut out, board.height() as u64);
+        write_varint(&mut out, self.elapsed().as_millis() as u64);
+        for &count in &[self.moves, self.mistakes, self.hints, self.undos] {
+            write_varint(&mut out, count as u64);
+        }
+        for pos in board.positions() {
+            let marked = self.session.is_marked(pos) as u8;
+            let frozen = self.session.is_frozen(pos) as u8;
+            out.push(state_code(board.get(pos)) | marked << 2 | frozen << 3);
+        }
+        write_varint(&mut out, self.history.len() as u64);
+        for step in &self.history {
+            write_varint(
+                &mut out,
+                (step.pos.row * board.width() + step.pos.col) as u64,
+            );
+            out.push(state_code(step.before) | (step.was_marked as u8) << 2);
+        }
+        out
+    }
+
+    /// Restore a saved game onto its puzzle, paused
+    pub fn from_bytes(puzzle: Puzzle, bytes: &[u8]) -> Result<PlaySession, PlayError> {
+        let mut r = ByteReader::new(bytes);
+        if r.take(4) != Some(&MAGIC[..]) {
+            return Err(PlayError::BadMagic);
+        }
+        match r.u8().ok_or(PlayError::Truncated)? {
+            VERSION => {}
+            version => return Err(PlayError::UnsupportedVersion(version)),
+        }
+        let mut varint = || r.varint().map(|v| v as usize).ok_or(PlayError::Truncated);
+        let (width, height) = (varint()?, varint()?);
+        if width != puzzle.width() || height != puzzle.height() {
+            return Err(PlayError::SizeMismatch);
+        }
+        let elapsed = Duration::from_millis(varint()? as u64);
+        let (moves, mistakes, hints, undos) = (varint()?, varint()?, varint()?, varint()?);
+
+        let mut session = Session::new(puzzle).ok_or(PlayError::Unsolvable)?;
+        let positions: Vec<CellPos> = session.board.positions().collect();
+        for pos in positions {
+            let flags = r.u8().ok_or(PlayError::Truncated)?;
+            let state = code_state(flags & 3).ok_or(PlayError::Corrupt)?;
+            session.board.set_state(pos, state);
+            if flags & 4 != 0 {
+                session.marks.insert(pos);
+            }
+            if flags & 8 != 0 {
+                session.frozen.insert(pos);
+            }
+        }
+
+        let count = r.varint().ok_or(PlayError::Truncated)? as usize;
+        let mut history = Vec::new();
+        for _ in 0..count {
+            let index = r.varint().ok_or(PlayError::Truncated)? as usize;
+            let flags = r.u8().ok_or(PlayError::Truncated)?;
+            let before = code_state(flags & 3).ok_or(PlayError::Corrupt)?;
+            if index >= width * height {
+                return Err(PlayError::Corrupt);
+            }
+            history.push(Move {
+                pos: CellPos::new(index / width, index % width),
+                before,
+                was_marked: flags & 4 != 0,
+            });
+        }
+        if !r.is_done() {
+            return Err(PlayError::Corrupt);
+        }
+
+        Ok(PlaySession {
+            session,
+            elapsed,
+            started: None,
+            moves,
+            mistakes,
+            hints,
+            undos,
+            history,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    fn play() -> PlaySession {
+        let puzzle = Puzzle::from_goal(from_art(&["##.", "#..", "..#"]));
+        PlaySession::new(Session::new(puzzle).unwrap())
+    }
+
+    #[test]
+    fn counts_and_undo() {
+        let mut play = play();
+        let pos = CellPos::new(0, 2);
+        play.set(pos, CellState::Filled, Mode::Free).unwrap();
+        play.set(pos, CellState::Empty, Mode::Free).unwrap();
+        assert_eq!((play.moves(), play.mistakes()), (2, 1));
+
+        assert_eq!(play.undo(), Ok(true));
+        assert_eq!(play.session().board().get(pos), CellState::Filled);
+        assert!(play.session().is_marked(pos));
+        assert_eq!(play.undo(), Ok(true));
+        assert_eq!(play.session().board().get(pos), CellState::Unknown);
+        assert!(!play.session().is_marked(pos));
+        assert_eq!(play.undo(), Ok(false));
+        assert_eq!(play.undos(), 2);
+
+        let hint = play.hint().unwrap().unwrap();
+        assert_eq!(play.hints(), 1);
+        play.undo().unwrap();
+        assert_eq!(play.session().board().get(hint.pos), CellState::Unknown);
+    }
+
+    #[test]
+    fn pausing_stops_the_clock() {
+        let mut play = play();
+        play.pause();
+        assert!(play.is_paused());
+        let paused = play.elapsed();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(play.elapsed(), paused);
+        play.resume();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(play.elapsed() > paused);
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut play = play();
+        play.set(CellPos::new(0, 0), CellState::Filled, Mode::Strict)
+            .unwrap();
+        play.set(CellPos::new(2, 0), CellState::Filled, Mode::Free)
+            .unwrap();
+        play.hint().unwrap();
+        play.session.freeze(vec![CellPos::new(0, 0)]);
+        let bytes = play.to_bytes();
+
+        let puzzle = play.session().puzzle().clone();
+        let mut restored = PlaySession::from_bytes(puzzle.clone(), &bytes).unwrap();
+        assert!(restored.is_paused());
+        assert_eq!(restored.session().board(), play.session().board());
+        assert!(restored.session().is_marked(CellPos::new(2, 0)));
+        assert!(restored.session().is_frozen(CellPos::new(0, 0)));
+        assert_eq!(
+            (restored.moves(), restored.mistakes(), restored.hints()),
+            (2, 1, 1)
+        );
+        assert_eq!(restored.undo(), Ok(true));
+
+        assert_eq!(
+            PlaySession::from_bytes(puzzle.clone(), &bytes[..bytes.len() - 1]).unwrap_err(),
+            PlayError::Truncated
+        );
+        let other = Puzzle::unclued(2, 2);
+        assert_eq!(
+            PlaySession::from_bytes(other, &bytes).unwrap_err(),
+            PlayError::SizeMismatch
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Comparable scores for finished games
+
+use super::play::PlaySession;
+use crate::difficulty::DifficultyReport;
+
+/// Weights turning a finished game into a score
+///
+/// A game starts from `base`, scaled by how hard the puzzle is, and loses
+/// points for time taken, hints, mistakes and undos, never going below zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scoring {
+    pub base: f64,
+    /// Points lost per second played
+    pub per_second: f64,
+    pub per_hint: f64,
+    pub per_mistake: f64,
+    pub per_undo: f64,
+    /// How strongly difficulty scales the base, zero ignoring it
+    pub difficulty_weight: f64,
+}
+
+impl Default for Scoring {
+    fn default() -> Scoring {
+        Scoring {
+            base: 1000.0,
+            per_second: 1.0,
+            per_hint: 50.0,
+            per_mistake: 25.0,
+            per_undo: 5.0,
+            difficulty_weight: 1.0,
+        }
+    }
+}
+
+impl Scoring {
+    /// Factor the base is scaled by, one for a puzzle solved by overlap alone
+    pub fn multiplier(&self, report: &DifficultyReport) -> f64 {
+        let per_cell = report.score() as f64 / report.cells.max(1) as f64;
+        1.0 + self.difficulty_weight * (per_cell - 1.0).max(0.0)
+    }
+
+    /// Score a game, `None` until its board is solved
+    pub fn score(&self, play: &PlaySession) -> Option<u64> {
+        if !play.session().is_solved() {
+            return None;
+        }
+        let report = DifficultyReport::analyse(play.session().puzzle())?;
+        let penalty = self.per_second * play.elapsed().as_secs_f64()
+            + self.per_hint * play.hints() as f64
+            + self.per_mistake * play.mistakes() as f64
+            + self.per_undo * play.undos() as f64;
+        Some((self.base * self.multiplier(&report) - penalty).max(0.0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::session::{Mode, Session};
+    use crate::spaces::grid::from_art;
+    use crate::spaces::node::CellState;
+
+    fn solve_by_hand(play: &mut PlaySession) {
+        let goal = play.session().puzzle().goal().unwrap().clone();
+        for pos in goal.positions() {
+            play.set(pos, goal.get(pos), Mode::Free).unwrap();
+        }
+    }
+
+    #[test]
+    fn penalties_lower_the_score() {
+        let puzzle = Puzzle::from_goal(from_art(&["##.", "#..", "..#"]));
+        let scoring = Scoring {
+            per_second: 0.0,
+            ..Scoring::default()
+        };
+
+        let mut clean = PlaySession::new(Session::new(puzzle.clone()).unwrap());
+        assert_eq!(scoring.score(&clean), None);
+        solve_by_hand(&mut clean);
+        let clean_score = scoring.score(&clean).unwrap();
+
+        let mut sloppy = PlaySession::new(Session::new(puzzle).unwrap());
+        let pos = crate::spaces::grid::CellPos::new(2, 0);
+        sloppy.set(pos, CellState::Filled, Mode::Free).unwrap();
+        sloppy.undo().unwrap();
+        sloppy.hint().unwrap();
+        solve_by_hand(&mut sloppy);
+        assert_eq!(scoring.score(&sloppy), Some(clean_score - 80));
+
+        let flat = Scoring {
+            per_second: 0.0,
+            difficulty_weight: 0.0,
+            ..Scoring::default()
+        };
+        assert_eq!(flat.score(&clean), Some(1000));
+        assert!(clean_score >= 1000);
+    }
+}
@@ -0,0 +1,208 @@
+//! Shared boards for co-operative play, kept in step by exchanging operations
+//!
+//! Every peer keeps an [`OpLog`]. Local edits are stamped with a Lamport clock
+//! and the peer's id, then sent to the others, which `receive` them in any
+//! order and any number of times. Operations are totally ordered by stamp and
+//! the latest one on a cell wins, so peers holding the same operations replay
+//! to the same board.
+
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::collections::BTreeMap;
+
+/// Logical time of an operation, ties between peers broken by peer id
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Stamp {
+    pub counter: u64,
+    pub peer: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum OpKind {
+    Set(CellState),
+    /// Return the cell to unknown
+    Clear,
+    /// Attach a note to the cell, an empty note removing it
+    Annotate(String),
+}
+
+/// One edit to the shared board
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Op {
+    pub stamp: Stamp,
+    pub pos: CellPos,
+    pub kind: OpKind,
+}
+
+/// A board and its notes as replayed from a log
+#[derive(Clone, PartialEq, Debug)]
+pub struct SyncState {
+    pub grid: Grid,
+    pub notes: BTreeMap<CellPos, String>,
+}
+
+/// One peer's ordered copy of every operation it has seen
+#[derive(Clone, Debug)]
+pub struct OpLog {
+    peer: u32,
+    clock: u64,
+    // Sorted by stamp, each stamp once
+    ops: Vec<Op>,
+}
+
+impl OpLog {
+    pub fn new(peer: u32) -> OpLog {
+        OpLog {
+            peer,
+            clock: 0,
+            ops: Vec::new(),
+        }
+    }
+
+    pub fn peer(&self) -> u32 {
+        self.peer
+    }
+
+    /// Every operation seen so far, oldest first
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Record a local edit, returning the operation to send to other peers
+    pub fn local(&mut self, pos: CellPos, kind: OpKind) -> Op {
+        self.clock += 1;
+        let op = Op {
+            stamp: Stamp {
+                counter: self.clock,
+                peer: self.peer,
+            },
+            pos,
+            kind,
+        };
+        self.ops.push(op.clone());
+        op
+    }
+
+    pub fn set(&mut self, pos: CellPos, state: CellState) -> Op {
+        self.local(pos, OpKind::Set(state))
+    }
+
+    pub fn clear(&mut self, pos: CellPos) -> Op {
+        self.local(pos, OpKind::Clear)
+    }
+
+    pub fn annotate(&mut self, pos: CellPos, note: impl Into<String>) -> Op {
+        self.local(pos, OpKind::Annotate(note.into()))
+    }
+
+    /// Take in another peer's operation, false if it was already known
+    pub fn receive(&mut self, op: Op) -> bool {
+        self.clock = self.clock.max(op.stamp.counter);
+        match self
+            .ops
+            .binary_search_by_key(&op.stamp, |known| known.stamp)
+        {
+            Ok(_) => false,
+            Err(i) => {
+                self.ops.insert(i, op);
+                true
+            }
+        }
+    }
+
+    /// Take in every operation of another log, returning how many were new
+    pub fn merge(&mut self, other: &OpLog) -> usize {
+        other
+            .ops
+            .iter()
+            .filter(|&op| self.receive(op.clone()))
+            .count()
+    }
+
+    /// Operations stamped after `since`, for catching up a peer
+    pub fn since(&self, since: Stamp) -> &[Op] {
+        let start = self.ops.partition_point(|op| op.stamp <= since);
+        &self.ops[start..]
+    }
+
+    /// Apply every operation in order to a blank board of the given size
+    ///
+    /// Operations on cells outside the board are skipped.
+    pub fn replay(&self, width: usize, height: usize) -> SyncState {
+        let mut state = SyncState {
+            grid: Grid::new(width, height),
+            notes: BTreeMap::new(),
+        };
+        for op in &self.ops {
+            if op.pos.row >= height || op.pos.col >= width {
+                continue;
+            }
+            match &op.kind {
+                OpKind::Set(cell) => state.grid.set_state(op.pos, *cell),
+                OpKind::Clear => state.grid.set_state(op.pos, CellState::Unknown),
+                OpKind::Annotate(note) if note.is_empty() => {
+                    state.notes.remove(&op.pos);
+                }
+                OpKind::Annotate(note) => {
+                    state.notes.insert(op.pos, note.clone());
+                }
+            }
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_edits_converge() {
+        let cell = CellPos::new(0, 1);
+        let mut alice = OpLog::new(1);
+        let mut bob = OpLog::new(2);
+        let a = [
+            alice.set(cell, CellState::Filled),
+            alice.annotate(cell, "sure?"),
+        ];
+        let b = [
+            bob.set(cell, CellState::Empty),
+            bob.set(CellPos::new(1, 0), CellState::Filled),
+        ];
+
+        for op in b.iter().rev() {
+            alice.receive(op.clone());
+        }
+        for op in &a {
+            bob.receive(op.clone());
+        }
+        assert!(!bob.receive(a[0].clone()));
+
+        let state = alice.replay(2, 2);
+        assert_eq!(state, bob.replay(2, 2));
+        // Both first edits are at counter 1, and bob's higher id breaks the tie
+        assert_eq!(state.grid.get(cell), CellState::Empty);
+        assert_eq!(state.notes.get(&cell).map(String::as_str), Some("sure?"));
+        assert_eq!(state.grid.get(CellPos::new(1, 0)), CellState::Filled);
+    }
+
+    #[test]
+    fn clock_moves_past_received_ops() {
+        let cell = CellPos::new(0, 0);
+        let mut alice = OpLog::new(1);
+        let mut bob = OpLog::new(2);
+        for _ in 0..3 {
+            bob.set(cell, CellState::Filled);
+        }
+        assert_eq!(alice.merge(&bob), 3);
+
+        let later = alice.clear(cell);
+        assert_eq!(later.stamp.counter, 4);
+        assert_eq!(alice.since(bob.ops()[2].stamp), &[later]);
+        assert_eq!(alice.replay(1, 1).grid.get(cell), CellState::Unknown);
+
+        alice.annotate(cell, "x");
+        alice.annotate(cell, "");
+        assert!(alice.replay(1, 1).notes.is_empty());
+    }
+}
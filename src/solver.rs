@@ -0,0 +1,1185 @@
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod batch;
+pub mod cheapest;
+pub mod checkpoint;
+pub mod conflict;
+pub mod crossline;
+pub mod deduction;
+pub mod deepening;
+pub mod events;
+pub mod heuristic;
+pub mod line;
+pub mod nogood;
+pub mod parallel;
+pub mod replay;
+pub mod restart;
+pub mod technique;
+
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::hint::Hint;
+use crate::spaces::node::CellState;
+use crate::spaces::{Line, LineId};
+use deduction::{Deduction, Veto};
+use events::SolverEvent;
+use heuristic::{BranchHeuristic, FirstUnknown};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Instant;
+use technique::Technique;
+
+/// A line whose clues can no longer be satisfied
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Contradiction {
+    pub line: LineId,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    grid: Grid,
+    lines: Vec<Option<Line>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Status {
+    Fresh,
+    Searching,
+    // The grid holds a solution that has already been returned
+    Found,
+    Exhausted,
+}
+
+/// Outcome of a single search step
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Progress,
+    Solution(Grid),
+    Exhausted,
+}
+
+// An open guess, holding what it takes to return to the state before it
+#[derive(Clone)]
+struct Branch {
+    lines: Vec<Option<Line>>,
+    cell: CellPos,
+    // Length of the trail before the guess
+    trail: usize,
+    // Length of the journal before the guess
+    journal: usize,
+}
+
+// Whether the validity cached in `lines` can have come from `grid`'s history,
+// which line stamps need to mean the same thing in both
+fn cached_against(lines: &[Option<Line>], grid: &Grid) -> bool {
+    let hints = lines.iter().flatten().flat_map(Line::hints);
+    let mut solutions = hints.flat_map(Hint::solutions);
+    solutions.all(|soln| soln.checked_version().is_none_or(|v| v <= grid.clock()))
+}
+
+/// Line propagation and backtracking search over a puzzle
+#[derive(Clone)]
+pub struct Solver {
+    grid: Grid,
+    // Rows followed by columns, `None` for unclued lines
+    lines: Vec<Option<Line>>,
+    dirty: VecDeque<LineId>,
+    queued: Vec<bool>,
+    stack: Vec<Branch>,
+    status: Status,
+    scratch: SolveScratch,
+    events: Option<Sender<SolverEvent>>,
+    stats: SolveStats,
+    heuristic: Arc<dyn BranchHeuristic>,
+    restarts: Option<restart::Restarts>,
+    nogoods: Option<nogood::NogoodStore>,
+    veto: Option<Veto>,
+    // Board before the first guess, and the guesses made since
+    root: Option<Arc<Snapshot>>,
+    trail: Vec<(CellPos, bool)>,
+    // Cells solved while a guess is open, unsolved again on backtracking
+    journal: Vec<CellPos>,
+}
+
+/// Counters describing the work a solver has done so far
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    pub line_solves: usize,
+    pub guesses: usize,
+    pub backtracks: usize,
+    /// Deepest the guess stack has been
+    pub max_depth: usize,
+    pub contradictions: usize,
+    pub restarts: usize,
+    techniques: BTreeMap<Technique, TechniqueUsage>,
+}
+
+/// How often a technique fired and how many cells it solved
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TechniqueUsage {
+    /// Line solves or guesses in which it solved at least one cell
+    pub fired: usize,
+    pub cells: usize,
+}
+
+impl SolveStats {
+    /// Usage of each technique that solved anything, simplest first
+    pub fn technique_breakdown(&self) -> &BTreeMap<Technique, TechniqueUsage> {
+        &self.techniques
+    }
+
+    pub(crate) fn record(&mut self, technique: Technique, cells: usize) {
+        let usage = self.techniques.entry(technique).or_default();
+        usage.fired += 1;
+        usage.cells += cells;
+    }
+
+    /// Add another run's counters to these
+    pub fn merge(&mut self, other: &SolveStats) {
+        self.line_solves += other.line_solves;
+        self.guesses += other.guesses;
+        self.backtracks += other.backtracks;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.contradictions += other.contradictions;
+        self.restarts += other.restarts;
+        for (&technique, usage) in &other.techniques {
+            let total = self.techniques.entry(technique).or_default();
+            total.fired += usage.fired;
+            total.cells += usage.cells;
+        }
+    }
+}
+
+/// What a search had proven when its time ran out
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialResult {
+    /// Cells forced in every solution, the rest left unknown
+    pub grid: Grid,
+    pub stats: SolveStats,
+}
+
+impl PartialResult {
+    /// Share of the board proven, from 0 to 1
+    pub fn progress(&self) -> f64 {
+        let cells = self.grid.width() * self.grid.height();
+        if cells == 0 {
+            return 1.0;
+        }
+        let known = self
+            .grid
+            .positions()
+            .filter(|&pos| self.grid.get(pos) != CellState::Unknown)
+            .count();
+        known as f64 / cells as f64
+    }
+}
+
+impl fmt::Display for PartialResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "timed out with {:.0}% of the board proven",
+            self.progress() * 100.0
+        )
+    }
+}
+
+impl std::error::Error for PartialResult {}
+
+/// Settings for solving whole puzzles through calls such as `batch::solve_batch`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolveOptions {
+    /// Most solutions to look for, two being enough to tell if one is unique
+    pub limit: usize,
+    /// Worker threads to use
+    pub threads: usize,
+    /// Produce solutions and deductions in the same order on every run, so
+    /// a single puzzle is never split between threads
+    pub deterministic: bool,
+    /// Break ties between branching cells at random from this seed
+    pub seed: Option<u64>,
+    /// Capacity of a store of nogoods learned from failed guesses
+    pub nogoods: Option<usize>,
+    /// Levels of nested assumption tried before any guessing, as in `deepening`
+    pub probe_depth: usize,
+    /// Fall back to guessing when reasoning up to `probe_depth` stalls,
+    /// otherwise give up without solutions
+    pub guessing: bool,
+}
+
+impl Default for SolveOptions {
+    fn default() -> SolveOptions {
+        SolveOptions {
+            limit: 1,
+            threads: 1,
+            deterministic: false,
+            seed: None,
+            nogoods: None,
+            probe_depth: 0,
+            guessing: true,
+        }
+    }
+}
+
+impl SolveOptions {
+    /// First solution as quickly as possible, on every core
+    pub fn fast() -> SolveOptions {
+        SolveOptions {
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            ..SolveOptions::default()
+        }
+    }
+
+    /// Check uniqueness, learning from failures and probing before guessing
+    pub fn thorough() -> SolveOptions {
+        SolveOptions {
+            limit: 2,
+            deterministic: true,
+            nogoods: Some(1024),
+            probe_depth: 1,
+            ..SolveOptions::default()
+        }
+    }
+
+    /// Only what a person could deduce, in the same order every run
+    pub fn teaching() -> SolveOptions {
+        SolveOptions {
+            deterministic: true,
+            probe_depth: 2,
+            guessing: false,
+            ..SolveOptions::default()
+        }
+    }
+
+    // A solver set up with the seed and nogood store these options ask for
+    pub(crate) fn solver(&self, puzzle: &Puzzle) -> Solver {
+        let mut solver = Solver::new(puzzle);
+        if let Some(seed) = self.seed {
+            solver.set_heuristic(Arc::new(heuristic::Seeded { seed }));
+        }
+        if let Some(capacity) = self.nogoods {
+            solver.set_nogoods(nogood::NogoodStore::new(capacity));
+        }
+        solver
+    }
+}
+
+/// Buffers reused across line solves so that propagation does not allocate
+#[derive(Clone, Debug, Default)]
+pub struct SolveScratch {
+    clues: Vec<usize>,
+    line: line::LineScratch,
+    labels: Vec<Option<Technique>>,
+    solved: Vec<(Deduction, Technique)>,
+    // Solved cells still to be passed to their crossing lines
+    pending: VecDeque<(LineId, Deduction, Technique)>,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no arrangement of clues fits {:?}", self.line)
+    }
+}
+
+impl std::error::Error for Contradiction {}
+
+impl Solver {
+    pub fn new(puzzle: &Puzzle) -> Solver {
+        Solver::with_scratch(puzzle, SolveScratch::default())
+    }
+
+    // Continue from a partly solved grid, rechecking every line
+    pub(crate) fn from_grid(puzzle: &Puzzle, grid: Grid) -> Solver {
+        let mut solver = Solver::new(puzzle);
+        solver.grid = grid;
+        solver
+    }
+
+    /// Create a solver reusing the buffers of an earlier one
+    pub fn with_scratch(puzzle: &Puzzle, scratch: SolveScratch) -> Solver {
+        let grid = puzzle.givens().clone();
+        let lines = puzzle
+            .lines()
+            .map(|line| {
+                puzzle
+                    .clues(line)
+                    .map(|clues| Line::new(clues, grid.line_len(line)))
+            })
+            .collect();
+
+        let mut solver = Solver {
+            grid,
+            lines,
+            dirty: VecDeque::new(),
+            queued: vec![false; puzzle.width() + puzzle.height()],
+            stack: Vec::new(),
+            status: Status::Fresh,
+            scratch,
+            events: None,
+            stats: SolveStats::default(),
+            heuristic: Arc::new(FirstUnknown),
+            restarts: None,
+            nogoods: None,
+            veto: None,
+            root: None,
+            trail: Vec::new(),
+            journal: Vec::new(),
+        };
+        for line in puzzle.lines() {
+            solver.mark_dirty(line);
+        }
+        solver
+    }
+
+    pub fn into_scratch(self) -> SolveScratch {
+        self.scratch
+    }
+
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn line(&self, line: LineId) -> Option<&Line> {
+        self.lines[self.line_index(line)].as_ref()
+    }
+
+    fn line_index(&self, line: LineId) -> usize {
+        match line {
+            LineId::Row(row) => row,
+            LineId::Column(col) => self.grid.height() + col,
+        }
+    }
+
+    fn mark_dirty(&mut self, line: LineId) {
+        let i = self.line_index(line);
+        if !self.queued[i] && self.lines[i].is_some() {
+            self.queued[i] = true;
+            self.dirty.push_back(line);
+        }
+    }
+
+    /// Solve one line, leaving the cells it solved in the scratch buffer
+    fn solve_line(&mut self, id: LineId) -> Result<(), Contradiction> {
+        let index = self.line_index(id);
+        let line = match self.lines[index].as_mut() {
+            Some(line) => line,
+            None => {
+                self.scratch.solved.clear();
+                return Ok(());
+            }
+        };
+
+        self.scratch.clues.clear();
+        self.scratch
+            .clues
+            .extend(line.hints().iter().map(Hint::hint));
+        deduction::propose_into(&self.grid, id, &mut self.scratch)?;
+        line.set_starts(self.scratch.line.starts());
+        self.apply_solved();
+        Ok(())
+    }
+
+    // Apply the deductions of the last line solve that the veto allows,
+    // dropping the rest from the scratch buffer
+    fn apply_solved(&mut self) {
+        let mut solved = std::mem::take(&mut self.scratch.solved);
+        solved.retain(|(deduction, _)| self.allows(deduction));
+        for (deduction, _) in &solved {
+            self.solve_cell(deduction.cell, deduction.state == CellState::Filled);
+        }
+        self.scratch.solved = solved;
+    }
+
+    /// Run line logic until no line yields anything new
+    pub fn propagate(&mut self) -> Result<(), Contradiction> {
+        self.propagate_into(None)
+    }
+
+    // Propagate, logging each applied deduction if asked
+    fn propagate_into(
+        &mut self,
+        mut log: Option<&mut Vec<Deduction>>,
+    ) -> Result<(), Contradiction> {
+        while let Some(id) = self.dirty.pop_front() {
+            let index = self.line_index(id);
+            self.queued[index] = false;
+
+            self.stats.line_solves += 1;
+            if let Err(err) = self.solve_line(id) {
+                self.stats.contradictions += 1;
+                self.emit(|| SolverEvent::Contradiction { line: id });
+                self.clear_dirty();
+                return Err(err);
+            }
+            for &technique in &Technique::ALL {
+                let cells = self.scratch.solved.iter();
+                let cells = cells.filter(|&&(_, t)| t == technique).count();
+                if cells > 0 {
+                    self.stats.record(technique, cells);
+                }
+            }
+            self.scratch.pending.clear();
+            let solved = self.scratch.solved.iter();
+            self.scratch
+                .pending
+                .extend(solved.map(|&(deduction, technique)| (id, deduction, technique)));
+            while let Some((line, deduction, technique)) = self.scratch.pending.pop_front() {
+                if let Some(log) = log.as_mut() {
+                    log.push(deduction);
+                }
+                let (pos, state) = (deduction.cell, deduction.state);
+                self.emit(|| SolverEvent::CellSolved {
+                    pos,
+                    state,
+                    line,
+                    technique,
+                });
+                let cross = match line {
+                    LineId::Row(_) => LineId::Column(pos.col),
+                    LineId::Column(_) => LineId::Row(pos.row),
+                };
+                if state == CellState::Empty {
+                    self.tighten_and_fill(cross);
+                }
+                self.mark_dirty(cross);
+            }
+        }
+        Ok(())
+    }
+
+    // Trim and split the line's windows around a new empty cell and keep its
+    // blocks in order, then solve the cells of any block left with a window
+    // of exactly its length
+    fn tighten_and_fill(&mut self, id: LineId) {
+        let index = self.line_index(id);
+        let line = match self.lines[index].as_mut() {
+            Some(line) => line,
+            None => return,
+        };
+        let cells = self.grid.line(id);
+        line.tighten(&cells);
+        line.refine(&cells);
+        line.order();
+        let queued = self.scratch.pending.len();
+        deduction::window_fills(line, &self.grid, id, &mut self.scratch.pending);
+
+        let mut fills = self.scratch.pending.split_off(queued);
+        fills.retain(|&(_, deduction, _)| {
+            let fresh =
+                self.grid.get(deduction.cell) == CellState::Unknown && self.allows(&deduction);
+            if fresh {
+                self.solve_cell(deduction.cell, deduction.state == CellState::Filled);
+            }
+            fresh
+        });
+        for &technique in &Technique::ALL {
+            let cells = fills.iter().filter(|&&(_, _, t)| t == technique).count();
+            if cells > 0 {
+                self.stats.record(technique, cells);
+            }
+        }
+        self.scratch.pending.extend(fills);
+    }
+
+    fn clear_dirty(&mut self) {
+        self.dirty.clear();
+        self.queued.iter_mut().for_each(|queued| *queued = false);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            grid: self.grid.clone(),
+            lines: self.lines.clone(),
+        }
+    }
+
+    // The state from before the branch's guess, rebuilt from the journal
+    fn branch_snapshot(&self, branch: &Branch) -> Snapshot {
+        let mut grid = self.grid.clone();
+        for &pos in &self.journal[branch.journal..] {
+            grid.unsolve(pos);
+        }
+        Snapshot {
+            grid,
+            lines: branch.lines.clone(),
+        }
+    }
+
+    // Return to the state from before the branch's guess
+    fn undo(&mut self, branch: Branch) {
+        for pos in self.journal.drain(branch.journal..).rev() {
+            self.grid.unsolve(pos);
+        }
+        if self.stack.is_empty() {
+            self.journal.clear();
+        }
+        debug_assert!(cached_against(&branch.lines, &self.grid));
+        self.lines = branch.lines;
+        self.clear_dirty();
+    }
+
+    /// Choose guesses with `heuristic` from now on, instead of the first unknown cell
+    pub fn set_heuristic(&mut self, heuristic: Arc<dyn BranchHeuristic>) {
+        self.heuristic = heuristic;
+    }
+
+    fn choose_cell(&self) -> Option<CellPos> {
+        if let Some(order) = self.restart_order() {
+            return order
+                .iter()
+                .cloned()
+                .find(|&pos| !self.grid.node(pos).is_solved());
+        }
+        let cell = self.heuristic.choose(self);
+        debug_assert!(cell.is_none_or(|pos| !self.grid.node(pos).is_solved()));
+        cell
+    }
+
+    // Solve a cell, journaling it while there is a guess to undo
+    fn solve_cell(&mut self, pos: CellPos, filled: bool) {
+        self.grid.solve(pos, filled);
+        if !self.stack.is_empty() {
+            self.journal.push(pos);
+        }
+    }
+
+    // Solve a cell by hand and queue its lines for propagation
+    pub(crate) fn assume(&mut self, pos: CellPos, filled: bool) {
+        self.solve_cell(pos, filled);
+        self.mark_dirty(LineId::Row(pos.row));
+        self.mark_dirty(LineId::Column(pos.col));
+    }
+
+    // Undo guesses until an untried alternative is found, false once exhausted
+    fn backtrack(&mut self) -> bool {
+        while let Some(branch) = self.stack.pop() {
+            let cell = branch.cell;
+            self.trail.truncate(branch.trail);
+            self.undo(branch);
+            let depth = self.stack.len() + 1;
+            self.stats.backtracks += 1;
+            self.emit(|| SolverEvent::Backtrack { cell, depth });
+            // Filled is always guessed first, so the alternative is empty
+            self.assume(cell, false);
+            self.trail.push((cell, false));
+            match self.propagate() {
+                Ok(()) if !self.violates_nogood() => return true,
+                Ok(()) => {}
+                Err(_) => self.learn(),
+            }
+        }
+        false
+    }
+
+    /// Advance the search by one guess or backtrack
+    pub fn step(&mut self) -> Step {
+        match self.status {
+            Status::Fresh => {
+                self.status = match self.propagate() {
+                    Ok(()) => Status::Searching,
+                    Err(_) => Status::Exhausted,
+                };
+                if self.root.is_none() && self.trail.is_empty() {
+                    self.root = Some(Arc::new(self.snapshot()));
+                }
+            }
+            Status::Found => {
+                self.status = match self.backtrack() {
+                    true => Status::Searching,
+                    false => Status::Exhausted,
+                };
+            }
+            Status::Searching => match self.choose_cell() {
+                None => {
+                    self.status = Status::Found;
+                    self.restarts = None;
+                    self.emit(|| SolverEvent::Solution(self.grid.clone()));
+                    return Step::Solution(self.grid.clone());
+                }
+                Some(cell) => {
+                    let depth = self.stack.len() + 1;
+                    self.stats.guesses += 1;
+                    self.stats.record(Technique::Guess, 1);
+                    self.stats.max_depth = self.stats.max_depth.max(depth);
+                    self.emit(|| SolverEvent::Guess { cell, depth });
+                    self.stack.push(Branch {
+                        lines: self.lines.clone(),
+                        cell,
+                        trail: self.trail.len(),
+                        journal: self.journal.len(),
+                    });
+                    self.assume(cell, true);
+                    self.trail.push((cell, true));
+                    let failed = match self.propagate() {
+                        Ok(()) => self.violates_nogood(),
+                        Err(_) => {
+                            self.learn();
+                            true
+                        }
+                    };
+                    if failed {
+                        if !self.backtrack() {
+                            self.status = Status::Exhausted;
+                        }
+                        self.note_failure();
+                    }
+                }
+            },
+            Status::Exhausted => {}
+        }
+
+        match self.status {
+            Status::Exhausted => Step::Exhausted,
+            _ => Step::Progress,
+        }
+    }
+
+    /// Find a cell where one value contradicts the clues after propagation,
+    /// returning it with the value it is therefore forced to take
+    pub fn probe(&self) -> Option<(CellPos, bool)> {
+        let mut trial_base = self.clone();
+        trial_base.events = None;
+        trial_base.stack.clear();
+
+        let unknown = self
+            .grid
+            .positions()
+            .filter(|&pos| !self.grid.node(pos).is_solved());
+        for pos in unknown {
+            for &filled in &[true, false] {
+                let mut trial = trial_base.clone();
+                trial.assume(pos, filled);
+                if trial.propagate().is_err() {
+                    return Some((pos, !filled));
+                }
+            }
+        }
+        None
+    }
+
+    /// Hand the oldest untried alternative to a new solver, for another worker to explore
+    pub fn split_off(&mut self) -> Option<Solver> {
+        if self.status != Status::Searching || self.stack.is_empty() {
+            return None;
+        }
+        let branch = self.stack.remove(0);
+        let mut other = self.fork(self.branch_snapshot(&branch));
+        other.events = self.events.clone();
+        other.trail = self.trail[..branch.trail].to_vec();
+        other.assume(branch.cell, false);
+        other.trail.push((branch.cell, false));
+        Some(other)
+    }
+
+    // A fresh solver starting from `snapshot` with the same configuration
+    fn fork(&self, snapshot: Snapshot) -> Solver {
+        debug_assert!(cached_against(&snapshot.lines, &snapshot.grid));
+        Solver {
+            grid: snapshot.grid,
+            lines: snapshot.lines,
+            dirty: VecDeque::new(),
+            queued: vec![false; self.queued.len()],
+            stack: Vec::new(),
+            status: Status::Fresh,
+            scratch: SolveScratch::default(),
+            events: None,
+            stats: SolveStats::default(),
+            heuristic: self.heuristic.clone(),
+            restarts: None,
+            nogoods: self.nogoods.clone(),
+            veto: self.veto.clone(),
+            root: self.root.clone(),
+            trail: Vec::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    /// Search for the next solution not yet returned
+    pub fn next_solution(&mut self) -> Option<Grid> {
+        loop {
+            match self.step() {
+                Step::Progress => {}
+                Step::Solution(grid) => return Some(grid),
+                Step::Exhausted => return None,
+            }
+        }
+    }
+
+    /// Cells settled by everything explored so far, and so shared by every
+    /// solution still to be found
+    ///
+    /// After `split_off` this only speaks for the branches left to this solver.
+    pub fn proven(&self) -> Grid {
+        match self.stack.first() {
+            Some(branch) => self.branch_snapshot(branch).grid,
+            None => self.grid.clone(),
+        }
+    }
+
+    /// Like `next_solution`, giving up with the proven cells at `deadline`
+    pub fn next_solution_before(
+        &mut self,
+        deadline: Instant,
+    ) -> Result<Option<Grid>, Box<PartialResult>> {
+        loop {
+            if Instant::now() >= deadline {
+                return Err(Box::new(PartialResult {
+                    grid: self.proven(),
+                    stats: self.stats.clone(),
+                }));
+            }
+            match self.step() {
+                Step::Progress => {}
+                Step::Solution(grid) => return Ok(Some(grid)),
+                Step::Exhausted => return Ok(None),
+            }
+        }
+    }
+
+    /// Find up to `limit` distinct solutions by propagation and guessing
+    pub fn solutions(&mut self, limit: usize) -> Vec<Grid> {
+        let mut found = Vec::new();
+        while found.len() < limit {
+            match self.next_solution() {
+                Some(grid) => found.push(grid),
+                None => break,
+            }
+        }
+        found
+    }
+}
+
+/// Solutions found by a search stopped at a bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtMost {
+    pub solutions: Vec<Grid>,
+    /// The search stopped at the bound, so there may be more solutions
+    pub hit_bound: bool,
+}
+
+impl AtMost {
+    pub fn is_solvable(&self) -> bool {
+        !self.solutions.is_empty()
+    }
+
+    /// Exactly one solution, known only when the bound was above one
+    pub fn is_unique(&self) -> bool {
+        self.solutions.len() == 1 && !self.hit_bound
+    }
+}
+
+/// Search until `n` solutions are found or none are left
+pub fn solve_at_most(puzzle: &Puzzle, n: usize) -> AtMost {
+    let solutions = Solver::new(puzzle).solutions(n);
+    AtMost {
+        hit_bound: solutions.len() == n,
+        solutions,
+    }
+}
+
+/// Solve a puzzle, returning its first solution
+pub fn solve(puzzle: &Puzzle) -> Option<Grid> {
+    solve_at_most(puzzle, 1).solutions.pop()
+}
+
+/// Find up to `options.limit` solutions, across threads unless the options
+/// ask for a deterministic order
+pub fn solve_with(puzzle: &Puzzle, options: &SolveOptions) -> Vec<Grid> {
+    if options.limit == 0 {
+        return Vec::new();
+    }
+    if options.probe_depth > 0 || !options.guessing {
+        // A board resolved without guessing has only that solution
+        if let Some(resolved) = deepening::solve(puzzle, options.probe_depth) {
+            return vec![resolved.grid];
+        }
+        if !options.guessing {
+            return Vec::new();
+        }
+    }
+    let mut solver = options.solver(puzzle);
+    if options.threads > 1 && !options.deterministic {
+        parallel::search(solver, options.limit, options.threads)
+    } else {
+        solver.solutions(options.limit)
+    }
+}
+
+/// Whether a puzzle has exactly one solution
+pub fn is_unique(puzzle: &Puzzle) -> bool {
+    solve_at_most(puzzle, 2).is_unique()
+}
+
+/// Solutions equal up to the puzzle's symmetries, with how many were found
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionClass {
+    /// Canonical form shared by every member
+    pub representative: Grid,
+    pub count: usize,
+}
+
+/// Find up to `limit` solutions, collapsing those related by the puzzle's symmetries
+///
+/// Classes are ordered by when their first member was found.
+pub fn solution_classes(puzzle: &Puzzle, limit: usize) -> Vec<SolutionClass> {
+    let symmetries = puzzle.symmetries();
+    let mut classes: Vec<SolutionClass> = Vec::new();
+    for solution in Solver::new(puzzle).solutions(limit) {
+        let representative = solution.canonical(&symmetries);
+        match classes
+            .iter_mut()
+            .find(|class| class.representative == representative)
+        {
+            Some(class) => class.count += 1,
+            None => classes.push(SolutionClass {
+                representative,
+                count: 1,
+            }),
+        }
+    }
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn propagation_solves_simple_puzzle() {
+        let goal = from_art(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let mut solver = Solver::new(&puzzle);
+
+        solver.propagate().unwrap();
+        assert_eq!(solver.grid(), &goal);
+    }
+
+    #[test]
+    fn technique_breakdown() {
+        let goal = from_art(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let mut solver = Solver::new(&Puzzle::from_goal(goal));
+        solver.propagate().unwrap();
+        let breakdown = solver.stats().technique_breakdown();
+
+        assert_eq!(
+            breakdown.values().map(|usage| usage.cells).sum::<usize>(),
+            25
+        );
+        assert!(breakdown.values().all(|usage| usage.fired > 0));
+        assert!(!breakdown.contains_key(&Technique::Guess));
+
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let mut solver = Solver::new(&puzzle);
+        solver.next_solution();
+        let guesses = solver.stats().technique_breakdown()[&Technique::Guess];
+        assert_eq!((guesses.fired, guesses.cells), (1, 1));
+    }
+
+    #[test]
+    fn search_finds_all_solutions() {
+        // Diagonal pair is ambiguous
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let solutions = Solver::new(&puzzle).solutions(10);
+
+        assert_eq!(solutions.len(), 2);
+        assert!(solutions.iter().all(|soln| puzzle.is_solved_by(soln)));
+        assert!(!is_unique(&puzzle));
+    }
+
+    #[test]
+    fn bounded_counting() {
+        let puzzle = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 3]);
+        let bounded = solve_at_most(&puzzle, 4);
+        assert_eq!(bounded.solutions.len(), 4);
+        assert!(bounded.hit_bound && !bounded.is_unique());
+
+        let all = solve_at_most(&puzzle, 7);
+        assert_eq!(all.solutions.len(), 6);
+        assert!(!all.hit_bound);
+
+        let single = Puzzle::from_goal(from_art(&["#.", ".."]));
+        assert!(solve_at_most(&single, 2).is_unique());
+        assert!(!solve_at_most(&single, 1).is_unique());
+        assert!(solve_at_most(&single, 1).is_solvable());
+
+        let contradictory = Puzzle::new(vec![vec![1]; 2], vec![vec![2]; 2]);
+        assert!(!solve_at_most(&contradictory, 1).is_solvable());
+    }
+
+    #[test]
+    fn search_respects_limit() {
+        let puzzle = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 3]);
+
+        assert_eq!(Solver::new(&puzzle).solutions(10).len(), 6);
+        assert_eq!(Solver::new(&puzzle).solutions(4).len(), 4);
+    }
+
+    #[test]
+    fn classes_collapse_symmetric_solutions() {
+        // Six permutation matrices: the identity and anti-diagonal, which swap
+        // under a mirror, and four others forming a second class
+        let puzzle = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 3]);
+        let classes = solution_classes(&puzzle, 10);
+
+        let mut counts: Vec<usize> = classes.iter().map(|class| class.count).collect();
+        counts.sort_unstable();
+        assert_eq!(counts, vec![2, 4]);
+        for class in &classes {
+            assert!(puzzle.is_solved_by(&class.representative));
+        }
+
+        let goal = from_art(&["##.", ".##", "#.#"]);
+        let unique = solution_classes(&Puzzle::from_goal(goal.clone()), 10);
+        assert_eq!(
+            unique,
+            vec![SolutionClass {
+                representative: goal,
+                count: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn stepping_matches_solutions() {
+        let puzzle = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 3]);
+        let mut solver = Solver::new(&puzzle);
+
+        let mut found = Vec::new();
+        loop {
+            match solver.step() {
+                Step::Progress => {}
+                Step::Solution(grid) => found.push(grid),
+                Step::Exhausted => break,
+            }
+        }
+        assert_eq!(found, Solver::new(&puzzle).solutions(10));
+        assert_eq!(solver.step(), Step::Exhausted);
+    }
+
+    #[test]
+    fn scratch_carries_between_solvers() {
+        let puzzle = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 3]);
+        let mut solver = Solver::new(&puzzle);
+        assert_eq!(solver.solutions(10).len(), 6);
+
+        let mut again = Solver::with_scratch(&puzzle, solver.into_scratch());
+        assert_eq!(again.solutions(10).len(), 6);
+    }
+
+    #[test]
+    fn probing_finds_forced_cell() {
+        // Line logic alone stalls, but filling the corner breaks the 2 in row 1
+        let puzzle = Puzzle::new(
+            vec![vec![1, 1], vec![2], vec![1]],
+            vec![vec![1, 1], vec![1], vec![2]],
+        );
+        let mut solver = Solver::new(&puzzle);
+        solver.propagate().unwrap();
+
+        match solver.probe() {
+            Some((pos, filled)) => {
+                let solution = solve(&puzzle).unwrap();
+                assert!(!solver.grid().node(pos).is_solved());
+                assert_eq!(solution.get(pos) == CellState::Filled, filled);
+            }
+            None => assert!(solver.grid().is_complete()),
+        }
+    }
+
+    #[test]
+    fn contradictory_puzzle() {
+        let puzzle = Puzzle::new(vec![vec![2], vec![]], vec![vec![1], vec![]]);
+
+        assert!(solve(&puzzle).is_none());
+        assert!(Solver::new(&puzzle).propagate().is_err());
+    }
+
+    #[test]
+    fn unclued_lines_are_unconstrained() {
+        let mut puzzle = Puzzle::new(vec![vec![2], vec![]], vec![vec![1], vec![1]]);
+        puzzle.remove_clues(LineId::Row(1));
+
+        assert!(is_unique(&puzzle));
+        assert_eq!(solve(&puzzle), Some(from_art(&["##", ".."])));
+    }
+
+    #[test]
+    fn givens_are_honoured() {
+        // Either diagonal fits the clues until a cell is given
+        let mut puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        puzzle.set_given(CellPos::new(0, 1), CellState::Filled);
+
+        assert!(is_unique(&puzzle));
+        assert_eq!(solve(&puzzle), Some(from_art(&[".#", "#."])));
+
+        puzzle.set_given(CellPos::new(1, 1), CellState::Filled);
+        assert_eq!(solve(&puzzle), None);
+    }
+
+    #[test]
+    fn partially_clued_matches_oracle() {
+        for mut rng in crate::strategies::seeds(593, 40) {
+            let mut puzzle = crate::strategies::puzzle(&mut rng, 4, 4);
+            let lines: Vec<LineId> = puzzle.lines().collect();
+            for line in lines {
+                if rng.chance(0.3) {
+                    puzzle.remove_clues(line);
+                }
+            }
+
+            let key = |grid: &Grid| format!("{:?}", grid);
+            let mut expected = crate::oracle::solutions(&puzzle, 1000);
+            let mut found = Solver::new(&puzzle).solutions(1000);
+            expected.sort_by_key(key);
+            found.sort_by_key(key);
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn deadline_keeps_proven_cells() {
+        for mut rng in crate::strategies::seeds(604, 30) {
+            let mut puzzle = crate::strategies::puzzle(&mut rng, 6, 6);
+            let lines: Vec<LineId> = puzzle.lines().collect();
+            for line in lines {
+                if rng.chance(0.3) {
+                    puzzle.remove_clues(line);
+                }
+            }
+            let expected = crate::oracle::solutions(&puzzle, 10_000);
+
+            let mut solver = Solver::new(&puzzle);
+            for _ in 0..rng.below(20) {
+                if let Step::Solution(_) = solver.step() {
+                    break;
+                }
+            }
+            let partial = solver.next_solution_before(Instant::now()).unwrap_err();
+            for pos in partial.grid.positions() {
+                let state = partial.grid.get(pos);
+                if state != CellState::Unknown {
+                    assert!(expected.iter().all(|soln| soln.get(pos) == state));
+                }
+            }
+        }
+
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "###"]));
+        let deadline = Instant::now() + std::time::Duration::from_secs(60);
+        let mut solver = Solver::new(&puzzle);
+        assert!(solver.next_solution_before(deadline).unwrap().is_some());
+        assert_eq!(solver.next_solution_before(deadline), Ok(None));
+    }
+
+    #[test]
+    fn deterministic_order() {
+        let puzzle = Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4]);
+        let options = SolveOptions {
+            limit: 10,
+            threads: 4,
+            deterministic: true,
+            ..SolveOptions::default()
+        };
+        let first = solve_with(&puzzle, &options);
+        assert_eq!(first, Solver::new(&puzzle).solutions(10));
+        for _ in 0..5 {
+            assert_eq!(solve_with(&puzzle, &options), first);
+        }
+
+        let parallel = SolveOptions {
+            deterministic: false,
+            ..options
+        };
+        assert_eq!(solve_with(&puzzle, &parallel).len(), 10);
+    }
+
+    #[test]
+    fn presets() {
+        let ambiguous = Puzzle::new(vec![vec![1]; 2], vec![vec![1]; 2]);
+        let goal = from_art(&["#.#", ".#.", "##."]);
+        let unique = Puzzle::from_goal(goal.clone());
+
+        assert_eq!(solve_with(&ambiguous, &SolveOptions::fast()).len(), 1);
+        assert_eq!(solve_with(&ambiguous, &SolveOptions::thorough()).len(), 2);
+        assert_eq!(
+            solve_with(&unique, &SolveOptions::thorough()),
+            vec![goal.clone()]
+        );
+        assert_eq!(solve_with(&unique, &SolveOptions::teaching()), vec![goal]);
+        assert!(solve_with(&ambiguous, &SolveOptions::teaching()).is_empty());
+    }
+
+    #[test]
+    fn seeded_branching() {
+        let puzzle = Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4]);
+        let seeded = |seed| SolveOptions {
+            limit: 3,
+            seed: Some(seed),
+            ..SolveOptions::default()
+        };
+        let first = solve_with(&puzzle, &seeded(1));
+        assert_eq!(solve_with(&puzzle, &seeded(1)), first);
+        assert!((2..20).any(|seed| solve_with(&puzzle, &seeded(seed)) != first));
+        assert!(first.iter().all(|grid| puzzle.is_solved_by(grid)));
+    }
+
+    #[test]
+    fn empty_cells_split_windows() {
+        let puzzle = Puzzle::from_goal(from_art(&["..##..."]));
+        let mut solver = Solver::new(&puzzle);
+        solver.grid.solve(CellPos::new(0, 3), false);
+        solver.grid.solve(CellPos::new(0, 6), false);
+        solver.tighten_and_fill(LineId::Row(0));
+
+        let windows: Vec<_> = solver.line(LineId::Row(0)).unwrap().hints()[0]
+            .solutions()
+            .iter()
+            .map(|soln| (soln.offset(), soln.length()))
+            .collect();
+        assert_eq!(windows, vec![(0, 3), (4, 2)]);
+    }
+
+    #[test]
+    fn window_fills_record_once_per_technique() {
+        let puzzle = Puzzle::from_goal(from_art(&[".###."]));
+        let mut solver = Solver::new(&puzzle);
+        solver.grid.solve(CellPos::new(0, 0), false);
+        solver.grid.solve(CellPos::new(0, 4), false);
+        solver.tighten_and_fill(LineId::Row(0));
+
+        let overlap = solver.stats().technique_breakdown()[&Technique::Overlap];
+        assert_eq!((overlap.fired, overlap.cells), (1, 3));
+    }
+
+    #[test]
+    fn backtracking_unsolves_journaled_cells() {
+        let puzzle = Puzzle::new(vec![vec![1]; 2], vec![vec![1]; 2]);
+        let mut solver = Solver::new(&puzzle);
+        let first = solver.next_solution().unwrap();
+        assert_eq!(solver.stack.len(), 1);
+        assert_eq!(solver.journal.len(), 4);
+        assert_eq!(solver.proven(), Grid::new(2, 2));
+
+        let second = solver.next_solution().unwrap();
+        assert_ne!(first, second);
+        assert!(solver.stack.is_empty() && solver.journal.is_empty());
+        assert_eq!(solver.next_solution(), None);
+    }
+
+    #[test]
+    fn overlong_clues_are_contradictions() {
+        let puzzle = Puzzle::new(vec![vec![5], vec![1]], vec![vec![1], vec![1]]);
+        assert_eq!(solve(&puzzle), None);
+        assert!(!is_unique(&puzzle));
+        assert!(Solver::new(&puzzle).propagate().is_err());
+
+        let puzzle = Puzzle::new(vec![vec![1, 1, 1]], vec![vec![1]; 3]);
+        assert_eq!(solve(&puzzle), None);
+    }
+}
@@ -0,0 +1,93 @@
+use super::{Solver, Step};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::Grid;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future resolving to the next solution, yielding to the executor after every search step
+pub struct SolveFuture {
+    solver: Solver,
+}
+
+impl SolveFuture {
+    /// Recover the solver to keep searching for further solutions
+    pub fn into_solver(self) -> Solver {
+        self.solver
+    }
+}
+
+impl Future for SolveFuture {
+    type Output = Option<Grid>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Grid>> {
+        match self.solver.step() {
+            Step::Solution(grid) => Poll::Ready(Some(grid)),
+            Step::Exhausted => Poll::Ready(None),
+            Step::Progress => {
+                // Still runnable, so ask to be polled again straight away
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Solver {
+    pub fn solve_async(self) -> SolveFuture {
+        SolveFuture { solver: self }
+    }
+}
+
+pub fn solve_async(puzzle: &Puzzle) -> SolveFuture {
+    Solver::new(puzzle).solve_async()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    // Poll to completion, counting how often the future yielded
+    fn block_on<F: Future + Unpin>(mut future: F) -> (F::Output, usize) {
+        let waker = Arc::new(Noop).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut yields = 0;
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(output) => return (output, yields),
+                Poll::Pending => yields += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn solves_with_yields() {
+        let goal = from_art(&["#.#", ".#.", "#.#"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let (solution, yields) = block_on(solve_async(&puzzle));
+
+        assert_eq!(solution, Some(goal));
+        assert!(yields > 0);
+    }
+
+    #[test]
+    fn resumes_for_more_solutions() {
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let mut future = solve_async(&puzzle);
+        let (first, _) = block_on(&mut future);
+        let mut solver = future.into_solver();
+
+        assert!(first.is_some());
+        assert!(solver.next_solution().is_some());
+        assert!(solver.next_solution().is_none());
+    }
+}
@@ -0,0 +1,174 @@
+//! Solving many puzzles in one call, spread over worker threads
+
+use super::{deepening, SolveOptions, SolveStats};
+use crate::puzzle::{ClueError, Puzzle};
+use crate::spaces::grid::Grid;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// What solving one puzzle of a batch found
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchResult {
+    /// Up to `SolveOptions::limit` solutions
+    pub solutions: Vec<Grid>,
+    pub stats: SolveStats,
+    /// Why the puzzle was rejected without solving, empty if it was solved
+    pub errors: Vec<ClueError>,
+}
+
+impl BatchResult {
+    pub fn is_solved(&self) -> bool {
+        !self.solutions.is_empty()
+    }
+
+    /// Exactly one solution, only meaningful with a limit of at least two
+    pub fn is_unique(&self) -> bool {
+        self.solutions.len() == 1
+    }
+
+    /// Whether the puzzle passed `Puzzle::validate` and was solved
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Results for every puzzle of a batch, in input order, with totals
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchReport {
+    pub results: Vec<BatchResult>,
+    /// Counters summed over the batch, with the deepest search's depth
+    pub totals: SolveStats,
+}
+
+impl BatchReport {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|r| r.is_solved()).count()
+    }
+
+    pub fn unique(&self) -> usize {
+        self.results.iter().filter(|r| r.is_unique()).count()
+    }
+
+    /// Puzzles without a solution, invalid ones included
+    pub fn unsolvable(&self) -> usize {
+        self.results.len() - self.solved()
+    }
+
+    pub fn invalid(&self) -> usize {
+        self.results.iter().filter(|r| !r.is_valid()).count()
+    }
+}
+
+fn solve_one(puzzle: &Puzzle, options: &SolveOptions) -> BatchResult {
+    if let Err(errors) = puzzle.validate() {
+        return BatchResult {
+            solutions: Vec::new(),
+            stats: SolveStats::default(),
+            errors,
+        };
+    }
+    if options.limit > 0 && (options.probe_depth > 0 || !options.guessing) {
+        let resolved = deepening::solve(puzzle, options.probe_depth);
+        if resolved.is_some() || !options.guessing {
+            return BatchResult {
+                solutions: resolved.into_iter().map(|resolved| resolved.grid).collect(),
+                stats: SolveStats::default(),
+                errors: Vec::new(),
+            };
+        }
+    }
+    let mut solver = options.solver(puzzle);
+    let solutions = solver.solutions(options.limit);
+    BatchResult {
+        solutions,
+        stats: solver.stats().clone(),
+        errors: Vec::new(),
+    }
+}
+
+/// Solve every puzzle, `options.threads` at a time
+///
+/// Each puzzle is solved on a single thread, so results are the same on
+/// every run whether or not `options.deterministic` is set.
+pub fn solve_batch(puzzles: &[Puzzle], options: &SolveOptions) -> BatchReport {
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<BatchResult>>> = Mutex::new(vec![None; puzzles.len()]);
+    let work = || loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        let puzzle = match puzzles.get(i) {
+            Some(puzzle) => puzzle,
+            None => return,
+        };
+        let result = solve_one(puzzle, options);
+        slots.lock().unwrap()[i] = Some(result);
+    };
+    thread::scope(|scope| {
+        for _ in 0..options.threads.clamp(1, puzzles.len().max(1)) {
+            scope.spawn(work);
+        }
+    });
+
+    let mut report = BatchReport::default();
+    for result in slots.into_inner().unwrap().into_iter().flatten() {
+        report.totals.merge(&result.stats);
+        report.results.push(result);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn batch_matches_single_solves() {
+        let puzzles = vec![
+            Puzzle::from_goal(from_art(&["#.#", "###"])),
+            Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]),
+            Puzzle::new(vec![vec![2], vec![]], vec![vec![1], vec![]]),
+            Puzzle::from_goal(from_art(&["##", ".#"])),
+        ];
+        let options = SolveOptions {
+            limit: 2,
+            threads: 3,
+            ..SolveOptions::default()
+        };
+        let report = solve_batch(&puzzles, &options);
+
+        assert_eq!(report.results.len(), 4);
+        assert_eq!(
+            (report.solved(), report.unique(), report.unsolvable()),
+            (3, 2, 1)
+        );
+        assert_eq!(report.results[0].solutions, vec![from_art(&["#.#", "###"])]);
+        assert_eq!(report.results[1].solutions.len(), 2);
+        for (puzzle, result) in puzzles.iter().zip(&report.results) {
+            assert_eq!(*result, solve_one(puzzle, &options));
+        }
+        let guesses: usize = report.results.iter().map(|r| r.stats.guesses).sum();
+        assert_eq!(report.totals.guesses, guesses);
+        assert_eq!(solve_batch(&[], &options), BatchReport::default());
+    }
+
+    #[test]
+    fn invalid_puzzles_are_reported() {
+        let puzzles = vec![
+            Puzzle::new(vec![vec![5], vec![1]], vec![vec![1], vec![1]]),
+            Puzzle::from_goal(from_art(&["#.", "##"])),
+        ];
+        let options = SolveOptions {
+            threads: 2,
+            ..SolveOptions::default()
+        };
+        let report = solve_batch(&puzzles, &options);
+
+        assert_eq!((report.solved(), report.invalid()), (1, 1));
+        assert!(matches!(
+            report.results[0].errors[0],
+            ClueError::TooLong { needed: 5, .. }
+        ));
+        assert!(report.results[1].is_valid());
+    }
+}
@@ -0,0 +1,145 @@
+//! The single easiest deduction on a board, for hints that nudge the player
+//! rather than walk them through the propagation order
+//!
+//! Line techniques are tried on each line as the board stands, without
+//! following up on what other lines would solve, then cross-line logic and
+//! then probing at increasing depth.
+
+use super::technique::{self, Technique};
+use super::{crossline, deepening, line, Solver};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+
+/// A cell that can be deduced, and what it takes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CheapestDeduction {
+    pub pos: CellPos,
+    pub state: CellState,
+    pub technique: Technique,
+    /// Levels of nested assumption, zero for line techniques
+    pub depth: usize,
+}
+
+/// The deduction of least cost on `grid`, ranked by technique and then by
+/// probing depth, trying probes no deeper than `max_depth`
+///
+/// `None` if nothing is deducible within that depth or the board already
+/// contradicts the clues.
+pub fn cheapest(puzzle: &Puzzle, grid: &Grid, max_depth: usize) -> Option<CheapestDeduction> {
+    let mut best: Option<CheapestDeduction> = None;
+    let mut labels = Vec::new();
+    for id in puzzle.lines() {
+        let clues = match puzzle.clues(id) {
+            Some(clues) => clues,
+            None => continue,
+        };
+        let cells = grid.line(id);
+        let solved = line::solve(clues, &cells)?;
+        technique::classify(clues, &cells, &solved.cells, &solved.starts, &mut labels);
+        for (i, label) in labels.iter().enumerate() {
+            let technique = match *label {
+                Some(technique) => technique,
+                None => continue,
+            };
+            if best.is_none_or(|best| technique < best.technique) {
+                best = Some(CheapestDeduction {
+                    pos: grid.line_pos(id, i),
+                    state: solved.cells[i],
+                    technique,
+                    depth: 0,
+                });
+            }
+        }
+    }
+    if best.is_some() {
+        return best;
+    }
+
+    if let Some((pos, state)) = crossline::deduce(puzzle, grid) {
+        return Some(CheapestDeduction {
+            pos,
+            state,
+            technique: Technique::CrossLine,
+            depth: 1,
+        });
+    }
+
+    let base = Solver::from_grid(puzzle, grid.clone());
+    let unknown: Vec<CellPos> = grid
+        .positions()
+        .filter(|&pos| grid.get(pos) == CellState::Unknown)
+        .collect();
+    for depth in 1..=max_depth {
+        for &pos in &unknown {
+            for &filled in &[true, false] {
+                let mut trial = base.clone();
+                trial.assume(pos, filled);
+                if deepening::settle(&mut trial, depth - 1).is_err() {
+                    return Some(CheapestDeduction {
+                        pos,
+                        state: if filled {
+                            CellState::Empty
+                        } else {
+                            CellState::Filled
+                        },
+                        technique: Technique::Probing,
+                        depth,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn prefers_the_easiest_technique() {
+        // The empty middle row is a completed line, cheaper than the
+        // overlap filling the top row
+        let puzzle = Puzzle::from_goal(from_art(&["###", "...", "#.#"]));
+        let found = cheapest(&puzzle, &Grid::new(3, 3), 0).unwrap();
+        assert_eq!(found.technique, Technique::CompletedLine);
+        assert_eq!(found.pos.row, 1);
+        assert_eq!(found.state, CellState::Empty);
+
+        let mut grid = Grid::new(3, 3);
+        for pos in grid.clone().positions().filter(|pos| pos.row == 1) {
+            grid.solve(pos, false);
+        }
+        let found = cheapest(&puzzle, &grid, 0).unwrap();
+        assert_eq!(found.technique, Technique::Overlap);
+        assert_eq!(found.depth, 0);
+    }
+
+    #[test]
+    fn falls_back_to_assumptions() {
+        use crate::generator::{targeting, TechniqueTarget};
+        let target = TechniqueTarget {
+            required: Technique::CrossLine,
+            hardest: Technique::CrossLine,
+        };
+        let mut rng = crate::rng::Rng::new(638);
+        let puzzle = targeting(&mut rng, 8, 8, target, 1000).unwrap();
+        let mut solver = Solver::new(&puzzle);
+        solver.propagate().unwrap();
+
+        let found = cheapest(&puzzle, solver.grid(), 1).unwrap();
+        assert!(found.technique >= Technique::CrossLine);
+        assert_eq!(found.depth, 1);
+        assert_eq!(puzzle.goal().unwrap().get(found.pos), found.state);
+    }
+
+    #[test]
+    fn nothing_on_a_solved_or_broken_board() {
+        let goal = from_art(&["#.", "##"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        assert_eq!(cheapest(&puzzle, &goal, 2), None);
+        assert_eq!(cheapest(&puzzle, &from_art(&["##", "##"]), 2), None);
+    }
+}
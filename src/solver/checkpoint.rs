@@ -0,0 +1,552 @@
+use super::nogood::NogoodStore;
+use super::{Branch, Snapshot, Solver, Status, Step};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::hint::HSoln;
+use crate::spaces::node::CellState;
+use crate::spaces::{Line, LineId};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 4] = b"GRDC";
+// Version 2 adds the guess trail, the root board and the nogood store
+const VERSION: u8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CheckpointError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Corrupt,
+    /// The checkpoint was taken for a puzzle of different shape
+    PuzzleMismatch,
+    /// Reading the checkpoint failed
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::BadMagic => write!(f, "not a solver checkpoint"),
+            CheckpointError::UnsupportedVersion(v) => {
+                write!(f, "unsupported checkpoint version {}", v)
+            }
+            CheckpointError::Truncated => write!(f, "checkpoint ended early"),
+            CheckpointError::Corrupt => write!(f, "checkpoint contains invalid data"),
+            CheckpointError::PuzzleMismatch => write!(f, "checkpoint belongs to another puzzle"),
+            CheckpointError::Io(kind) => write!(f, "cannot read checkpoint: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.bytes.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    fn grid(&mut self, grid: &Grid) {
+        for pos in grid.positions() {
+            self.u8(match grid.get(pos) {
+                CellState::Unknown => 0,
+                CellState::Empty => 1,
+                CellState::Filled => 2,
+            });
+        }
+    }
+
+    fn lines(&mut self, lines: &[Option<Line>]) {
+        for line in lines.iter().flatten() {
+            for hint in line.hints() {
+                self.usize(hint.solutions().len());
+                for soln in hint.solutions() {
+                    self.usize(soln.offset());
+                    self.usize(soln.length());
+                }
+            }
+        }
+    }
+
+    fn snapshot(&mut self, snapshot: &Snapshot) {
+        self.grid(&snapshot.grid);
+        self.lines(&snapshot.lines);
+    }
+
+    fn state(&mut self, state: CellState) {
+        self.u8(match state {
+            CellState::Unknown => 0,
+            CellState::Empty => 1,
+            CellState::Filled => 2,
+        });
+    }
+
+    fn line_id(&mut self, line: LineId) {
+        match line {
+            LineId::Row(row) => {
+                self.u8(0);
+                self.usize(row);
+            }
+            LineId::Column(col) => {
+                self.u8(1);
+                self.usize(col);
+            }
+        }
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, CheckpointError> {
+        let value = *self.bytes.get(self.pos).ok_or(CheckpointError::Truncated)?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn usize(&mut self) -> Result<usize, CheckpointError> {
+        let end = self.pos.checked_add(8).ok_or(CheckpointError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(CheckpointError::Truncated)?;
+        self.pos = end;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(buf) as usize)
+    }
+
+    fn bool(&mut self) -> Result<bool, CheckpointError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CheckpointError::Corrupt),
+        }
+    }
+
+    fn grid(&mut self, width: usize, height: usize) -> Result<Grid, CheckpointError> {
+        let mut grid = Grid::new(width, height);
+        let positions: Vec<CellPos> = grid.positions().collect();
+        for pos in positions {
+            match self.u8()? {
+                0 => {}
+                1 => grid.solve(pos, false),
+                2 => grid.solve(pos, true),
+                _ => return Err(CheckpointError::Corrupt),
+            }
+        }
+        Ok(grid)
+    }
+
+    // Hint windows are stored without clues, so fill them into fresh lines
+    fn lines(&mut self, lines: &[Option<Line>]) -> Result<Vec<Option<Line>>, CheckpointError> {
+        let mut lines = lines.to_vec();
+        for line in lines.iter_mut().flatten() {
+            let length = line.len();
+            for hint in line.hints_mut() {
+                let count = self.usize()?;
+                let mut solutions = Vec::new();
+                for _ in 0..count {
+                    let offset = self.usize()?;
+                    let len = self.usize()?;
+                    let end = offset.checked_add(len);
+                    if end.is_none_or(|end| end > length) || len < hint.hint() {
+                        return Err(CheckpointError::Corrupt);
+                    }
+                    solutions.push(HSoln::new(offset, len));
+                }
+                hint.set_solutions(solutions);
+            }
+        }
+        Ok(lines)
+    }
+
+    fn line_id(&mut self, width: usize, height: usize) -> Result<LineId, CheckpointError> {
+        let line = match self.u8()? {
+            0 => LineId::Row(self.usize()?),
+            1 => LineId::Column(self.usize()?),
+            _ => return Err(CheckpointError::Corrupt),
+        };
+        match line {
+            LineId::Row(row) if row < height => Ok(line),
+            LineId::Column(col) if col < width => Ok(line),
+            _ => Err(CheckpointError::Corrupt),
+        }
+    }
+
+    fn snapshot(
+        &mut self,
+        width: usize,
+        height: usize,
+        fresh: &[Option<Line>],
+    ) -> Result<Snapshot, CheckpointError> {
+        Ok(Snapshot {
+            grid: self.grid(width, height)?,
+            lines: self.lines(fresh)?,
+        })
+    }
+
+    fn state(&mut self) -> Result<CellState, CheckpointError> {
+        match self.u8()? {
+            1 => Ok(CellState::Empty),
+            2 => Ok(CellState::Filled),
+            _ => Err(CheckpointError::Corrupt),
+        }
+    }
+
+    fn cell(&mut self, width: usize, height: usize) -> Result<CellPos, CheckpointError> {
+        let pos = CellPos::new(self.usize()?, self.usize()?);
+        if pos.row < height && pos.col < width {
+            Ok(pos)
+        } else {
+            Err(CheckpointError::Corrupt)
+        }
+    }
+}
+
+impl Solver {
+    /// Serialize the complete solver state, search stack included
+    pub fn checkpoint(&self) -> Vec<u8> {
+        let mut w = Writer { bytes: Vec::new() };
+        w.bytes.extend_from_slice(MAGIC);
+        w.u8(VERSION);
+        w.usize(self.grid.width());
+        w.usize(self.grid.height());
+        for line in &self.lines {
+            w.u8(line.is_some() as u8);
+        }
+        w.u8(match self.status {
+            Status::Fresh => 0,
+            Status::Searching => 1,
+            Status::Found => 2,
+            Status::Exhausted => 3,
+        });
+
+        w.grid(&self.grid);
+        w.lines(&self.lines);
+
+        w.usize(self.dirty.len());
+        for &line in &self.dirty {
+            w.line_id(line);
+        }
+
+        w.usize(self.stack.len());
+        for branch in &self.stack {
+            w.usize(branch.cell.row);
+            w.usize(branch.cell.col);
+            w.usize(branch.trail);
+            w.snapshot(&self.branch_snapshot(branch));
+        }
+
+        w.usize(self.trail.len());
+        for &(pos, filled) in &self.trail {
+            w.usize(pos.row);
+            w.usize(pos.col);
+            w.u8(filled as u8);
+        }
+        w.u8(self.root.is_some() as u8);
+        if let Some(root) = &self.root {
+            w.snapshot(root);
+        }
+
+        w.u8(self.nogoods.is_some() as u8);
+        if let Some(store) = &self.nogoods {
+            let nogoods = store.nogoods();
+            w.usize(store.capacity());
+            w.usize(nogoods.len());
+            for nogood in nogoods {
+                w.usize(nogood.len());
+                for (pos, state) in nogood {
+                    w.usize(pos.row);
+                    w.usize(pos.col);
+                    w.state(state);
+                }
+            }
+        }
+
+        w.bytes
+    }
+
+    /// Write a checkpoint to `writer`
+    pub fn write_checkpoint(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&self.checkpoint())?;
+        writer.flush()
+    }
+
+    /// Rebuild a solver from a checkpoint read from `reader`
+    pub fn resume_from(puzzle: &Puzzle, mut reader: impl Read) -> Result<Solver, CheckpointError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|err| CheckpointError::Io(err.kind()))?;
+        Solver::resume(puzzle, &bytes)
+    }
+
+    /// Like `next_solution`, writing a checkpoint to a fresh writer from
+    /// `open` whenever `interval` has passed since the last one
+    ///
+    /// A job preempted part way can carry on from the last checkpoint with
+    /// `resume_from`, redoing at most `interval` of work.
+    pub fn next_solution_checkpointed<W: Write>(
+        &mut self,
+        interval: Duration,
+        mut open: impl FnMut() -> io::Result<W>,
+    ) -> io::Result<Option<Grid>> {
+        let mut last = Instant::now();
+        loop {
+            if last.elapsed() >= interval {
+                self.write_checkpoint(open()?)?;
+                last = Instant::now();
+            }
+            match self.step() {
+                Step::Progress => {}
+                Step::Solution(grid) => return Ok(Some(grid)),
+                Step::Exhausted => return Ok(None),
+            }
+        }
+    }
+
+    /// Rebuild a solver from a checkpoint taken while solving `puzzle`
+    pub fn resume(puzzle: &Puzzle, bytes: &[u8]) -> Result<Solver, CheckpointError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err(CheckpointError::BadMagic);
+        }
+        r.pos = 4;
+        let version = match r.u8()? {
+            version @ 1..=VERSION => version,
+            version => return Err(CheckpointError::UnsupportedVersion(version)),
+        };
+
+        let mut solver = Solver::new(puzzle);
+        let (width, height) = (r.usize()?, r.usize()?);
+        if width != puzzle.width() || height != puzzle.height() {
+            return Err(CheckpointError::PuzzleMismatch);
+        }
+        for line in &solver.lines {
+            if r.bool()? != line.is_some() {
+                return Err(CheckpointError::PuzzleMismatch);
+            }
+        }
+        solver.status = match r.u8()? {
+            0 => Status::Fresh,
+            1 => Status::Searching,
+            2 => Status::Found,
+            3 => Status::Exhausted,
+            _ => return Err(CheckpointError::Corrupt),
+        };
+
+        solver.grid = r.grid(width, height)?;
+        solver.lines = r.lines(&solver.lines)?;
+
+        solver.clear_dirty();
+        let mut dirty = VecDeque::new();
+        for _ in 0..r.usize()? {
+            dirty.push_back(r.line_id(width, height)?);
+        }
+        for line in dirty {
+            solver.mark_dirty(line);
+        }
+
+        let fresh = solver.lines.clone();
+        let mut branches = Vec::new();
+        for _ in 0..r.usize()? {
+            let cell = r.cell(width, height)?;
+            // Version 1 has no guess trail, so nothing is learned from these branches
+            let trail = if version > 1 { r.usize()? } else { 0 };
+            branches.push((cell, trail, r.snapshot(width, height, &fresh)?));
+        }
+        // Journal the cells each guess led to, oldest guess first
+        for i in 0..branches.len() {
+            let (cell, trail, snapshot) = &branches[i];
+            let next = branches
+                .get(i + 1)
+                .map_or(&solver.grid, |branch| &branch.2.grid);
+            let journal = solver.journal.len();
+            for pos in snapshot.grid.positions() {
+                match (snapshot.grid.get(pos), next.get(pos)) {
+                    (before, after) if before == after => {}
+                    (CellState::Unknown, _) => solver.journal.push(pos),
+                    _ => return Err(CheckpointError::Corrupt),
+                }
+            }
+            solver.stack.push(Branch {
+                lines: snapshot.lines.clone(),
+                cell: *cell,
+                trail: *trail,
+                journal,
+            });
+        }
+
+        if version > 1 {
+            for _ in 0..r.usize()? {
+                let pos = r.cell(width, height)?;
+                solver.trail.push((pos, r.bool()?));
+            }
+            if solver
+                .stack
+                .iter()
+                .any(|branch| branch.trail > solver.trail.len())
+            {
+                return Err(CheckpointError::Corrupt);
+            }
+            if r.bool()? {
+                solver.root = Some(Arc::new(r.snapshot(width, height, &fresh)?));
+            }
+            if r.bool()? {
+                let store = NogoodStore::new(r.usize()?);
+                for _ in 0..r.usize()? {
+                    let mut nogood = Vec::new();
+                    for _ in 0..r.usize()? {
+                        nogood.push((r.cell(width, height)?, r.state()?));
+                    }
+                    store.record(nogood);
+                }
+                solver.nogoods = Some(store);
+            }
+        }
+
+        if r.pos != bytes.len() {
+            return Err(CheckpointError::Corrupt);
+        }
+        Ok(solver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Step;
+
+    fn ambiguous() -> Puzzle {
+        Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4])
+    }
+
+    #[test]
+    fn resume_mid_search() {
+        let puzzle = ambiguous();
+        let mut solver = Solver::new(&puzzle);
+        let first = solver.solutions(5);
+
+        let mut resumed = Solver::resume(&puzzle, &solver.checkpoint()).unwrap();
+        assert_eq!(resumed.solutions(100), solver.solutions(100));
+        assert_eq!(first.len(), 5);
+    }
+
+    #[test]
+    fn resume_fresh() {
+        let puzzle = ambiguous();
+        let solver = Solver::new(&puzzle);
+        let mut resumed = Solver::resume(&puzzle, &solver.checkpoint()).unwrap();
+
+        assert_eq!(resumed.step(), Step::Progress);
+        assert_eq!(resumed.solutions(100).len(), 24);
+    }
+
+    #[test]
+    fn keeps_trail_and_nogoods() {
+        let puzzle = ambiguous();
+        let mut solver = Solver::new(&puzzle);
+        let store = NogoodStore::new(16);
+        store.record(vec![(CellPos::new(0, 0), CellState::Filled)]);
+        solver.set_nogoods(store);
+        solver.next_solution();
+
+        let resumed = Solver::resume(&puzzle, &solver.checkpoint()).unwrap();
+        assert_eq!(resumed.trail, solver.trail);
+        assert!(resumed.root.is_some());
+        let stores = (resumed.nogoods.unwrap(), solver.nogoods.unwrap());
+        assert_eq!(stores.0.capacity(), 16);
+        assert_eq!(stores.0.nogoods(), stores.1.nogoods());
+    }
+
+    #[test]
+    fn reads_version_one() {
+        let puzzle = ambiguous();
+        let solver = Solver::new(&puzzle);
+        // A fresh solver's version 1 checkpoint is the version 2 one without
+        // the empty trail, missing root and missing store
+        let mut bytes = solver.checkpoint();
+        bytes[4] = 1;
+        bytes.truncate(bytes.len() - 10);
+
+        let mut resumed = Solver::resume(&puzzle, &bytes).unwrap();
+        assert_eq!(resumed.solutions(100).len(), 24);
+    }
+
+    #[test]
+    fn periodic_checkpoints_through_writers() {
+        let puzzle = ambiguous();
+        let mut solver = Solver::new(&puzzle);
+        let path = std::env::temp_dir().join(format!("gurrido-{}.ckpt", std::process::id()));
+        let mut saved = 0;
+        let found = solver
+            .next_solution_checkpointed(Duration::ZERO, || {
+                saved += 1;
+                std::fs::File::create(&path)
+            })
+            .unwrap();
+        assert!(found.is_some());
+        assert!(saved > 0);
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mut resumed = Solver::resume_from(&puzzle, file).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.next_solution(), found);
+        assert_eq!(
+            Solver::resume_from(&puzzle, &b""[..]).err(),
+            Some(CheckpointError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn rejects_other_puzzle() {
+        let bytes = Solver::new(&ambiguous()).checkpoint();
+        let other = Puzzle::new(vec![vec![1]; 3], vec![vec![1]; 4]);
+
+        assert_eq!(
+            Solver::resume(&other, &bytes).err(),
+            Some(CheckpointError::PuzzleMismatch)
+        );
+    }
+
+    #[test]
+    fn rejects_damaged_bytes() {
+        let puzzle = ambiguous();
+        let bytes = Solver::new(&puzzle).checkpoint();
+
+        assert_eq!(
+            Solver::resume(&puzzle, &bytes[..bytes.len() - 1]).err(),
+            Some(CheckpointError::Truncated)
+        );
+        assert_eq!(
+            Solver::resume(&puzzle, b"nope").err(),
+            Some(CheckpointError::BadMagic)
+        );
+
+        // The first window's offset, after the header, line flags, status,
+        // grid and the first hint's window count
+        let lines = puzzle.width() + puzzle.height();
+        let at = 4 + 1 + 16 + lines + 1 + puzzle.width() * puzzle.height() + 8;
+        let mut damaged = bytes.clone();
+        damaged[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            Solver::resume(&puzzle, &damaged).err(),
+            Some(CheckpointError::Corrupt)
+        );
+    }
+}
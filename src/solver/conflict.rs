@@ -0,0 +1,147 @@
+//! Shrinking a contradictory puzzle down to the clues that cause it
+
+use crate::puzzle::{ClueError, Puzzle};
+use crate::spaces::grid::CellPos;
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+
+/// Clued lines and givens that cannot all be satisfied at once
+///
+/// Dropping any single line or given from the set makes it satisfiable.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Conflict {
+    pub lines: Vec<LineId>,
+    pub givens: Vec<CellPos>,
+}
+
+impl Conflict {
+    pub fn len(&self) -> usize {
+        self.lines.len() + self.givens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Only the parts of `puzzle` named by `conflict`
+fn restrict(puzzle: &Puzzle, conflict: &Conflict) -> Puzzle {
+    let mut restricted = Puzzle::unclued(puzzle.width(), puzzle.height());
+    for &line in &conflict.lines {
+        if let Some(clues) = puzzle.clues(line) {
+            restricted.set_clues(line, clues.to_vec());
+        }
+    }
+    for &pos in &conflict.givens {
+        restricted.set_given(pos, puzzle.givens().get(pos));
+    }
+    restricted
+}
+
+fn satisfiable(puzzle: &Puzzle, conflict: &Conflict) -> bool {
+    super::solve(&restrict(puzzle, conflict)).is_some()
+}
+
+/// Find a minimal set of clues and givens with no solution
+///
+/// Each line and given is dropped in turn, staying out if the rest is still
+/// unsatisfiable. `None` if the puzzle has a solution.
+pub fn unsatisfiable_core(puzzle: &Puzzle) -> Option<Conflict> {
+    // A line whose clues are too long for it is a core on its own
+    let too_long = puzzle.lines().find(|&line| {
+        puzzle
+            .line_errors(line)
+            .iter()
+            .any(|error| matches!(error, ClueError::TooLong { .. }))
+    });
+    if let Some(line) = too_long {
+        return Some(Conflict {
+            lines: vec![line],
+            givens: Vec::new(),
+        });
+    }
+
+    let mut core = Conflict {
+        lines: puzzle
+            .lines()
+            .filter(|&line| puzzle.is_clued(line))
+            .collect(),
+        givens: puzzle
+            .givens()
+            .positions()
+            .filter(|&pos| puzzle.givens().get(pos) != CellState::Unknown)
+            .collect(),
+    };
+    if satisfiable(puzzle, &core) {
+        return None;
+    }
+
+    let mut i = 0;
+    while i < core.givens.len() {
+        let pos = core.givens.remove(i);
+        if satisfiable(puzzle, &core) {
+            core.givens.insert(i, pos);
+            i += 1;
+        }
+    }
+    let mut i = 0;
+    while i < core.lines.len() {
+        let line = core.lines.remove(i);
+        if satisfiable(puzzle, &core) {
+            core.lines.insert(i, line);
+            i += 1;
+        }
+    }
+    Some(core)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn satisfiable_has_no_core() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "###", "..#"]));
+        assert_eq!(unsatisfiable_core(&puzzle), None);
+    }
+
+    #[test]
+    fn mistyped_clue() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["##..", ".##.", "..##", "#..#"]));
+        // Four filled cells by rows, five by columns
+        puzzle.set_clues(LineId::Column(0), vec![3]);
+        let core = unsatisfiable_core(&puzzle).unwrap();
+
+        assert!(core.lines.contains(&LineId::Column(0)));
+        assert!(core.givens.is_empty());
+        assert!(!satisfiable(&puzzle, &core));
+        for i in 0..core.lines.len() {
+            let mut smaller = core.clone();
+            smaller.lines.remove(i);
+            assert!(satisfiable(&puzzle, &smaller));
+        }
+    }
+
+    #[test]
+    fn given_against_clue() {
+        let mut puzzle = Puzzle::from_goal(from_art(&["###", "...", "#.#"]));
+        puzzle.set_given(CellPos::new(1, 1), CellState::Filled);
+        puzzle.set_given(CellPos::new(2, 0), CellState::Filled);
+
+        assert_eq!(
+            unsatisfiable_core(&puzzle),
+            Some(Conflict {
+                lines: vec![LineId::Row(1)],
+                givens: vec![CellPos::new(1, 1)],
+            })
+        );
+    }
+
+    #[test]
+    fn overlong_clue() {
+        let mut puzzle = Puzzle::unclued(3, 3);
+        puzzle.set_clues(LineId::Row(2), vec![2, 2]);
+        assert_eq!(unsatisfiable_core(&puzzle).unwrap().lines, [LineId::Row(2)]);
+    }
+}
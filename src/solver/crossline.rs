@@ -0,0 +1,149 @@
+//! Cross-line edge logic: reasoning over a line and the lines crossing it
+//!
+//! Assuming a value and following its effect through the lines crossing the
+//! cell, and the lines crossing those, finds what a human spots when a block
+//! pushed against an edge leaves no room for the crossing lines' clues.
+//! Single line solving misses these, full probing finds them at more cost.
+
+use super::line;
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+
+// How many rounds of lines are solved after an assumption: the lines
+// through the cell, those crossing them, and those crossing the second round
+const ROUNDS: usize = 3;
+
+fn cell_at(line: LineId, i: usize) -> CellPos {
+    match line {
+        LineId::Row(row) => CellPos::new(row, i),
+        LineId::Column(col) => CellPos::new(i, col),
+    }
+}
+
+fn crossing(line: LineId, pos: CellPos) -> LineId {
+    match line {
+        LineId::Row(_) => LineId::Column(pos.col),
+        LineId::Column(_) => LineId::Row(pos.row),
+    }
+}
+
+// Whether `pos` taking `state` breaks a line within `ROUNDS` of it, undoing
+// every cell it solves on the way out
+fn contradicts(puzzle: &Puzzle, grid: &mut Grid, pos: CellPos, state: CellState) -> bool {
+    let mut journal = vec![pos];
+    grid.solve(pos, state == CellState::Filled);
+    let broken = follow(puzzle, grid, pos, &mut journal);
+    for &at in journal.iter().rev() {
+        grid.unsolve(at);
+    }
+    broken
+}
+
+// Solve the lines around `pos` round by round, recording each cell solved
+fn follow(puzzle: &Puzzle, grid: &mut Grid, pos: CellPos, journal: &mut Vec<CellPos>) -> bool {
+    let mut frontier = vec![LineId::Row(pos.row), LineId::Column(pos.col)];
+
+    for _ in 0..ROUNDS {
+        let mut next = Vec::new();
+        for &line in &frontier {
+            let clues = match puzzle.clues(line) {
+                Some(clues) => clues,
+                None => continue,
+            };
+            let solved = match line::solve(clues, &grid.line(line)) {
+                Some(solved) => solved,
+                None => return true,
+            };
+            for (i, &cell) in solved.cells.iter().enumerate() {
+                let at = cell_at(line, i);
+                // A line that fits only ever solves unknown cells
+                if grid.get(at) != cell {
+                    grid.solve(at, cell == CellState::Filled);
+                    journal.push(at);
+                    let other = crossing(line, at);
+                    if !next.contains(&other) {
+                        next.push(other);
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    false
+}
+
+/// Find a cell whose other value fails within a few rounds of crossing
+/// lines, returning the cell with the value it is forced to take
+pub fn deduce(puzzle: &Puzzle, grid: &Grid) -> Option<(CellPos, CellState)> {
+    let mut trial = grid.clone();
+    let unknown = grid
+        .positions()
+        .filter(|&pos| grid.get(pos) == CellState::Unknown);
+    for pos in unknown {
+        for &(state, other) in &[
+            (CellState::Filled, CellState::Empty),
+            (CellState::Empty, CellState::Filled),
+        ] {
+            if contradicts(puzzle, &mut trial, pos, state) {
+                return Some((pos, other));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn finds_what_lines_miss() {
+        let mut found = 0;
+        let target = crate::generator::TechniqueTarget {
+            required: crate::solver::technique::Technique::CrossLine,
+            hardest: crate::solver::technique::Technique::CrossLine,
+        };
+        for mut rng in crate::strategies::seeds(606, 5) {
+            let puzzle = crate::generator::targeting(&mut rng, 8, 8, target, 1000).unwrap();
+            let mut solver = Solver::new(&puzzle);
+            solver.propagate().unwrap();
+            let grid = solver.grid().clone();
+
+            if let Some((pos, state)) = deduce(&puzzle, &grid) {
+                found += 1;
+                // Sound: the other value really is impossible, and no
+                // single line already knew it
+                let mut trial = Solver::from_grid(&puzzle, grid.clone());
+                trial.assume(pos, state != CellState::Filled);
+                assert!(trial.propagate().is_err());
+                assert_eq!(grid.get(pos), CellState::Unknown);
+                if let Some(goal) = puzzle.goal().filter(|_| super::super::is_unique(&puzzle)) {
+                    assert_eq!(goal.get(pos), state);
+                }
+            }
+        }
+        assert!(found > 0);
+    }
+
+    #[test]
+    fn trials_are_undone() {
+        let puzzle = Puzzle::from_goal(crate::spaces::grid::from_art(&["#.", "##"]));
+        let mut grid = Grid::new(2, 2);
+        assert!(!contradicts(
+            &puzzle,
+            &mut grid,
+            CellPos::new(1, 1),
+            CellState::Filled
+        ));
+        assert!(contradicts(
+            &puzzle,
+            &mut grid,
+            CellPos::new(1, 1),
+            CellState::Empty
+        ));
+        assert_eq!(grid, Grid::new(2, 2));
+    }
+}
@@ -0,0 +1,545 @@
+//! Typed records of what the solver concludes about single cells
+//!
+//! Line logic proposes each cell it solves as a [`Deduction`] before the
+//! grid changes, so a veto set with `Solver::set_veto` can hold a deduction
+//! back and `Solver::propagate_deductions` can hand them all to the caller.
+//!
+//! Proposing never changes anything: [`propose`] solves a single line against
+//! a grid, and `Solver::dry_run` runs a whole propagation on a copy. Only the
+//! solver's separate apply step writes to its grid, and `Grid::apply_all`
+//! replays deductions made elsewhere onto another grid.
+
+use super::events::SolverEvent;
+use super::technique::{self, Technique};
+use super::{line, Contradiction, SolveScratch, Solver};
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::{Line, LineId};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+/// Why a cell took its value
+///
+/// The discriminants from `code` and the names from `key` are stable, so
+/// they can be stored or sent to other programs. New reasons may be added.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum Reason {
+    CompletedLine = 0,
+    Overlap = 1,
+    Punctuation = 2,
+    EdgeLogic = 3,
+    CrossLine = 4,
+    Probing = 5,
+    /// Picked by the search, to be undone if it fails
+    Guess = 6,
+    /// The alternative of a failed guess
+    Backtrack = 7,
+    /// Part of the puzzle as set
+    Given = 8,
+}
+
+/// A cell, the value it takes and why
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Deduction {
+    pub cell: CellPos,
+    pub state: CellState,
+    pub reason: Reason,
+}
+
+/// Decides whether a proposed deduction may be applied
+pub type Veto = Arc<dyn Fn(&Deduction) -> bool + Send + Sync>;
+
+/// What `Grid::apply_all` did
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AppliedReport {
+    /// Deductions that solved a cell
+    pub applied: usize,
+    /// Deductions the grid already agreed with
+    pub unchanged: usize,
+}
+
+/// Why `Grid::apply_all` left the grid untouched, naming the deduction by index
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ApplyError {
+    OutOfBounds(usize),
+    /// The deduction leaves its cell unknown
+    NoValue(usize),
+    /// The cell already holds, or an earlier deduction gave it, another value
+    Conflict {
+        index: usize,
+        found: CellState,
+    },
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApplyError::OutOfBounds(i) => write!(f, "deduction {} is outside the grid", i),
+            ApplyError::NoValue(i) => write!(f, "deduction {} gives no value", i),
+            ApplyError::Conflict { index, found } => {
+                write!(f, "deduction {} conflicts with a {:?} cell", index, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+impl From<Technique> for Reason {
+    fn from(technique: Technique) -> Reason {
+        match technique {
+            Technique::CompletedLine => Reason::CompletedLine,
+            Technique::Overlap => Reason::Overlap,
+            Technique::Punctuation => Reason::Punctuation,
+            Technique::EdgeLogic => Reason::EdgeLogic,
+            Technique::CrossLine => Reason::CrossLine,
+            Technique::Probing => Reason::Probing,
+            Technique::Guess => Reason::Guess,
+        }
+    }
+}
+
+impl Reason {
+    /// Every reason, in code order
+    pub const ALL: [Reason; 9] = [
+        Reason::CompletedLine,
+        Reason::Overlap,
+        Reason::Punctuation,
+        Reason::EdgeLogic,
+        Reason::CrossLine,
+        Reason::Probing,
+        Reason::Guess,
+        Reason::Backtrack,
+        Reason::Given,
+    ];
+
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Reason> {
+        Reason::ALL.get(usize::from(code)).cloned()
+    }
+
+    /// Stable snake case name, as used in JSON
+    pub fn key(self) -> &'static str {
+        match self {
+            Reason::CompletedLine => "completed_line",
+            Reason::Overlap => "overlap",
+            Reason::Punctuation => "punctuation",
+            Reason::EdgeLogic => "edge_logic",
+            Reason::CrossLine => "cross_line",
+            Reason::Probing => "probing",
+            Reason::Guess => "guess",
+            Reason::Backtrack => "backtrack",
+            Reason::Given => "given",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Reason> {
+        Reason::ALL
+            .iter()
+            .cloned()
+            .find(|reason| reason.key() == key)
+    }
+
+    /// The technique behind the reason, `None` for backtracks and givens
+    pub fn technique(self) -> Option<Technique> {
+        match self {
+            Reason::CompletedLine => Some(Technique::CompletedLine),
+            Reason::Overlap => Some(Technique::Overlap),
+            Reason::Punctuation => Some(Technique::Punctuation),
+            Reason::EdgeLogic => Some(Technique::EdgeLogic),
+            Reason::CrossLine => Some(Technique::CrossLine),
+            Reason::Probing => Some(Technique::Probing),
+            Reason::Guess => Some(Technique::Guess),
+            Reason::Backtrack | Reason::Given => None,
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl SolverEvent {
+    /// The cell this event settles, if it settles one
+    pub fn deduction(&self) -> Option<Deduction> {
+        match *self {
+            SolverEvent::CellSolved {
+                pos,
+                state,
+                technique,
+                ..
+            } => Some(Deduction {
+                cell: pos,
+                state,
+                reason: technique.into(),
+            }),
+            SolverEvent::Guess { cell, .. } => Some(Deduction {
+                cell,
+                state: CellState::Filled,
+                reason: Reason::Guess,
+            }),
+            SolverEvent::Backtrack { cell, .. } => Some(Deduction {
+                cell,
+                state: CellState::Empty,
+                reason: Reason::Backtrack,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Grid {
+    /// Apply deductions made against another copy of the board, all or none
+    ///
+    /// Deductions the grid already agrees with are counted but change nothing,
+    /// so a client can replay a stream from a server over its own moves.
+    pub fn apply_all(&mut self, deductions: &[Deduction]) -> Result<AppliedReport, ApplyError> {
+        let mut pending = HashMap::new();
+        let mut report = AppliedReport::default();
+        for (i, deduction) in deductions.iter().enumerate() {
+            let pos = deduction.cell;
+            if pos.row >= self.height() || pos.col >= self.width() {
+                return Err(ApplyError::OutOfBounds(i));
+            }
+            if deduction.state == CellState::Unknown {
+                return Err(ApplyError::NoValue(i));
+            }
+            let found = pending.get(&pos).copied().unwrap_or_else(|| self.get(pos));
+            match found {
+                CellState::Unknown => {
+                    pending.insert(pos, deduction.state);
+                    report.applied += 1;
+                }
+                state if state == deduction.state => report.unchanged += 1,
+                found => return Err(ApplyError::Conflict { index: i, found }),
+            }
+        }
+
+        for (pos, state) in pending {
+            self.solve(pos, state == CellState::Filled);
+        }
+        Ok(report)
+    }
+}
+
+/// Deductions the clues of line `id` allow on `grid`, leaving the grid as it is
+pub fn propose(clues: &[usize], grid: &Grid, id: LineId) -> Result<Vec<Deduction>, Contradiction> {
+    let mut scratch = SolveScratch::default();
+    scratch.clues.extend_from_slice(clues);
+    propose_into(grid, id, &mut scratch)?;
+    Ok(scratch
+        .solved
+        .iter()
+        .map(|&(deduction, _)| deduction)
+        .collect())
+}
+
+// Propose the deductions the clues in `scratch.clues` allow on line `id` into
+// `scratch.solved`, leaving the feasible block starts in `scratch.line`
+pub(super) fn propose_into(
+    grid: &Grid,
+    id: LineId,
+    scratch: &mut SolveScratch,
+) -> Result<(), Contradiction> {
+    scratch.solved.clear();
+    let cells = grid.line(id);
+    if !line::solve_into(&scratch.clues, &cells, &mut scratch.line) {
+        return Err(Contradiction { line: id });
+    }
+    technique::classify(
+        &scratch.clues,
+        &cells,
+        scratch.line.cells(),
+        scratch.line.starts(),
+        &mut scratch.labels,
+    );
+
+    for (i, &state) in scratch.line.cells().iter().enumerate() {
+        if let Some(technique) = scratch.labels[i] {
+            let deduction = Deduction {
+                cell: grid.line_pos(id, i),
+                state,
+                reason: technique.into(),
+            };
+            scratch.solved.push((deduction, technique));
+        }
+    }
+    Ok(())
+}
+
+// Queue the unknown cells of blocks whose only window fits them exactly:
+// the window filled and the cells either side of it empty. A window the cells
+// already rule out is left for the next line solve to report.
+pub(super) fn window_fills(
+    line: &Line,
+    grid: &Grid,
+    id: LineId,
+    pending: &mut VecDeque<(LineId, Deduction, Technique)>,
+) {
+    let (cells, version) = (grid.line(id), grid.line_version(id));
+    for hint in line.hints() {
+        let start = match hint.exact() {
+            Some(start) => start,
+            None => continue,
+        };
+        if !hint.solutions()[0].is_valid_at(&cells, hint.hint(), version) {
+            continue;
+        }
+        let end = start + hint.hint();
+        let filled = (start..end).map(|i| (i, CellState::Filled, Technique::Overlap));
+        let sides = [start.checked_sub(1), Some(end).filter(|&i| i < line.len())];
+        let sides = sides
+            .iter()
+            .flatten()
+            .map(|&i| (i, CellState::Empty, Technique::Punctuation));
+        for (i, state, technique) in filled.chain(sides) {
+            let cell = grid.line_pos(id, i);
+            if grid.get(cell) == CellState::Unknown {
+                let reason = technique.into();
+                pending.push_back((
+                    id,
+                    Deduction {
+                        cell,
+                        state,
+                        reason,
+                    },
+                    technique,
+                ));
+            }
+        }
+    }
+}
+
+impl Solver {
+    /// Only apply line deductions `veto` allows, leaving the rest to the search
+    ///
+    /// Held back deductions are proposed again whenever their line is next
+    /// solved. Guesses and backtracks are not subject to the veto.
+    pub fn set_veto(&mut self, veto: Veto) {
+        self.veto = Some(veto);
+    }
+
+    pub(super) fn allows(&self, deduction: &Deduction) -> bool {
+        self.veto.as_ref().is_none_or(|veto| veto(deduction))
+    }
+
+    /// Like `propagate`, returning the deductions applied in order
+    pub fn propagate_deductions(&mut self) -> Result<Vec<Deduction>, Contradiction> {
+        let mut applied = Vec::new();
+        self.propagate_into(Some(&mut applied))?;
+        Ok(applied)
+    }
+
+    /// The deductions `propagate_deductions` would apply, without applying them
+    ///
+    /// Propagation runs on a copy of the solver that sends no events.
+    pub fn dry_run(&self) -> Result<Vec<Deduction>, Contradiction> {
+        let mut copy = self.clone();
+        copy.events = None;
+        copy.propagate_deductions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn propagation_reports_deductions() {
+        let goal = from_art(&["#.#", "###", "..#"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let mut solver = Solver::new(&puzzle);
+        let deductions = solver.propagate_deductions().unwrap();
+
+        assert_eq!(deductions.len(), 9);
+        for deduction in &deductions {
+            assert_eq!(goal.get(deduction.cell), deduction.state);
+            assert!(deduction.reason.technique().unwrap().is_line_technique());
+        }
+    }
+
+    #[test]
+    fn vetoed_cells_are_left_to_the_search() {
+        let goal = from_art(&["#.#", "###", "..#"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let corner = CellPos::new(0, 0);
+        let mut solver = Solver::new(&puzzle);
+        solver.set_veto(Arc::new(move |deduction: &Deduction| {
+            deduction.cell != corner
+        }));
+
+        let deductions = solver.propagate_deductions().unwrap();
+        assert!(deductions.iter().all(|deduction| deduction.cell != corner));
+        assert_eq!(solver.grid().get(corner), CellState::Unknown);
+        assert_eq!(solver.next_solution(), Some(goal));
+    }
+
+    #[test]
+    fn proposing_changes_nothing() {
+        let grid = Grid::new(5, 2);
+        let deductions = propose(&[2, 1], &grid, LineId::Row(1)).unwrap();
+
+        assert_eq!(deductions.len(), 1);
+        assert_eq!(deductions[0].cell, CellPos::new(1, 1));
+        assert_eq!(deductions[0].reason, Reason::Overlap);
+        let deductions = propose(&[3, 1], &grid, LineId::Row(1)).unwrap();
+        assert_eq!(deductions.len(), 5);
+        assert!(deductions.iter().all(|d| d.cell.row == 1));
+        assert_eq!(grid, Grid::new(5, 2));
+        assert_eq!(
+            propose(&[3, 2], &grid, LineId::Row(0)),
+            Err(Contradiction {
+                line: LineId::Row(0)
+            })
+        );
+    }
+
+    #[test]
+    fn dry_run_matches_propagation() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", "###", "..#"]));
+        let mut solver = Solver::new(&puzzle);
+        let proposed = solver.dry_run().unwrap();
+
+        assert_eq!(solver.grid().unknown_count(), 9);
+        assert_eq!(solver.stats().line_solves, 0);
+        assert_eq!(solver.propagate_deductions().unwrap(), proposed);
+    }
+
+    #[test]
+    fn replaying_on_a_client() {
+        let goal = from_art(&["#.#", "###", "..#"]);
+        let mut server = Solver::new(&Puzzle::from_goal(goal.clone()));
+        let deductions = server.propagate_deductions().unwrap();
+
+        let mut client = Grid::new(3, 3);
+        client.solve(CellPos::new(0, 0), true);
+        let report = client.apply_all(&deductions).unwrap();
+        assert_eq!(
+            report,
+            AppliedReport {
+                applied: 8,
+                unchanged: 1
+            }
+        );
+        assert_eq!(client, goal);
+    }
+
+    #[test]
+    fn conflicts_leave_the_grid_alone() {
+        let deduce = |row, col, state| Deduction {
+            cell: CellPos::new(row, col),
+            state,
+            reason: Reason::Given,
+        };
+        let mut grid = Grid::new(2, 2);
+        grid.solve(CellPos::new(1, 1), false);
+        let before = grid.clone();
+
+        let clash = [
+            deduce(0, 0, CellState::Filled),
+            deduce(1, 1, CellState::Filled),
+        ];
+        assert_eq!(
+            grid.apply_all(&clash),
+            Err(ApplyError::Conflict {
+                index: 1,
+                found: CellState::Empty
+            })
+        );
+        let twice = [
+            deduce(0, 1, CellState::Filled),
+            deduce(0, 1, CellState::Empty),
+        ];
+        assert_eq!(
+            grid.apply_all(&twice),
+            Err(ApplyError::Conflict {
+                index: 1,
+                found: CellState::Filled
+            })
+        );
+        let outside = [
+            deduce(0, 0, CellState::Empty),
+            deduce(2, 0, CellState::Empty),
+        ];
+        assert_eq!(grid.apply_all(&outside), Err(ApplyError::OutOfBounds(1)));
+        assert_eq!(
+            grid.apply_all(&[deduce(0, 0, CellState::Unknown)]),
+            Err(ApplyError::NoValue(0))
+        );
+        assert_eq!(grid, before);
+    }
+
+    #[test]
+    fn stable_codes_and_keys() {
+        for (i, &reason) in Reason::ALL.iter().enumerate() {
+            assert_eq!(usize::from(reason.code()), i);
+            assert_eq!(Reason::from_code(reason.code()), Some(reason));
+            assert_eq!(Reason::from_key(reason.key()), Some(reason));
+        }
+        assert_eq!(Reason::EdgeLogic.code(), 3);
+        assert_eq!(Reason::Given.key(), "given");
+        assert_eq!(Reason::from_code(9), None);
+        assert_eq!(Reason::from_key("edge logic"), None);
+    }
+
+    #[test]
+    fn events_convert() {
+        let guess = SolverEvent::Guess {
+            cell: CellPos::new(1, 2),
+            depth: 1,
+        };
+        assert_eq!(guess.deduction().unwrap().reason, Reason::Guess);
+        assert_eq!(SolverEvent::Finished { solutions: 1 }.deduction(), None);
+        assert_eq!(
+            Reason::from(Technique::Overlap).technique(),
+            Some(Technique::Overlap)
+        );
+    }
+
+    #[test]
+    fn exact_windows_fill() {
+        use crate::spaces::hint::HSoln;
+        let mut grid = Grid::new(6, 1);
+        grid.solve(CellPos::new(0, 2), true);
+        let mut line = Line::new(&[2], 6);
+        line.hints_mut()[0].set_solutions(vec![HSoln::new(1, 2)]);
+
+        let mut pending = VecDeque::new();
+        window_fills(&line, &grid, LineId::Row(0), &mut pending);
+        let cells: Vec<_> = pending
+            .iter()
+            .map(|(_, deduction, _)| (deduction.cell.col, deduction.state, deduction.reason))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                (1, CellState::Filled, Reason::Overlap),
+                (0, CellState::Empty, Reason::Punctuation),
+                (3, CellState::Empty, Reason::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn broken_exact_windows_are_skipped() {
+        use crate::spaces::hint::HSoln;
+        let mut grid = Grid::new(6, 1);
+        grid.solve(CellPos::new(0, 2), false);
+        let mut line = Line::new(&[2], 6);
+        line.hints_mut()[0].set_solutions(vec![HSoln::new(1, 2)]);
+
+        let mut pending = VecDeque::new();
+        window_fills(&line, &grid, LineId::Row(0), &mut pending);
+        assert!(pending.is_empty());
+    }
+}
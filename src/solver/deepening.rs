@@ -0,0 +1,129 @@
+//! Solving with a bounded depth of nested assumptions, deepening until the
+//! puzzle resolves
+//!
+//! Depth zero is plain line propagation. At depth `k` a value is ruled out
+//! when assuming it leads to a contradiction using depth `k - 1`, so depth
+//! one is the probing of `Solver::probe`.
+
+use super::{Contradiction, Solver};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+
+/// A puzzle solved without guessing, and the shallowest depth that did it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resolved {
+    pub grid: Grid,
+    pub depth: usize,
+}
+
+// Prove all that `depth` levels of assumption can, erring if the board
+// itself is contradictory
+pub(super) fn settle(solver: &mut Solver, depth: usize) -> Result<(), Contradiction> {
+    solver.propagate()?;
+    if depth == 0 {
+        return Ok(());
+    }
+
+    loop {
+        let mut changed = false;
+        let unknown: Vec<CellPos> = solver
+            .grid
+            .positions()
+            .filter(|&pos| !solver.grid.node(pos).is_solved())
+            .collect();
+        for pos in unknown {
+            if solver.grid.node(pos).is_solved() {
+                continue;
+            }
+            for &filled in &[true, false] {
+                let mut trial = solver.clone();
+                trial.events = None;
+                trial.assume(pos, filled);
+                if settle(&mut trial, depth - 1).is_err() {
+                    solver.assume(pos, !filled);
+                    solver.propagate()?;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        if !changed {
+            return Ok(());
+        }
+    }
+}
+
+/// Solve by deepening from zero up to `max_depth` levels of assumption
+///
+/// `None` if the puzzle is contradictory, has several solutions, or needs
+/// deeper reasoning than `max_depth`.
+pub fn solve(puzzle: &Puzzle, max_depth: usize) -> Option<Resolved> {
+    let mut solver = Solver::new(puzzle);
+    for depth in 0..=max_depth {
+        // What shallower depths proved still holds, so carry on from there
+        settle(&mut solver, depth).ok()?;
+        if solver.grid.is_complete() {
+            return Some(Resolved {
+                grid: solver.grid.clone(),
+                depth,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn line_solvable_is_depth_zero() {
+        let goal = from_art(&["###", "#.#", "###"]);
+        let resolved = solve(&Puzzle::from_goal(goal.clone()), 3).unwrap();
+        assert_eq!(
+            resolved,
+            Resolved {
+                grid: goal,
+                depth: 0
+            }
+        );
+    }
+
+    #[test]
+    fn depth_matches_probing() {
+        for mut rng in crate::strategies::seeds(605, 40) {
+            let puzzle = crate::strategies::puzzle(&mut rng, 8, 8);
+            let unique = super::super::is_unique(&puzzle);
+            match solve(&puzzle, 2) {
+                Some(resolved) => {
+                    assert!(unique);
+                    assert_eq!(Some(&resolved.grid), puzzle.goal());
+                    let mut solver = Solver::new(&puzzle);
+                    solver.propagate().unwrap();
+                    assert_eq!(resolved.depth == 0, solver.grid().is_complete());
+                }
+                None => {
+                    assert!(solve(&puzzle, 1).is_none());
+                }
+            }
+        }
+
+        // Cross-line logic needs an assumption, so depth one
+        use crate::generator::{targeting, TechniqueTarget};
+        use crate::solver::technique::Technique;
+        let target = TechniqueTarget {
+            required: Technique::CrossLine,
+            hardest: Technique::CrossLine,
+        };
+        let mut rng = crate::rng::Rng::new(605);
+        let puzzle = targeting(&mut rng, 8, 8, target, 1000).unwrap();
+        assert_eq!(solve(&puzzle, 3).unwrap().depth, 1);
+    }
+
+    #[test]
+    fn ambiguous_never_resolves() {
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        assert_eq!(solve(&puzzle, 4), None);
+    }
+}
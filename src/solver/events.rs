@@ -0,0 +1,113 @@
+use super::technique::Technique;
+use super::Solver;
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::sync::mpsc::Sender;
+
+/// Progress reported while solving
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolverEvent {
+    /// Line logic on `line` solved a cell
+    CellSolved {
+        pos: CellPos,
+        state: CellState,
+        line: LineId,
+        technique: Technique,
+    },
+    /// A cell was guessed filled, opening a branch `depth` guesses deep
+    Guess {
+        cell: CellPos,
+        depth: usize,
+    },
+    /// A guess failed and its alternative is being tried instead
+    Backtrack {
+        cell: CellPos,
+        depth: usize,
+    },
+    Contradiction {
+        line: LineId,
+    },
+    Solution(Grid),
+    /// Sent once by `solve_with_events` with the number of solutions found
+    Finished {
+        solutions: usize,
+    },
+}
+
+impl Solver {
+    /// Send every event from now on to `events`, even after the receiver hangs up
+    pub fn set_event_sender(&mut self, events: Sender<SolverEvent>) {
+        self.events = Some(events);
+    }
+
+    pub(super) fn emit(&self, event: impl FnOnce() -> SolverEvent) {
+        if let Some(events) = &self.events {
+            // A dropped receiver just means nobody is listening any more
+            let _ = events.send(event());
+        }
+    }
+}
+
+/// Solve a puzzle, streaming its progress over a channel
+pub fn solve_with_events(puzzle: &Puzzle, events: Sender<SolverEvent>) -> Option<Grid> {
+    let mut solver = Solver::new(puzzle);
+    solver.set_event_sender(events.clone());
+    let solution = solver.next_solution();
+    let _ = events.send(SolverEvent::Finished {
+        solutions: solution.is_some() as usize,
+    });
+    solution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    #[test]
+    fn events_cover_every_cell() {
+        let goal = from_art(&["#.#", "###", "..#"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let (tx, rx) = channel();
+
+        let handle = thread::spawn(move || solve_with_events(&puzzle, tx));
+        let events: Vec<SolverEvent> = rx.iter().collect();
+
+        assert_eq!(handle.join().unwrap(), Some(goal.clone()));
+        let solved = events
+            .iter()
+            .filter(|e| matches!(e, SolverEvent::CellSolved { .. }))
+            .count();
+        assert_eq!(solved, 9);
+        assert_eq!(events[events.len() - 2], SolverEvent::Solution(goal));
+        assert_eq!(events.last(), Some(&SolverEvent::Finished { solutions: 1 }));
+    }
+
+    #[test]
+    fn search_reports_guesses() {
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let (tx, rx) = channel();
+        let mut solver = Solver::new(&puzzle);
+        solver.set_event_sender(tx);
+        solver.solutions(10);
+        drop(solver);
+
+        let events: Vec<SolverEvent> = rx.iter().collect();
+        assert!(events.contains(&SolverEvent::Guess {
+            cell: CellPos::new(0, 0),
+            depth: 1
+        }));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SolverEvent::Backtrack { .. })));
+        let solutions = events
+            .iter()
+            .filter(|e| matches!(e, SolverEvent::Solution(_)))
+            .count();
+        assert_eq!(solutions, 2);
+    }
+}
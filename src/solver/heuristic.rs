@@ -0,0 +1,190 @@
+use super::Solver;
+use crate::rng::Rng;
+use crate::spaces::grid::CellPos;
+use crate::spaces::node::CellState;
+use crate::spaces::{Line, LineId};
+
+/// Decides which unsolved cell the search guesses next
+///
+/// The chosen cell is always guessed filled first, then empty on backtrack.
+pub trait BranchHeuristic: Send + Sync {
+    /// Pick an unsolved cell of `solver.grid()`, `None` only once every cell is solved
+    fn choose(&self, solver: &Solver) -> Option<CellPos>;
+}
+
+/// The first unsolved cell in row-major order
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FirstUnknown;
+
+/// The unsolved cell nearest the middle of the line with the fewest unknowns
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MostConstrainedLine;
+
+/// The unsolved cell most likely to be filled, judged by how many feasible
+/// block placements of its row and column cover it
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MostLikelyFilled;
+
+/// A random unsolved cell, the same on every run for a given seed and board
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Seeded {
+    pub seed: u64,
+}
+
+impl BranchHeuristic for FirstUnknown {
+    fn choose(&self, solver: &Solver) -> Option<CellPos> {
+        let grid = solver.grid();
+        grid.positions().find(|&pos| !grid.node(pos).is_solved())
+    }
+}
+
+impl BranchHeuristic for MostConstrainedLine {
+    fn choose(&self, solver: &Solver) -> Option<CellPos> {
+        let grid = solver.grid();
+        let (line, unknown) = grid
+            .lines()
+            .map(|line| {
+                let states = grid.line_states(line);
+                let unknown: Vec<usize> = (0..states.len())
+                    .filter(|&i| states[i] == CellState::Unknown)
+                    .collect();
+                (line, unknown)
+            })
+            .filter(|(_, unknown)| !unknown.is_empty())
+            .min_by_key(|(_, unknown)| unknown.len())?;
+
+        // Doubled to compare distances to a centre between two cells
+        let centre = grid.line_len(line) - 1;
+        let i = unknown
+            .into_iter()
+            .min_by_key(|&i| (2 * i).abs_diff(centre))?;
+        Some(grid.line_pos(line, i))
+    }
+}
+
+impl BranchHeuristic for Seeded {
+    fn choose(&self, solver: &Solver) -> Option<CellPos> {
+        let grid = solver.grid();
+        let unknown = grid.unknown_count();
+        if unknown == 0 {
+            return None;
+        }
+        // Mixing in the guesses so far keeps repeated boards from repeating choices
+        let mix = (unknown as u64) << 32 ^ solver.stats().guesses as u64;
+        let mut rng = Rng::new(self.seed ^ mix);
+        let pick = rng.below(unknown);
+        grid.positions()
+            .filter(|&pos| !grid.node(pos).is_solved())
+            .nth(pick)
+    }
+}
+
+// Expected number of blocks covering each cell if every feasible start of
+// each block were equally likely
+fn fill_estimate(line: &Line) -> Vec<f64> {
+    let mut estimate = vec![0.0; line.len()];
+    for hint in line.hints() {
+        let len = hint.hint();
+        let starts: Vec<usize> = hint
+            .solutions()
+            .iter()
+            .flat_map(|soln| soln.offset()..=soln.offset() + soln.length() - len)
+            .collect();
+        let weight = 1.0 / starts.len() as f64;
+        for start in starts {
+            estimate[start..start + len]
+                .iter_mut()
+                .for_each(|cell| *cell += weight);
+        }
+    }
+    estimate
+}
+
+impl BranchHeuristic for MostLikelyFilled {
+    fn choose(&self, solver: &Solver) -> Option<CellPos> {
+        let grid = solver.grid();
+        let estimates = |lines: &mut dyn Iterator<Item = LineId>| -> Vec<Option<Vec<f64>>> {
+            lines
+                .map(|line| solver.line(line).map(fill_estimate))
+                .collect()
+        };
+        let rows = estimates(&mut (0..grid.height()).map(LineId::Row));
+        let cols = estimates(&mut (0..grid.width()).map(LineId::Column));
+
+        // Unclued lines say nothing, so they count as even odds
+        let score = |pos: CellPos| {
+            let row = rows[pos.row].as_ref().map_or(0.5, |e| e[pos.col]);
+            let col = cols[pos.col].as_ref().map_or(0.5, |e| e[pos.row]);
+            row + col
+        };
+        grid.positions()
+            .filter(|&pos| !grid.node(pos).is_solved())
+            .fold(None, |best: Option<(CellPos, f64)>, pos| {
+                let score = score(pos);
+                match best {
+                    Some((_, top)) if top >= score => best,
+                    _ => Some((pos, score)),
+                }
+            })
+            .map(|(pos, _)| pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle;
+    use crate::puzzle::Puzzle;
+    use crate::strategies;
+    use std::sync::Arc;
+
+    fn heuristics() -> Vec<Arc<dyn BranchHeuristic>> {
+        vec![
+            Arc::new(FirstUnknown),
+            Arc::new(MostConstrainedLine),
+            Arc::new(Seeded { seed: 616 }),
+            Arc::new(MostLikelyFilled),
+        ]
+    }
+
+    #[test]
+    fn every_heuristic_finds_every_solution() {
+        for mut rng in strategies::seeds(587, 30) {
+            let puzzle = strategies::puzzle(&mut rng, 6, 6);
+            let mut expected = oracle::solutions(&puzzle, 50);
+            expected.sort_by_key(|grid| format!("{:?}", grid));
+
+            for heuristic in heuristics() {
+                let mut solver = Solver::new(&puzzle);
+                solver.set_heuristic(heuristic);
+                let mut found = solver.solutions(50);
+                found.sort_by_key(|grid| format!("{:?}", grid));
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn constrained_line_centre() {
+        // Nothing is deducible, every line has two unknowns
+        let puzzle = Puzzle::new(vec![vec![1]; 2], vec![vec![1]; 2]);
+        let mut solver = Solver::new(&puzzle);
+        solver.propagate().unwrap();
+
+        assert_eq!(
+            MostConstrainedLine.choose(&solver),
+            Some(CellPos::new(0, 0))
+        );
+        assert_eq!(FirstUnknown.choose(&solver), Some(CellPos::new(0, 0)));
+    }
+
+    #[test]
+    fn likely_filled_prefers_covered_cells() {
+        // A 2 in a line of 4 covers the middle cells twice as often as the ends
+        let puzzle = Puzzle::new(vec![vec![2]; 4], vec![vec![2]; 4]);
+        let mut solver = Solver::new(&puzzle);
+        solver.propagate().unwrap();
+
+        assert_eq!(MostLikelyFilled.choose(&solver), Some(CellPos::new(1, 1)));
+    }
+}
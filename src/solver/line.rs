@@ -0,0 +1,485 @@
+//! Solving one row or column on its own, independent of any puzzle or grid
+
+use crate::spaces::bits::{Bits, LineBits};
+use crate::spaces::node::CellState;
+use crate::spaces::slack;
+use crate::spaces::view::LineCells;
+use std::borrow::Cow;
+
+/// Every deduction a single line allows on its own
+pub struct LineSolution {
+    pub cells: Vec<CellState>,
+    /// Feasible start positions of each clue's block
+    pub starts: Vec<Vec<usize>>,
+}
+
+/// Buffers reused between line solves, holding the result of the last one
+#[derive(Clone, Debug, Default)]
+pub struct LineScratch {
+    bits: LineBits,
+    // Flattened (clues + 1) x (cells + 1) tables
+    prefix: Vec<bool>,
+    suffix: Vec<bool>,
+    covered: Bits,
+    cells: Vec<CellState>,
+    starts: Vec<Vec<usize>>,
+}
+
+impl LineScratch {
+    pub fn new() -> LineScratch {
+        LineScratch::default()
+    }
+
+    /// Cell states deduced by the last successful solve
+    pub fn cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    /// Feasible block starts found by the last successful solve
+    pub fn starts(&self) -> &[Vec<usize>] {
+        &self.starts
+    }
+}
+
+/// Solve a line as far as its clues allow, `None` if no arrangement fits
+pub fn solve<C: LineCells + ?Sized>(clues: &[usize], cells: &C) -> Option<LineSolution> {
+    let mut scratch = LineScratch::new();
+    if solve_into(clues, cells, &mut scratch) {
+        Some(LineSolution {
+            cells: scratch.cells,
+            starts: scratch.starts,
+        })
+    } else {
+        None
+    }
+}
+
+/// Solve a line reusing the scratch buffers, false if no arrangement fits
+pub fn solve_into<C: LineCells + ?Sized>(
+    clues: &[usize],
+    cells: &C,
+    scratch: &mut LineScratch,
+) -> bool {
+    if let Some(fits) = solve_trivial(clues, cells, scratch) {
+        return fits;
+    }
+    if !fits_segments(clues, cells) {
+        return false;
+    }
+
+    let n = cells.len();
+    let k = clues.len();
+    let w = n + 1;
+
+    scratch.bits.refill(cells);
+    let bits = &scratch.bits;
+    let fits = |start: usize, len: usize| bits.fits(start, len);
+    let open = |i: usize| !bits.filled().get(i);
+
+    // prefix[j][i]: first j clues can be placed within cells[..i]
+    let prefix = &mut scratch.prefix;
+    prefix.clear();
+    prefix.resize((k + 1) * w, false);
+    prefix[0] = true;
+    for i in 1..=n {
+        prefix[i] = prefix[i - 1] && open(i - 1);
+    }
+    for j in 1..=k {
+        let len = clues[j - 1];
+        let (done, rest) = prefix.split_at_mut(j * w);
+        let last = &done[(j - 1) * w..];
+        let row = &mut rest[..w];
+        for i in 1..=n {
+            row[i] = (open(i - 1) && row[i - 1])
+                || (i >= len && fits(i - len, len) && before(last, &open, i - len));
+        }
+    }
+
+    if !prefix[k * w + n] {
+        return false;
+    }
+
+    // suffix[j][i]: clues from j onward can be placed within cells[i..]
+    let suffix = &mut scratch.suffix;
+    suffix.clear();
+    suffix.resize((k + 1) * w, false);
+    suffix[k * w + n] = true;
+    for i in (0..n).rev() {
+        suffix[k * w + i] = suffix[k * w + i + 1] && open(i);
+    }
+    for j in (0..k).rev() {
+        let len = clues[j];
+        let (head, next) = suffix.split_at_mut((j + 1) * w);
+        let row = &mut head[j * w..];
+        let next = &next[..w];
+        for i in (0..n).rev() {
+            row[i] = (open(i) && row[i + 1]) || (fits(i, len) && after(next, &open, i + len, n));
+        }
+    }
+
+    // Mark every cell covered by a feasible block placement
+    scratch.covered.reset(n);
+    scratch.starts.resize_with(k, Vec::new);
+    for (j, &len) in clues.iter().enumerate() {
+        let feasible = &mut scratch.starts[j];
+        feasible.clear();
+        for s in 0..n {
+            if fits(s, len)
+                && before(&prefix[j * w..], &open, s)
+                && after(&suffix[(j + 1) * w..], &open, s + len, n)
+            {
+                feasible.push(s);
+                scratch.covered.set_range(s, s + len);
+            }
+        }
+    }
+
+    scratch.cells.clear();
+    for i in 0..n {
+        let cell = cells.state(i);
+        let can_fill = scratch.covered.get(i);
+        let can_empty = open(i) && (0..=k).any(|j| prefix[j * w + i] && suffix[j * w + i + 1]);
+        scratch.cells.push(match (can_fill, can_empty) {
+            (true, true) => cell,
+            (true, false) => CellState::Filled,
+            (false, true) => CellState::Empty,
+            (false, false) => return false,
+        });
+    }
+
+    true
+}
+
+/// Outcome of [`solve_line`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineResult {
+    /// No arrangement of the clues fits, and the cells were left alone
+    Contradiction,
+    /// Nothing new could be deduced
+    Unchanged,
+    /// This many unknown cells were solved
+    Changed(usize),
+}
+
+// A 0 clue places no block, as in `Puzzle::from_lines`
+fn without_zeros(clues: &[usize]) -> Cow<'_, [usize]> {
+    if clues.contains(&0) {
+        Cow::Owned(clues.iter().copied().filter(|&clue| clue > 0).collect())
+    } else {
+        Cow::Borrowed(clues)
+    }
+}
+
+/// Solve the cells in place as far as the clues allow
+///
+/// Needs nothing beyond the clues and the cells, so it can be used without a
+/// puzzle. An empty clue list, or one of only zeros, means a line with no
+/// filled cells.
+pub fn solve_line(clues: &[usize], cells: &mut [CellState]) -> LineResult {
+    let solved = match solve(&without_zeros(clues), &*cells) {
+        Some(solved) => solved,
+        None => return LineResult::Contradiction,
+    };
+    let mut changed = 0;
+    for (cell, &state) in cells.iter_mut().zip(&solved.cells) {
+        if *cell != state {
+            *cell = state;
+            changed += 1;
+        }
+    }
+    match changed {
+        0 => LineResult::Unchanged,
+        n => LineResult::Changed(n),
+    }
+}
+
+/// Earliest and latest start of each block given the cells known so far,
+/// `None` if no arrangement fits
+///
+/// Zero clues place no block and get no bounds.
+pub fn start_bounds<C: LineCells + ?Sized>(
+    clues: &[usize],
+    cells: &C,
+) -> Option<Vec<(usize, usize)>> {
+    let solved = solve(&without_zeros(clues), cells)?;
+    // Every clue has at least one feasible start once the line is solvable
+    let bounds = solved
+        .starts
+        .iter()
+        .map(|starts| (starts[0], starts[starts.len() - 1]))
+        .collect();
+    Some(bounds)
+}
+
+/// Whether the clues, in order and with their gaps, could be packed into the
+/// runs of cells not known to be empty
+///
+/// Filled cells are ignored, so a line passing this may still have no
+/// arrangement; a line failing it never has one.
+pub fn fits_segments<C: LineCells + ?Sized>(clues: &[usize], cells: &C) -> bool {
+    let mut clues = clues.iter().peekable();
+    let mut i = 0;
+    while i < cells.len() && clues.peek().is_some() {
+        if cells.state(i) == CellState::Empty {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < cells.len() && cells.state(i) != CellState::Empty {
+            i += 1;
+        }
+        // Pack greedily from the left of the segment
+        let mut free = i - start + 1;
+        while let Some(&&clue) = clues.peek() {
+            if clue + 1 > free {
+                break;
+            }
+            free -= clue + 1;
+            clues.next();
+        }
+    }
+    clues.peek().is_none()
+}
+
+// Lines with no clues or no slack have only one arrangement, so skip the
+// tables and check the cells against it directly. `None` for other lines.
+fn solve_trivial<C: LineCells + ?Sized>(
+    clues: &[usize],
+    cells: &C,
+    scratch: &mut LineScratch,
+) -> Option<bool> {
+    let n = cells.len();
+    match slack::slack(clues, n) {
+        None => return Some(false),
+        Some(slack) if slack > 0 && !clues.is_empty() => return None,
+        Some(_) => {}
+    }
+
+    scratch.cells.clear();
+    scratch.cells.resize(n, CellState::Empty);
+    scratch.starts.resize_with(clues.len(), Vec::new);
+    for ((&len, start), feasible) in clues
+        .iter()
+        .zip(slack::earliest_starts(clues))
+        .zip(&mut scratch.starts)
+    {
+        feasible.clear();
+        feasible.push(start);
+        scratch.cells[start..start + len]
+            .iter_mut()
+            .for_each(|cell| *cell = CellState::Filled);
+    }
+    let fits = (0..n).all(|i| {
+        let cell = cells.state(i);
+        cell == CellState::Unknown || cell == scratch.cells[i]
+    });
+    Some(fits)
+}
+
+/// Where a clue stands given the cells known so far
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClueStatus {
+    /// Its block has only one place left and every cell of it is filled
+    Satisfied,
+    /// Its block has only one place left, not all of it filled yet
+    Placed,
+    Open,
+}
+
+/// Status of each nonzero clue in a line, `None` if no arrangement fits the cells
+pub fn clue_status<C: LineCells + ?Sized>(clues: &[usize], cells: &C) -> Option<Vec<ClueStatus>> {
+    let clues = without_zeros(clues);
+    let solved = solve(&clues, cells)?;
+    let status = solved
+        .starts
+        .iter()
+        .zip(clues.iter())
+        .map(|(starts, &len)| match starts[..] {
+            [start] if (start..start + len).all(|i| cells.state(i) == CellState::Filled) => {
+                ClueStatus::Satisfied
+            }
+            [_] => ClueStatus::Placed,
+            _ => ClueStatus::Open,
+        })
+        .collect();
+    Some(status)
+}
+
+// A block starting at `start` is preceded by a gap and the earlier clues
+fn before(prefix: &[bool], open: &impl Fn(usize) -> bool, start: usize) -> bool {
+    if start == 0 {
+        prefix[0]
+    } else {
+        open(start - 1) && prefix[start - 1]
+    }
+}
+
+// A block ending at `end` is followed by a gap and the later clues
+fn after(suffix: &[bool], open: &impl Fn(usize) -> bool, end: usize, n: usize) -> bool {
+    if end == n {
+        suffix[n]
+    } else {
+        open(end) && suffix[end + 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CellState::*;
+
+    fn parse(line: &str) -> Vec<CellState> {
+        line.chars()
+            .map(|c| match c {
+                '#' => Filled,
+                '.' => Empty,
+                _ => Unknown,
+            })
+            .collect()
+    }
+
+    fn check(clues: &[usize], line: &str, expected: &str) {
+        let soln = solve(clues, &parse(line)).expect("Line should be solvable");
+        assert_eq!(soln.cells, parse(expected));
+    }
+
+    #[test]
+    fn overlap() {
+        check(&[7], "??????????", "???####???");
+        check(&[3, 3], "???????", "###.###");
+    }
+
+    #[test]
+    fn clue_statuses() {
+        use ClueStatus::*;
+        assert_eq!(
+            clue_status(&[2, 1], &parse("##.????")),
+            Some(vec![Satisfied, Open])
+        );
+        assert_eq!(
+            clue_status(&[2, 3], &parse("???.###")),
+            Some(vec![Open, Satisfied])
+        );
+        assert_eq!(
+            clue_status(&[3, 3], &parse("???????")),
+            Some(vec![Placed, Placed])
+        );
+        assert_eq!(clue_status(&[1], &parse("##")), None);
+        assert_eq!(clue_status(&[], &parse("...")), Some(vec![]));
+    }
+
+    #[test]
+    fn empty_clues() {
+        check(&[], "????", "....");
+    }
+
+    #[test]
+    fn edge_logic() {
+        check(&[3], "?#????????", "?##?......");
+    }
+
+    #[test]
+    fn completed_line() {
+        check(&[1, 2], "#???##??", "#...##..");
+    }
+
+    #[test]
+    fn trivial_lines() {
+        check(&[2, 1, 1], "??????", "##.#.#");
+        check(&[5], "??#??", "#####");
+        check(&[], "?.??", "....");
+        assert!(solve(&[2, 1], &parse("?.??")).is_none());
+        assert!(solve(&[], &parse("?#?")).is_none());
+        assert_eq!(
+            solve(&[1, 1], &parse("???")).unwrap().starts,
+            vec![vec![0], vec![2]]
+        );
+    }
+
+    #[test]
+    fn gaps_too_small() {
+        check(&[2], "?.??.?", "..##..");
+    }
+
+    #[test]
+    fn contradiction() {
+        assert!(solve(&[3], &parse("?.?.?")).is_none());
+        assert!(solve(&[1], &parse("#?#")).is_none());
+        assert!(solve(&[4], &parse("???")).is_none());
+    }
+
+    #[test]
+    fn scratch_reuse() {
+        let mut scratch = LineScratch::new();
+
+        assert!(solve_into(&[2, 2], &parse("??????"), &mut scratch));
+        assert!(solve_into(&[3], &parse("????"), &mut scratch));
+        assert_eq!(scratch.cells(), &parse("?##?")[..]);
+        assert_eq!(scratch.starts(), &[vec![0, 1]]);
+        assert!(!solve_into(&[5], &parse("????"), &mut scratch));
+    }
+
+    #[test]
+    fn bounds_with_fixed_cells() {
+        assert_eq!(
+            start_bounds(&[3, 1, 2], &parse("??????????")),
+            Some(vec![(0, 2), (4, 6), (6, 8)])
+        );
+        assert_eq!(
+            start_bounds(&[2, 1], &parse("???#?.?")),
+            Some(vec![(0, 3), (3, 6)])
+        );
+        assert_eq!(start_bounds(&[2], &parse("?.?")), None);
+    }
+
+    #[test]
+    fn feasible_starts() {
+        let soln = solve(&[2, 1], &parse("?????")).unwrap();
+        assert_eq!(soln.starts, vec![vec![0, 1], vec![3, 4]]);
+    }
+
+    #[test]
+    fn solve_line_in_place() {
+        let mut cells = parse("??????????");
+        assert_eq!(solve_line(&[8], &mut cells), LineResult::Changed(6));
+        assert_eq!(cells, parse("??######??"));
+        assert_eq!(solve_line(&[8], &mut cells), LineResult::Unchanged);
+
+        let mut cells = parse("?#.#?");
+        assert_eq!(solve_line(&[3], &mut cells), LineResult::Contradiction);
+        assert_eq!(cells, parse("?#.#?"));
+
+        let mut cells = parse("???");
+        assert_eq!(solve_line(&[], &mut cells), LineResult::Changed(3));
+        assert_eq!(cells, parse("..."));
+    }
+
+    #[test]
+    fn segment_capacity() {
+        assert!(fits_segments(&[2, 1], &parse("???.?")));
+        assert!(fits_segments(&[], &parse("...")));
+        assert!(!fits_segments(&[2, 2], &parse("???.?")));
+        assert!(!fits_segments(&[3], &parse("??.??.??")));
+        // Filled cells are not checked
+        assert!(fits_segments(&[1], &parse("##")));
+        assert!(solve(&[2, 2], &parse("???.?")).is_none());
+    }
+
+    #[test]
+    fn zero_clues_place_no_block() {
+        let mut zeros = parse("????");
+        let mut plain = zeros.clone();
+        assert_eq!(solve_line(&[0, 2], &mut zeros), LineResult::Unchanged);
+        assert_eq!(solve_line(&[2], &mut plain), LineResult::Unchanged);
+        assert_eq!(zeros, plain);
+
+        let mut cells = parse("??");
+        assert_eq!(solve_line(&[0], &mut cells), LineResult::Changed(2));
+        assert_eq!(cells, parse(".."));
+
+        let cells = parse("?#??");
+        assert_eq!(start_bounds(&[0, 2, 0], &cells), start_bounds(&[2], &cells));
+        assert_eq!(clue_status(&[0], &parse("..")), Some(Vec::new()));
+        assert_eq!(clue_status(&[2, 0], &cells), clue_status(&[2], &cells));
+    }
+}
@@ -0,0 +1,207 @@
+use super::{Snapshot, Solver};
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Longest assumption set worth minimizing and keeping
+const MAX_LEN: usize = 10;
+
+/// Cell values that together force a contradiction
+pub type Nogood = Vec<(CellPos, CellState)>;
+
+/// Bounded store of learned nogoods, oldest evicted first
+///
+/// Clones share the same store, so one store can serve several workers
+/// searching the same puzzle. Nogoods only hold for the puzzle they were
+/// learned on.
+#[derive(Clone, Debug)]
+pub struct NogoodStore {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    nogoods: VecDeque<Nogood>,
+    capacity: usize,
+}
+
+impl NogoodStore {
+    pub fn new(capacity: usize) -> NogoodStore {
+        NogoodStore {
+            inner: Arc::new(Mutex::new(Inner {
+                nogoods: VecDeque::new(),
+                capacity,
+            })),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().nogoods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of every nogood currently kept, oldest first
+    pub fn nogoods(&self) -> Vec<Nogood> {
+        self.inner.lock().unwrap().nogoods.iter().cloned().collect()
+    }
+
+    pub fn record(&self, mut nogood: Nogood) {
+        nogood.sort_by_key(|&(pos, _)| (pos.row, pos.col));
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 || inner.nogoods.contains(&nogood) {
+            return;
+        }
+        if inner.nogoods.len() == inner.capacity {
+            inner.nogoods.pop_front();
+        }
+        inner.nogoods.push_back(nogood);
+    }
+
+    /// Whether every cell of some nogood already holds its value in `grid`
+    pub fn violated_by(&self, grid: &Grid) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .nogoods
+            .iter()
+            .any(|nogood| nogood.iter().all(|&(pos, state)| grid.get(pos) == state))
+    }
+}
+
+impl Default for NogoodStore {
+    fn default() -> NogoodStore {
+        NogoodStore::new(1024)
+    }
+}
+
+impl Solver {
+    /// Learn nogoods into `store` when guesses fail, pruning branches that repeat them
+    pub fn set_nogoods(&mut self, store: NogoodStore) {
+        self.nogoods = Some(store);
+    }
+
+    pub(super) fn violates_nogood(&self) -> bool {
+        self.nogoods
+            .as_ref()
+            .is_some_and(|store| store.violated_by(&self.grid))
+    }
+
+    // Whether the assumptions alone contradict the clues, starting from the root
+    fn contradicts(&self, root: &Snapshot, assumptions: &[(CellPos, bool)]) -> bool {
+        let mut trial = self.fork(root.clone());
+        for &(pos, filled) in assumptions {
+            match trial.grid.get(pos) {
+                CellState::Unknown => trial.assume(pos, filled),
+                state if (state == CellState::Filled) != filled => return true,
+                _ => {}
+            }
+        }
+        trial.propagate().is_err()
+    }
+
+    // Shrink the assumptions on the trail to a minimal failing set and store it
+    pub(super) fn learn(&mut self) {
+        let (store, root) = match (&self.nogoods, &self.root) {
+            (Some(store), Some(root)) => (store, root),
+            _ => return,
+        };
+        if self.trail.len() > MAX_LEN {
+            return;
+        }
+
+        let mut set = self.trail.clone();
+        let mut i = 0;
+        while i < set.len() {
+            let mut without = set.clone();
+            without.remove(i);
+            if self.contradicts(root, &without) {
+                set = without;
+            } else {
+                i += 1;
+            }
+        }
+        let nogood = set
+            .into_iter()
+            .map(|(pos, filled)| {
+                let state = if filled {
+                    CellState::Filled
+                } else {
+                    CellState::Empty
+                };
+                (pos, state)
+            })
+            .collect();
+        store.record(nogood);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::strategies;
+
+    #[test]
+    fn store_is_bounded_and_shared() {
+        let store = NogoodStore::new(2);
+        let shared = store.clone();
+        for col in 0..3 {
+            shared.record(vec![(CellPos::new(0, col), CellState::Filled)]);
+        }
+        store.record(vec![(CellPos::new(0, 2), CellState::Filled)]);
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.nogoods()[0],
+            vec![(CellPos::new(0, 1), CellState::Filled)]
+        );
+
+        let mut grid = Grid::new(3, 1);
+        assert!(!store.violated_by(&grid));
+        grid.solve(CellPos::new(0, 2), true);
+        assert!(store.violated_by(&grid));
+    }
+
+    #[test]
+    fn learning_keeps_search_complete() {
+        let mut learned = 0;
+        for mut rng in strategies::seeds(589, 60) {
+            let puzzle = Puzzle::from_goal(strategies::goal(&mut rng, 15, 15, 0.5));
+            let mut expected = Solver::new(&puzzle).solutions(20);
+            expected.sort_by_key(|grid| format!("{:?}", grid));
+
+            let store = NogoodStore::default();
+            let mut solver = Solver::new(&puzzle);
+            solver.set_nogoods(store.clone());
+            let mut found = solver.solutions(20);
+            found.sort_by_key(|grid| format!("{:?}", grid));
+
+            assert_eq!(found, expected);
+            assert!(store.nogoods().iter().all(|nogood| nogood.len() <= MAX_LEN));
+            learned += store.len();
+        }
+        assert!(learned > 0);
+    }
+
+    #[test]
+    fn split_solvers_share_the_store() {
+        let puzzle = Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4]);
+        let store = NogoodStore::default();
+        let mut solver = Solver::new(&puzzle);
+        solver.set_nogoods(store.clone());
+        solver.step();
+        solver.step();
+
+        let other = solver.split_off().unwrap();
+        let nogood = vec![(CellPos::new(3, 3), CellState::Filled)];
+        other.nogoods.as_ref().unwrap().record(nogood.clone());
+        assert_eq!(store.nogoods(), vec![nogood]);
+    }
+}
@@ -0,0 +1,191 @@
+use super::{Solver, Step};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::Grid;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+struct Pool {
+    queue: Vec<Solver>,
+    // Workers waiting for something to do
+    idle: usize,
+    solutions: Vec<Grid>,
+    done: bool,
+}
+
+struct Shared {
+    pool: Mutex<Pool>,
+    wake: Condvar,
+    threads: usize,
+    limit: usize,
+}
+
+impl Shared {
+    // Block until work is available, `None` once the search is over
+    fn take(&self) -> Option<Solver> {
+        let mut pool = self.pool.lock().unwrap();
+        pool.idle += 1;
+        loop {
+            if pool.done {
+                return None;
+            }
+            if let Some(solver) = pool.queue.pop() {
+                pool.idle -= 1;
+                return Some(solver);
+            }
+            if pool.idle == self.threads {
+                // Nobody is left to produce work
+                pool.done = true;
+                self.wake.notify_all();
+                return None;
+            }
+            pool = self.wake.wait(pool).unwrap();
+        }
+    }
+
+    fn hungry(&self) -> bool {
+        let pool = self.pool.lock().unwrap();
+        pool.idle > 0 && pool.queue.is_empty()
+    }
+
+    fn give(&self, solver: Solver) {
+        self.pool.lock().unwrap().queue.push(solver);
+        self.wake.notify_one();
+    }
+
+    // Record a solution, returning whether the search should stop
+    fn found(&self, grid: Grid) -> bool {
+        let mut pool = self.pool.lock().unwrap();
+        if pool.solutions.len() < self.limit {
+            pool.solutions.push(grid);
+        }
+        if pool.solutions.len() >= self.limit {
+            pool.done = true;
+            self.wake.notify_all();
+        }
+        pool.done
+    }
+
+    fn is_done(&self) -> bool {
+        self.pool.lock().unwrap().done
+    }
+
+    fn work(&self) {
+        while let Some(mut solver) = self.take() {
+            loop {
+                match solver.step() {
+                    Step::Solution(grid) => {
+                        if self.found(grid) {
+                            return;
+                        }
+                    }
+                    Step::Exhausted => break,
+                    Step::Progress => {
+                        if self.hungry() {
+                            if let Some(other) = solver.split_off() {
+                                self.give(other);
+                            }
+                        } else if self.is_done() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Find up to `limit` solutions, sharing open branches between `threads` workers
+///
+/// Solutions are returned in the order they were found, which varies between runs.
+pub fn solutions(puzzle: &Puzzle, limit: usize, threads: usize) -> Vec<Grid> {
+    search(Solver::new(puzzle), limit, threads)
+}
+
+/// Like `solutions`, starting from a configured solver
+///
+/// Branches handed to other workers keep the solver's heuristic and nogood
+/// store, so every worker learns into and prunes with the same store.
+pub fn search(root: Solver, limit: usize, threads: usize) -> Vec<Grid> {
+    let threads = threads.max(1);
+    if limit == 0 {
+        return Vec::new();
+    }
+
+    let shared = Shared {
+        pool: Mutex::new(Pool {
+            queue: vec![root],
+            idle: 0,
+            solutions: Vec::new(),
+            done: false,
+        }),
+        wake: Condvar::new(),
+        threads,
+        limit,
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| shared.work());
+        }
+    });
+
+    shared.pool.into_inner().unwrap().solutions
+}
+
+/// Solve using every available core
+pub fn solve(puzzle: &Puzzle) -> Option<Grid> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    solutions(puzzle, 1, threads).pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::nogood::NogoodStore;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn finds_every_solution() {
+        let puzzle = Puzzle::new(vec![vec![1]; 4], vec![vec![1]; 4]);
+
+        let mut expected = Solver::new(&puzzle).solutions(100);
+        let mut found = solutions(&puzzle, 100, 4);
+        let key = |grid: &Grid| format!("{:?}", grid);
+        expected.sort_by_key(key);
+        found.sort_by_key(key);
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn stops_at_limit() {
+        let puzzle = Puzzle::new(vec![vec![1]; 5], vec![vec![1]; 5]);
+        let found = solutions(&puzzle, 7, 3);
+
+        assert_eq!(found.len(), 7);
+        assert!(found.iter().all(|grid| puzzle.is_solved_by(grid)));
+    }
+
+    #[test]
+    fn workers_share_nogoods() {
+        let mut rng = crate::rng::Rng::new(589);
+        let goal = crate::strategies::goal(&mut rng, 15, 15, 0.5);
+        let puzzle = Puzzle::from_goal(goal);
+        let store = NogoodStore::default();
+        let mut root = Solver::new(&puzzle);
+        root.set_nogoods(store.clone());
+
+        let found = search(root, 10, 4);
+        assert_eq!(found.len(), Solver::new(&puzzle).solutions(10).len());
+        assert!(found.iter().all(|grid| puzzle.is_solved_by(grid)));
+    }
+
+    #[test]
+    fn unique_and_unsolvable() {
+        let goal = from_art(&["##.", ".##", "#.#"]);
+        assert_eq!(solve(&Puzzle::from_goal(goal.clone())), Some(goal));
+
+        let broken = Puzzle::new(vec![vec![2], vec![]], vec![vec![1], vec![]]);
+        assert!(solutions(&broken, 5, 2).is_empty());
+    }
+}
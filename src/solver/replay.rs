@@ -0,0 +1,309 @@
+use super::events::SolverEvent;
+use super::technique::Technique;
+use super::{crossline, line, Solver};
+use crate::bytes::{write_varint, ByteReader};
+use crate::puzzle::Puzzle;
+use crate::spaces::grid::{CellPos, Grid};
+use crate::spaces::node::CellState;
+use crate::spaces::LineId;
+use std::fmt;
+use std::sync::mpsc::channel;
+
+const MAGIC: &[u8; 4] = b"GRDR";
+const VERSION: u8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReplayStep {
+    pub pos: CellPos,
+    pub state: CellState,
+    pub technique: Technique,
+}
+
+/// Ordered deductions taking a blank board to the solution
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay {
+    width: usize,
+    height: usize,
+    steps: Vec<ReplayStep>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Corrupt,
+    SizeMismatch,
+    /// The step solves a cell an earlier step already solved
+    AlreadySolved(usize),
+    /// The step's technique does not actually force it
+    NotDeducible(usize),
+    /// Every step applied but the board does not solve the puzzle
+    NotSolved,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::BadMagic => write!(f, "not a replay"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {}", v),
+            ReplayError::Truncated => write!(f, "replay ended early"),
+            ReplayError::Corrupt => write!(f, "replay contains invalid data"),
+            ReplayError::SizeMismatch => write!(f, "replay is for a board of another size"),
+            ReplayError::AlreadySolved(i) => write!(f, "step {} solves a solved cell", i),
+            ReplayError::NotDeducible(i) => write!(f, "step {} is not forced by its lines", i),
+            ReplayError::NotSolved => write!(f, "replay does not solve the puzzle"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// Solve a puzzle and record the deductions leading to its first solution
+    pub fn record(puzzle: &Puzzle) -> Option<Replay> {
+        let solution = super::solve(puzzle)?;
+        let (tx, rx) = channel();
+        let mut solver = Solver::new(puzzle);
+        solver.set_event_sender(tx);
+
+        let mut steps = Vec::new();
+        loop {
+            solver.propagate().ok()?;
+            steps.extend(rx.try_iter().filter_map(|event| match event {
+                SolverEvent::CellSolved {
+                    pos,
+                    state,
+                    technique,
+                    ..
+                } => Some(ReplayStep {
+                    pos,
+                    state,
+                    technique,
+                }),
+                _ => None,
+            }));
+
+            if solver.grid().is_complete() {
+                break;
+            }
+            // Prefer cross-line logic, then a probed cell, falling back to
+            // the solution's value
+            let crossed = crossline::deduce(puzzle, solver.grid())
+                .map(|(pos, state)| (pos, state == CellState::Filled, Technique::CrossLine));
+            let probed = || {
+                solver
+                    .probe()
+                    .map(|(pos, filled)| (pos, filled, Technique::Probing))
+            };
+            let (pos, filled, technique) = match crossed.or_else(probed) {
+                Some(step) => step,
+                None => {
+                    let grid = solver.grid();
+                    let pos = grid.positions().find(|&pos| !grid.node(pos).is_solved())?;
+                    (
+                        pos,
+                        solution.get(pos) == CellState::Filled,
+                        Technique::Guess,
+                    )
+                }
+            };
+            steps.push(ReplayStep {
+                pos,
+                state: if filled {
+                    CellState::Filled
+                } else {
+                    CellState::Empty
+                },
+                technique,
+            });
+            solver.assume(pos, filled);
+        }
+
+        Some(Replay {
+            width: puzzle.width(),
+            height: puzzle.height(),
+            steps,
+        })
+    }
+
+    pub fn steps(&self) -> &[ReplayStep] {
+        &self.steps
+    }
+
+    /// Apply the first `count` steps to a blank board, leaving out any given cells
+    pub fn board_after(&self, count: usize) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        for step in &self.steps[..count] {
+            grid.solve(step.pos, step.state == CellState::Filled);
+        }
+        grid
+    }
+
+    /// Check every step against the puzzle, ending on a solution
+    pub fn validate(&self, puzzle: &Puzzle) -> Result<(), ReplayError> {
+        if self.width != puzzle.width() || self.height != puzzle.height() {
+            return Err(ReplayError::SizeMismatch);
+        }
+
+        let mut grid = puzzle.givens().clone();
+        for (i, step) in self.steps.iter().enumerate() {
+            if grid.node(step.pos).is_solved() || step.state == CellState::Unknown {
+                return Err(ReplayError::AlreadySolved(i));
+            }
+            let valid = match step.technique {
+                Technique::Guess => true,
+                Technique::Probing | Technique::CrossLine => probed(puzzle, &grid, step),
+                _ => forced(puzzle, &grid, step),
+            };
+            if !valid {
+                return Err(ReplayError::NotDeducible(i));
+            }
+            grid.solve(step.pos, step.state == CellState::Filled);
+        }
+
+        if puzzle.is_solved_by(&grid) {
+            Ok(())
+        } else {
+            Err(ReplayError::NotSolved)
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_varint(&mut out, self.width as u64);
+        write_varint(&mut out, self.height as u64);
+        write_varint(&mut out, self.steps.len() as u64);
+        for step in &self.steps {
+            write_varint(&mut out, (step.pos.row * self.width + step.pos.col) as u64);
+            let filled = (step.state == CellState::Filled) as u8;
+            out.push(filled | (step.technique as u8) << 1);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut r = ByteReader::new(bytes);
+        if r.take(4) != Some(&MAGIC[..]) {
+            return Err(ReplayError::BadMagic);
+        }
+        match r.u8().ok_or(ReplayError::Truncated)? {
+            VERSION => {}
+            version => return Err(ReplayError::UnsupportedVersion(version)),
+        }
+
+        let mut varint = || r.varint().map(|v| v as usize).ok_or(ReplayError::Truncated);
+        let (width, height, count) = (varint()?, varint()?, varint()?);
+        let mut steps = Vec::new();
+        for _ in 0..count {
+            let index = r.varint().ok_or(ReplayError::Truncated)? as usize;
+            let flags = r.u8().ok_or(ReplayError::Truncated)?;
+            let technique = Technique::from_u8(flags >> 1);
+            let technique = match technique {
+                Some(technique) if index < width * height => technique,
+                _ => return Err(ReplayError::Corrupt),
+            };
+            steps.push(ReplayStep {
+                pos: CellPos::new(index / width, index % width),
+                state: if flags & 1 == 1 {
+                    CellState::Filled
+                } else {
+                    CellState::Empty
+                },
+                technique,
+            });
+        }
+        if !r.is_done() {
+            return Err(ReplayError::Corrupt);
+        }
+
+        Ok(Replay {
+            width,
+            height,
+            steps,
+        })
+    }
+}
+
+// Whether the row or column through the step's cell forces its state
+fn forced(puzzle: &Puzzle, grid: &Grid, step: &ReplayStep) -> bool {
+    [LineId::Row(step.pos.row), LineId::Column(step.pos.col)]
+        .iter()
+        .any(|&id| {
+            let clues = match puzzle.clues(id) {
+                Some(clues) => clues,
+                None => return false,
+            };
+            let i = match id {
+                LineId::Row(_) => step.pos.col,
+                LineId::Column(_) => step.pos.row,
+            };
+            line::solve(clues, &grid.line(id)).is_some_and(|soln| soln.cells[i] == step.state)
+        })
+}
+
+// Whether assuming the opposite value leads to a contradiction
+fn probed(puzzle: &Puzzle, grid: &Grid, step: &ReplayStep) -> bool {
+    let mut trial = Solver::from_grid(puzzle, grid.clone());
+    trial.assume(step.pos, step.state != CellState::Filled);
+    trial.propagate().is_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn line_solvable_replay() {
+        let goal = from_art(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let puzzle = Puzzle::from_goal(goal.clone());
+        let replay = Replay::record(&puzzle).unwrap();
+
+        assert_eq!(replay.steps().len(), 25);
+        assert!(replay
+            .steps()
+            .iter()
+            .all(|s| s.technique.is_line_technique()));
+        assert_eq!(replay.board_after(25), goal);
+        assert_eq!(replay.validate(&puzzle), Ok(()));
+    }
+
+    #[test]
+    fn guesses_are_tagged() {
+        let puzzle = Puzzle::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let replay = Replay::record(&puzzle).unwrap();
+
+        assert_eq!(replay.steps()[0].technique, Technique::Guess);
+        assert_eq!(replay.validate(&puzzle), Ok(()));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", ".#.", "##."]));
+        let replay = Replay::record(&puzzle).unwrap();
+        let bytes = replay.to_bytes();
+
+        assert_eq!(Replay::from_bytes(&bytes), Ok(replay));
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::Truncated)
+        );
+    }
+
+    #[test]
+    fn rejects_unforced_step() {
+        let puzzle = Puzzle::from_goal(from_art(&["#.#", ".#.", "##."]));
+        let mut replay = Replay::record(&puzzle).unwrap();
+        replay.steps.swap(0, 8);
+        replay.steps[0].technique = Technique::EdgeLogic;
+
+        assert!(matches!(
+            replay.validate(&puzzle),
+            Err(ReplayError::NotDeducible(_)) | Err(ReplayError::AlreadySolved(_))
+        ));
+        replay.steps.truncate(3);
+        assert!(replay.validate(&puzzle).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Solver, Status};
+use crate::rng::Rng;
+use crate::spaces::grid::CellPos;
+
+/// How many failed guesses the search may make before starting over
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RestartPolicy {
+    /// `unit` times the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+    Luby { unit: usize },
+    /// `first` failures, growing by `factor` after each restart
+    Geometric { first: usize, factor: f64 },
+}
+
+// Restart bookkeeping, dropped once the first solution is found
+#[derive(Clone, Debug)]
+pub(super) struct Restarts {
+    policy: RestartPolicy,
+    rng: Rng,
+    count: usize,
+    fails: usize,
+    // Guess order since the last restart, `None` before the first
+    order: Option<Vec<CellPos>>,
+}
+
+/// The `i`th term of the Luby sequence, counting from 0
+pub fn luby(i: usize) -> usize {
+    let mut i = i + 1;
+    loop {
+        // Find k with 2^(k-1) <= i < 2^k
+        let k = usize::BITS - i.leading_zeros();
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+impl RestartPolicy {
+    /// Failures allowed after `restarts` restarts
+    pub fn budget(self, restarts: usize) -> usize {
+        match self {
+            RestartPolicy::Luby { unit } => unit * luby(restarts),
+            RestartPolicy::Geometric { first, factor } => {
+                (first as f64 * factor.powi(restarts as i32)) as usize
+            }
+        }
+    }
+}
+
+impl Solver {
+    /// Start over after too many failed guesses, shuffling the guess order each time
+    ///
+    /// Restarts only happen while looking for the first solution, so later
+    /// solutions are still enumerated exhaustively.
+    pub fn set_restarts(&mut self, policy: RestartPolicy, seed: u64) {
+        self.restarts = Some(Restarts {
+            policy,
+            rng: Rng::new(seed),
+            count: 0,
+            fails: 0,
+            order: None,
+        });
+    }
+
+    pub(super) fn restart_order(&self) -> Option<&[CellPos]> {
+        self.restarts.as_ref()?.order.as_deref()
+    }
+
+    // Record a failed guess, restarting from the root once over budget
+    pub(super) fn note_failure(&mut self) {
+        let restarts = match self.restarts.as_mut() {
+            Some(restarts) => restarts,
+            None => return,
+        };
+        restarts.fails += 1;
+        if restarts.fails <= restarts.policy.budget(restarts.count) || self.stack.is_empty() {
+            return;
+        }
+
+        restarts.fails = 0;
+        restarts.count += 1;
+        let mut order: Vec<CellPos> = self.grid.positions().collect();
+        restarts.rng.shuffle(&mut order);
+        restarts.order = Some(order);
+
+        // The oldest branch holds the board as it was before any guess
+        let root = self.stack.drain(..).next().unwrap();
+        self.undo(root);
+        self.trail.clear();
+        self.stats.restarts += 1;
+        self.status = Status::Searching;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::strategies;
+
+    #[test]
+    fn luby_sequence() {
+        let terms: Vec<usize> = (0..15).map(luby).collect();
+        assert_eq!(terms, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn budgets() {
+        assert_eq!(RestartPolicy::Luby { unit: 10 }.budget(6), 40);
+        let geometric = RestartPolicy::Geometric {
+            first: 4,
+            factor: 1.5,
+        };
+        assert_eq!(geometric.budget(0), 4);
+        assert_eq!(geometric.budget(2), 9);
+    }
+
+    #[test]
+    fn restarts_keep_search_complete() {
+        let mut restarted = 0;
+        for (case, mut rng) in strategies::seeds(588, 60).enumerate() {
+            let puzzle = Puzzle::from_goal(strategies::goal(&mut rng, 15, 15, 0.5));
+            let mut expected = Solver::new(&puzzle).solutions(20);
+            expected.sort_by_key(|grid| format!("{:?}", grid));
+
+            let mut solver = Solver::new(&puzzle);
+            solver.set_restarts(RestartPolicy::Luby { unit: 1 }, case as u64);
+            let mut found = solver.solutions(20);
+            found.sort_by_key(|grid| format!("{:?}", grid));
+
+            assert_eq!(found, expected);
+            restarted += solver.stats().restarts;
+        }
+        assert!(restarted > 0);
+    }
+}
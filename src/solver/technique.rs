@@ -0,0 +1,222 @@
+use crate::spaces::node::CellState;
+use crate::spaces::view::LineCells;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Named human solving technique, with discriminants that never change
+///
+/// Techniques order from easiest to hardest, which is not discriminant order.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+pub enum Technique {
+    /// Every clue is already placed, so the rest of the line is empty
+    CompletedLine = 0,
+    /// A block is longer than its slack, so its middle is filled wherever it goes
+    Overlap = 1,
+    /// No block can reach the cell, so it is empty
+    Punctuation = 2,
+    /// Known cells pin blocks down further than overlap alone
+    EdgeLogic = 3,
+    /// Assuming the other value contradicts the clues after propagation
+    Probing = 4,
+    /// Picked arbitrarily by the search
+    Guess = 5,
+    /// The other value breaks a line crossing the cell's row or column
+    CrossLine = 6,
+}
+
+impl Technique {
+    /// Every technique, easiest first
+    pub const ALL: [Technique; 7] = [
+        Technique::CompletedLine,
+        Technique::Overlap,
+        Technique::Punctuation,
+        Technique::EdgeLogic,
+        Technique::CrossLine,
+        Technique::Probing,
+        Technique::Guess,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Technique> {
+        Technique::ALL
+            .iter()
+            .find(|&&technique| technique as u8 == value)
+            .cloned()
+    }
+
+    // Position in `ALL`
+    fn rank(self) -> usize {
+        Technique::ALL
+            .iter()
+            .position(|&technique| technique == self)
+            .unwrap()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::CompletedLine => "completed line",
+            Technique::Overlap => "overlap",
+            Technique::Punctuation => "punctuation",
+            Technique::EdgeLogic => "edge logic",
+            Technique::Probing => "probing",
+            Technique::Guess => "guess",
+            Technique::CrossLine => "cross-line",
+        }
+    }
+
+    /// Whether the technique works within a single line
+    pub fn is_line_technique(self) -> bool {
+        self <= Technique::EdgeLogic
+    }
+}
+
+impl PartialOrd for Technique {
+    fn partial_cmp(&self, other: &Technique) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Technique {
+    fn cmp(&self, other: &Technique) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Whether the filled runs of `cells` are exactly `clues`
+fn runs_match<C: LineCells + ?Sized>(cells: &C, clues: &[usize]) -> bool {
+    let mut clues = clues.iter();
+    let mut run = 0;
+    for i in 0..=cells.len() {
+        if i < cells.len() && cells.state(i) == CellState::Filled {
+            run += 1;
+        } else if run > 0 {
+            if clues.next() != Some(&run) {
+                return false;
+            }
+            run = 0;
+        }
+    }
+    clues.next().is_none()
+}
+
+/// Label each cell a line solve changed with the simplest technique explaining it
+///
+/// `before` and `after` are the line around the solve and `starts` the feasible
+/// block starts it found. Cells left unchanged are labelled `None`.
+pub fn classify<C: LineCells + ?Sized>(
+    clues: &[usize],
+    before: &C,
+    after: &[CellState],
+    starts: &[Vec<usize>],
+    labels: &mut Vec<Option<Technique>>,
+) {
+    let n = before.len();
+    labels.clear();
+    labels.resize(n, None);
+
+    // Filled runs so far already spell out the clues
+    let completed = runs_match(before, clues);
+
+    // Overlap on a blank line: block j lies somewhere in [earliest, latest + len)
+    let total: usize = clues.iter().sum::<usize>() + clues.len().saturating_sub(1);
+    let slack = n.saturating_sub(total);
+    let mut overlap = vec![false; n];
+    let mut earliest = 0;
+    for &len in clues {
+        if len > slack {
+            overlap[earliest + slack..earliest + len]
+                .iter_mut()
+                .for_each(|cell| *cell = true);
+        }
+        earliest += len + 1;
+    }
+
+    // Cells some block can still reach given the current state
+    let mut reachable = vec![false; n];
+    for (j, starts) in starts.iter().enumerate() {
+        if let (Some(&first), Some(&last)) = (starts.first(), starts.last()) {
+            reachable[first..last + clues[j]]
+                .iter_mut()
+                .for_each(|cell| *cell = true);
+        }
+    }
+
+    for i in 0..n {
+        if before.state(i) != CellState::Unknown || after[i] == CellState::Unknown {
+            continue;
+        }
+        labels[i] = Some(match after[i] {
+            CellState::Empty if completed => Technique::CompletedLine,
+            CellState::Filled if overlap[i] => Technique::Overlap,
+            CellState::Empty if !reachable[i] => Technique::Punctuation,
+            _ => Technique::EdgeLogic,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::line;
+    use CellState::*;
+
+    fn parse(line: &str) -> Vec<CellState> {
+        line.chars()
+            .map(|c| match c {
+                '#' => Filled,
+                '.' => Empty,
+                _ => Unknown,
+            })
+            .collect()
+    }
+
+    fn labels(clues: &[usize], line: &str) -> Vec<Option<Technique>> {
+        let before = parse(line);
+        let soln = line::solve(clues, &before).unwrap();
+        let mut labels = Vec::new();
+        classify(clues, &before, &soln.cells, &soln.starts, &mut labels);
+        labels
+    }
+
+    #[test]
+    fn overlap_on_blank_line() {
+        let labels = labels(&[4], "?????");
+        assert_eq!(labels[0], None);
+        assert_eq!(labels[1..4], [Some(Technique::Overlap); 3]);
+    }
+
+    #[test]
+    fn completed_line() {
+        let labels = labels(&[1, 1], "?#??#?");
+        assert!(labels
+            .iter()
+            .enumerate()
+            .all(|(i, l)| i == 1 || i == 4 || *l == Some(Technique::CompletedLine)));
+    }
+
+    #[test]
+    fn punctuation_and_edge_logic() {
+        // The 2 must sit in the open gap, the lone filled cell pins the 1
+        let labels = labels(&[2, 1], "??.?.#");
+        assert_eq!(labels[0], Some(Technique::EdgeLogic));
+        assert_eq!(labels[3], Some(Technique::Punctuation));
+    }
+
+    #[test]
+    fn stable_discriminants() {
+        for technique in Technique::ALL.iter() {
+            assert_eq!(Technique::from_u8(*technique as u8), Some(*technique));
+        }
+        assert_eq!(Technique::from_u8(7), None);
+        assert!(Technique::EdgeLogic < Technique::CrossLine);
+        assert!(Technique::CrossLine < Technique::Probing);
+        assert!(Technique::EdgeLogic.is_line_technique());
+        assert!(!Technique::Probing.is_line_technique());
+    }
+}
@@ -1,16 +1,206 @@
+pub mod bits;
+pub mod diff;
+pub mod grid;
 pub mod hint;
+pub mod merge;
 pub mod node;
+pub mod packed;
+pub mod slack;
+pub mod store;
+pub mod view;
 
 use hint::Hint;
 
+/// Integer type for positions and lengths stored in line bookkeeping
+///
+/// The `index-u32` and `index-u16` features narrow it, shrinking hint windows
+/// on giant boards; lines longer than it can count then panic. With both
+/// enabled the narrower wins.
+#[cfg(feature = "index-u16")]
+pub type Index = u16;
+#[cfg(all(feature = "index-u32", not(feature = "index-u16")))]
+pub type Index = u32;
+#[cfg(not(any(feature = "index-u16", feature = "index-u32")))]
+pub type Index = usize;
+
+// Narrow a position or length for storage
+#[cfg(any(feature = "index-u16", feature = "index-u32"))]
+pub(crate) fn to_index(n: usize) -> Index {
+    assert!(
+        n <= Index::MAX as usize,
+        "{} does not fit the configured index width",
+        n
+    );
+    n as Index
+}
+
+#[cfg(not(any(feature = "index-u16", feature = "index-u32")))]
+pub(crate) fn to_index(n: usize) -> Index {
+    n
+}
+
+#[cfg(any(feature = "index-u16", feature = "index-u32"))]
+pub(crate) fn from_index(i: Index) -> usize {
+    i as usize
+}
+
+#[cfg(not(any(feature = "index-u16", feature = "index-u32")))]
+pub(crate) fn from_index(i: Index) -> usize {
+    i
+}
+
+/// Identifies a single row or column of the board
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LineId {
+    Row(usize),
+    Column(usize),
+}
+
+/// A non-identity symmetry of the square, applied to boards and clue sets
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    /// Mirror left to right
+    MirrorH,
+    /// Mirror top to bottom
+    MirrorV,
+    /// Quarter turn clockwise
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swap rows and columns
+    Transpose,
+    /// Reflect across the other diagonal
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::MirrorH,
+        Symmetry::MirrorV,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// Whether the symmetry swaps width and height
+    pub fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::Transpose
+                | Symmetry::AntiTranspose
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Line {
     hints: Vec<Hint>,
+    length: Index,
 }
 
 impl Line {
     pub fn new(hints: &[usize], length: usize) -> Line {
         Line {
             hints: Hint::gen(hints, length),
+            length: to_index(length),
         }
     }
+
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
+    pub fn hints_mut(&mut self) -> &mut [Hint] {
+        &mut self.hints
+    }
+
+    pub fn len(&self) -> usize {
+        from_index(self.length)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn clues(&self) -> Vec<usize> {
+        self.hints.iter().map(Hint::hint).collect()
+    }
+
+    /// Narrow each hint's solutions around cells that have become empty
+    pub fn refine<C: view::LineCells + ?Sized>(&mut self, cells: &C) {
+        for hint in &mut self.hints {
+            hint.refine(cells);
+        }
+    }
+
+    /// Trim each hint's windows where their end cells have become empty
+    pub fn tighten<C: view::LineCells + ?Sized>(&mut self, cells: &C) {
+        for hint in &mut self.hints {
+            hint.tighten(cells);
+        }
+    }
+
+    /// Keep blocks in clue order, each starting after the earliest end of
+    /// the block before it and ending before the latest start of the next
+    pub fn order(&mut self) {
+        let mut start = 0;
+        for hint in &mut self.hints {
+            hint.clamp(start, usize::MAX);
+            match hint.solutions().first() {
+                Some(soln) => start = soln.offset() + hint.hint() + 1,
+                None => return,
+            }
+        }
+        let mut end = self.len();
+        for hint in self.hints.iter_mut().rev() {
+            hint.clamp(0, end);
+            match hint.solutions().last() {
+                Some(soln) => end = (soln.offset() + soln.length()).saturating_sub(hint.hint() + 1),
+                None => return,
+            }
+        }
+    }
+
+    /// Replace each hint's solutions with the windows covering its feasible starts
+    pub fn set_starts(&mut self, starts: &[Vec<usize>]) {
+        for (hint, starts) in self.hints.iter_mut().zip(starts) {
+            hint.set_starts(starts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hint::HSoln;
+
+    fn windows(line: &Line) -> Vec<Vec<(usize, usize)>> {
+        line.hints()
+            .iter()
+            .map(|hint| {
+                hint.solutions()
+                    .iter()
+                    .map(|soln| (soln.offset(), soln.length()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn order_pushes_neighbouring_blocks() {
+        let mut line = Line::new(&[2, 1, 2], 10);
+        line.hints_mut()[0].set_solutions(vec![HSoln::new(3, 3)]);
+        line.hints_mut()[2].set_solutions(vec![HSoln::new(2, 3), HSoln::new(6, 4)]);
+        line.order();
+
+        // The first block pushes the others right, and they pull it back left
+        assert_eq!(
+            windows(&line),
+            vec![vec![(3, 2)], vec![(6, 1)], vec![(8, 2)]]
+        );
+    }
 }
@@ -0,0 +1,218 @@
+use super::node::CellState;
+use super::view::LineCells;
+
+const WORD: usize = 64;
+
+/// Fixed length bitset with word-at-a-time range operations
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Bits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+/// A line split into filled and empty bitsets, unknown cells being in neither
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LineBits {
+    filled: Bits,
+    empty: Bits,
+}
+
+// Mask of the bits in [start, end) within a single word
+fn mask(start: usize, end: usize) -> u64 {
+    let high = if end == WORD { !0 } else { (1 << end) - 1 };
+    high & !((1u64 << start) - 1)
+}
+
+impl Bits {
+    pub fn new(len: usize) -> Bits {
+        Bits {
+            words: vec![0; len.div_ceil(WORD)],
+            len,
+        }
+    }
+
+    /// Clear every bit and change the length, keeping the allocation
+    pub fn reset(&mut self, len: usize) {
+        self.words.clear();
+        self.words.resize(len.div_ceil(WORD), 0);
+        self.len = len;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len);
+        self.words[i / WORD] >> (i % WORD) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize) {
+        assert!(i < self.len);
+        self.words[i / WORD] |= 1 << (i % WORD);
+    }
+
+    // Visit each word overlapping [start, end) with the mask of bits inside the range
+    fn for_range(&self, start: usize, end: usize, mut f: impl FnMut(usize, u64) -> bool) {
+        assert!(start <= end && end <= self.len);
+        let mut i = start;
+        while i < end {
+            let word = i / WORD;
+            let stop = end.min((word + 1) * WORD);
+            if !f(word, mask(i % WORD, stop - word * WORD)) {
+                return;
+            }
+            i = stop;
+        }
+    }
+
+    pub fn set_range(&mut self, start: usize, end: usize) {
+        let mut words = std::mem::take(&mut self.words);
+        self.for_range(start, end, |word, mask| {
+            words[word] |= mask;
+            true
+        });
+        self.words = words;
+    }
+
+    pub fn any_in(&self, start: usize, end: usize) -> bool {
+        let mut found = false;
+        self.for_range(start, end, |word, mask| {
+            found = self.words[word] & mask != 0;
+            !found
+        });
+        found
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Index of the first set bit at or after `from`
+    pub fn next_one(&self, from: usize) -> Option<usize> {
+        self.next(from, false)
+    }
+
+    /// Index of the first clear bit at or after `from`
+    pub fn next_zero(&self, from: usize) -> Option<usize> {
+        self.next(from, true)
+    }
+
+    fn next(&self, from: usize, invert: bool) -> Option<usize> {
+        let mut word = from / WORD;
+        let mut bits = !mask(0, from % WORD);
+        while word < self.words.len() {
+            let value = if invert {
+                !self.words[word]
+            } else {
+                self.words[word]
+            };
+            let hits = value & bits;
+            if hits != 0 {
+                let i = word * WORD + hits.trailing_zeros() as usize;
+                return if i < self.len { Some(i) } else { None };
+            }
+            word += 1;
+            bits = !0;
+        }
+        None
+    }
+}
+
+impl LineBits {
+    pub fn from_states(cells: &[CellState]) -> LineBits {
+        let mut bits = LineBits::default();
+        bits.refill(cells);
+        bits
+    }
+
+    /// Load a new line, keeping the allocations
+    pub fn refill<C: LineCells + ?Sized>(&mut self, cells: &C) {
+        self.filled.reset(cells.len());
+        self.empty.reset(cells.len());
+        for i in 0..cells.len() {
+            match cells.state(i) {
+                CellState::Filled => self.filled.set(i),
+                CellState::Empty => self.empty.set(i),
+                CellState::Unknown => {}
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.filled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filled.is_empty()
+    }
+
+    pub fn filled(&self) -> &Bits {
+        &self.filled
+    }
+
+    pub fn empty(&self) -> &Bits {
+        &self.empty
+    }
+
+    /// Whether a block of `len` cells can sit at `start` without covering an empty cell
+    pub fn fits(&self, start: usize, len: usize) -> bool {
+        start + len <= self.len() && !self.empty.any_in(start, start + len)
+    }
+
+    /// Maximal runs of non-empty cells as `(start, length)` pairs
+    pub fn open_runs(&self) -> Vec<(usize, usize)> {
+        let mut runs = Vec::new();
+        let mut i = 0;
+        while let Some(start) = self.empty.next_zero(i) {
+            let end = self.empty.next_one(start).unwrap_or(self.len());
+            runs.push((start, end - start));
+            i = end;
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CellState::*;
+
+    #[test]
+    fn ranges_across_words() {
+        let mut bits = Bits::new(200);
+        bits.set_range(60, 130);
+
+        assert_eq!(bits.count_ones(), 70);
+        assert!(!bits.get(59));
+        assert!(bits.get(60) && bits.get(129));
+        assert!(!bits.get(130));
+        assert!(bits.any_in(0, 61));
+        assert!(!bits.any_in(130, 200));
+        assert_eq!(bits.next_one(0), Some(60));
+        assert_eq!(bits.next_zero(60), Some(130));
+        assert_eq!(bits.next_one(130), None);
+    }
+
+    #[test]
+    fn next_zero_stops_at_len() {
+        let mut bits = Bits::new(64);
+        bits.set_range(0, 64);
+        assert_eq!(bits.next_zero(0), None);
+    }
+
+    #[test]
+    fn block_fits_and_runs() {
+        let line = LineBits::from_states(&[Unknown, Filled, Empty, Unknown, Unknown, Empty]);
+
+        assert!(line.fits(0, 2));
+        assert!(!line.fits(1, 2));
+        assert!(line.fits(3, 2));
+        assert!(!line.fits(4, 3));
+        assert_eq!(line.open_runs(), vec![(0, 2), (3, 2)]);
+    }
+}
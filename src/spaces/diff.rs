@@ -0,0 +1,149 @@
+use super::grid::{CellPos, Grid};
+use super::node::CellState;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CellChange {
+    pub pos: CellPos,
+    pub from: CellState,
+    pub to: CellState,
+}
+
+/// Cells whose state differs between two grids of the same size
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GridDiff {
+    changes: Vec<CellChange>,
+}
+
+/// A cell that did not hold the state a diff expected
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DiffConflict {
+    pub pos: CellPos,
+    pub expected: CellState,
+    pub found: CellState,
+}
+
+impl fmt::Display for DiffConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cell ({}, {}) is {:?}, expected {:?}",
+            self.pos.row, self.pos.col, self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for DiffConflict {}
+
+impl GridDiff {
+    /// Changes turning `a` into `b`, in row-major order
+    pub fn between(a: &Grid, b: &Grid) -> GridDiff {
+        assert_eq!(a.width(), b.width(), "Grids have different widths");
+        assert_eq!(a.height(), b.height(), "Grids have different heights");
+
+        GridDiff {
+            changes: a
+                .positions()
+                .filter(|&pos| a.get(pos) != b.get(pos))
+                .map(|pos| CellChange {
+                    pos,
+                    from: a.get(pos),
+                    to: b.get(pos),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn changes(&self) -> &[CellChange] {
+        &self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The diff undoing this one
+    pub fn reversed(&self) -> GridDiff {
+        GridDiff {
+            changes: self
+                .changes
+                .iter()
+                .map(|change| CellChange {
+                    pos: change.pos,
+                    from: change.to,
+                    to: change.from,
+                })
+                .collect(),
+        }
+    }
+
+    /// Apply every change, leaving the grid untouched if any cell conflicts
+    pub fn apply(&self, grid: &mut Grid) -> Result<(), DiffConflict> {
+        for change in &self.changes {
+            let found = grid.get(change.pos);
+            if found != change.from {
+                return Err(DiffConflict {
+                    pos: change.pos,
+                    expected: change.from,
+                    found,
+                });
+            }
+        }
+
+        for change in &self.changes {
+            grid.set_state(change.pos, change.to);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn diff_and_apply() {
+        let a = from_art(&["#.", ".#"]);
+        let b = from_art(&["##", ".."]);
+        let diff = GridDiff::between(&a, &b);
+
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff.changes()[0].pos, CellPos::new(0, 1));
+        assert_eq!(diff.changes()[1].to, CellState::Empty);
+
+        let mut grid = a.clone();
+        diff.apply(&mut grid).unwrap();
+        assert_eq!(grid, b);
+
+        diff.reversed().apply(&mut grid).unwrap();
+        assert_eq!(grid, a);
+    }
+
+    #[test]
+    fn identical_grids() {
+        let a = from_art(&["#.", ".#"]);
+        assert!(GridDiff::between(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn conflicting_apply_changes_nothing() {
+        let a = Grid::new(2, 1);
+        let mut b = a.clone();
+        b.solve(CellPos::new(0, 0), true);
+        b.solve(CellPos::new(0, 1), true);
+        let diff = GridDiff::between(&a, &b);
+
+        let mut target = Grid::new(2, 1);
+        target.solve(CellPos::new(0, 1), false);
+        let err = diff.apply(&mut target).unwrap_err();
+
+        assert_eq!(err.pos, CellPos::new(0, 1));
+        assert_eq!(err.found, CellState::Empty);
+        assert_eq!(target.get(CellPos::new(0, 0)), CellState::Unknown);
+    }
+}
@@ -0,0 +1,474 @@
+use super::node::{CellState, CellValue, Node};
+use super::view::{LineView, TransposedView};
+use super::{LineId, Symmetry};
+
+/// Orders row by row, as cells are stored
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CellPos {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Row-major matrix of nodes making up the board
+///
+/// Cells are binary by default; other `CellValue`s such as `ColorCell` share
+/// the storage, positions and versioning but not the solving helpers.
+#[derive(Clone, Debug)]
+pub struct Grid<V = CellState> {
+    width: usize,
+    height: usize,
+    nodes: Vec<Node<V>>,
+    // Version of each row then each column, see `line_version`
+    versions: Vec<u64>,
+    // Last version handed out
+    clock: u64,
+}
+
+impl CellPos {
+    pub fn new(row: usize, col: usize) -> CellPos {
+        CellPos { row, col }
+    }
+}
+
+impl<V: CellValue> Grid<V> {
+    /// A grid of the given size with every cell unknown
+    pub fn unsolved(width: usize, height: usize) -> Grid<V> {
+        Grid {
+            width,
+            height,
+            nodes: vec![Node::unknown(); width * height],
+            versions: vec![0; width + height],
+            clock: 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, pos: CellPos) -> usize {
+        assert!(pos.row < self.height && pos.col < self.width);
+        pos.row * self.width + pos.col
+    }
+
+    pub fn node(&self, pos: CellPos) -> &Node<V> {
+        &self.nodes[self.index(pos)]
+    }
+
+    pub fn get(&self, pos: CellPos) -> V {
+        self.node(pos).state()
+    }
+
+    pub(crate) fn set_state(&mut self, pos: CellPos, state: V) {
+        let i = self.index(pos);
+        self.nodes[i].set_state(state);
+        self.touch(pos);
+    }
+
+    // Undo a `solve`, giving the lines through the cell fresh versions
+    pub(crate) fn unsolve(&mut self, pos: CellPos) {
+        let i = self.index(pos);
+        self.nodes[i].unsolve();
+        self.touch(pos);
+    }
+
+    // Give the row and column through `pos` fresh versions
+    fn touch(&mut self, pos: CellPos) {
+        self.clock += 1;
+        self.versions[pos.row] = self.clock;
+        self.versions[self.height + pos.col] = self.clock;
+    }
+
+    /// Stamp that changes whenever a cell of the line changes, for validating
+    /// results cached alongside this grid
+    ///
+    /// Every grid counts its stamps up from zero, so a stamp only identifies
+    /// a line's contents within one grid's history. Results cached against
+    /// this grid may be checked against it or a clone taken afterwards, never
+    /// against another grid or an earlier clone that has changed since.
+    pub fn line_version(&self, line: LineId) -> u64 {
+        match line {
+            LineId::Row(row) => self.versions[row],
+            LineId::Column(col) => self.versions[self.height + col],
+        }
+    }
+
+    /// Latest stamp handed out by `line_version`
+    pub(crate) fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.nodes.iter().all(Node::is_solved)
+    }
+
+    pub fn unknown_count(&self) -> usize {
+        self.nodes.iter().filter(|node| !node.is_solved()).count()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = CellPos> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |i| CellPos::new(i / width, i % width))
+    }
+
+    pub fn line_len(&self, line: LineId) -> usize {
+        match line {
+            LineId::Row(_) => self.width,
+            LineId::Column(_) => self.height,
+        }
+    }
+
+    /// Position of the `i`th cell along a line
+    pub fn line_pos(&self, line: LineId, i: usize) -> CellPos {
+        match line {
+            LineId::Row(row) => CellPos::new(row, i),
+            LineId::Column(col) => CellPos::new(i, col),
+        }
+    }
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Grid {
+        Grid::unsolved(width, height)
+    }
+
+    /// Build a fully solved grid from a picture, `true` being a filled cell
+    pub fn from_image(image: &[Vec<bool>]) -> Grid {
+        let height = image.len();
+        let width = image.first().map_or(0, |row| row.len());
+        let mut grid = Grid::new(width, height);
+
+        for (row, cells) in image.iter().enumerate() {
+            assert_eq!(cells.len(), width, "Row {} has incorrect width", row);
+            for (col, &filled) in cells.iter().enumerate() {
+                grid.solve(CellPos::new(row, col), filled);
+            }
+        }
+
+        grid
+    }
+
+    pub fn solve(&mut self, pos: CellPos, filled: bool) {
+        let i = self.index(pos);
+        self.nodes[i].solve(filled);
+        self.touch(pos);
+    }
+
+    /// View a row without copying it
+    pub fn row(&self, row: usize) -> LineView<'_> {
+        assert!(row < self.height);
+        LineView::new(&self.nodes, row * self.width, 1, self.width)
+    }
+
+    /// View a column without copying it
+    pub fn col(&self, col: usize) -> LineView<'_> {
+        assert!(col < self.width);
+        LineView::new(&self.nodes, col, self.width, self.height)
+    }
+
+    pub fn line(&self, line: LineId) -> LineView<'_> {
+        match line {
+            LineId::Row(row) => self.row(row),
+            LineId::Column(col) => self.col(col),
+        }
+    }
+
+    pub fn line_states(&self, line: LineId) -> Vec<CellState> {
+        let mut states = Vec::with_capacity(self.line_len(line));
+        self.line_states_into(line, &mut states);
+        states
+    }
+
+    /// Copy a line's states into an existing buffer
+    pub fn line_states_into(&self, line: LineId, states: &mut Vec<CellState>) {
+        states.clear();
+        states.extend(self.line(line).iter());
+    }
+
+    // Build a grid of the given size copying each cell from its source position
+    fn remapped(&self, width: usize, height: usize, source: impl Fn(CellPos) -> CellPos) -> Grid {
+        let mut nodes = Vec::with_capacity(width * height);
+        for row in 0..height {
+            for col in 0..width {
+                nodes.push(self.node(source(CellPos::new(row, col))).clone());
+            }
+        }
+        Grid {
+            width,
+            height,
+            nodes,
+            versions: vec![0; width + height],
+            clock: 0,
+        }
+    }
+
+    /// The `width` by `height` block of cells whose top left corner is `origin`
+    pub fn cropped(&self, origin: CellPos, width: usize, height: usize) -> Grid {
+        assert!(origin.row + height <= self.height && origin.col + width <= self.width);
+        self.remapped(width, height, |pos| {
+            CellPos::new(origin.row + pos.row, origin.col + pos.col)
+        })
+    }
+
+    /// Surround the grid with margins of `fill` cells
+    pub fn padded(
+        &self,
+        top: usize,
+        right: usize,
+        bottom: usize,
+        left: usize,
+        fill: CellState,
+    ) -> Grid {
+        let mut grid = Grid::new(left + self.width + right, top + self.height + bottom);
+        for pos in grid.positions().collect::<Vec<_>>() {
+            let inside = pos.row >= top
+                && pos.row < top + self.height
+                && pos.col >= left
+                && pos.col < left + self.width;
+            let state = match inside {
+                true => self.get(CellPos::new(pos.row - top, pos.col - left)),
+                false => fill,
+            };
+            grid.set_state(pos, state);
+        }
+        grid
+    }
+
+    /// Top left corner and size of the smallest box holding every filled cell
+    pub fn filled_bounds(&self) -> Option<(CellPos, usize, usize)> {
+        let filled: Vec<CellPos> = self
+            .positions()
+            .filter(|&pos| self.get(pos) == CellState::Filled)
+            .collect();
+        let top = filled.iter().map(|pos| pos.row).min()?;
+        let bottom = filled.iter().map(|pos| pos.row).max()?;
+        let left = filled.iter().map(|pos| pos.col).min()?;
+        let right = filled.iter().map(|pos| pos.col).max()?;
+        Some((CellPos::new(top, left), right - left + 1, bottom - top + 1))
+    }
+
+    /// See the grid with rows and columns swapped, without copying it
+    ///
+    /// `transposed` gives an owned copy instead.
+    pub fn transposed_view(&self) -> TransposedView<'_> {
+        TransposedView::new(self)
+    }
+
+    /// Swap rows and columns
+    pub fn transposed(&self) -> Grid {
+        self.remapped(self.height, self.width, |pos| {
+            CellPos::new(pos.col, pos.row)
+        })
+    }
+
+    /// Mirror left to right
+    pub fn mirrored_h(&self) -> Grid {
+        let width = self.width;
+        self.remapped(self.width, self.height, |pos| {
+            CellPos::new(pos.row, width - 1 - pos.col)
+        })
+    }
+
+    /// Mirror top to bottom
+    pub fn mirrored_v(&self) -> Grid {
+        let height = self.height;
+        self.remapped(self.width, self.height, |pos| {
+            CellPos::new(height - 1 - pos.row, pos.col)
+        })
+    }
+
+    /// Rotate a quarter turn clockwise
+    pub fn rotated90(&self) -> Grid {
+        self.transposed().mirrored_h()
+    }
+
+    pub fn transformed(&self, symmetry: Symmetry) -> Grid {
+        match symmetry {
+            Symmetry::MirrorH => self.mirrored_h(),
+            Symmetry::MirrorV => self.mirrored_v(),
+            Symmetry::Rotate90 => self.rotated90(),
+            Symmetry::Rotate180 => self.mirrored_h().mirrored_v(),
+            Symmetry::Rotate270 => self.transposed().mirrored_v(),
+            Symmetry::Transpose => self.transposed(),
+            Symmetry::AntiTranspose => self.rotated90().mirrored_v(),
+        }
+    }
+
+    /// Smallest of the grid and its images under `symmetries`, cell by cell
+    ///
+    /// With the symmetries of a puzzle, grids equal up to those symmetries share
+    /// a canonical form. Symmetries that change the grid's shape are skipped.
+    pub fn canonical(&self, symmetries: &[Symmetry]) -> Grid {
+        let key =
+            |grid: &Grid| -> Vec<u8> { grid.nodes.iter().map(|node| node.state() as u8).collect() };
+        let mut best = self.clone();
+        for &symmetry in symmetries {
+            if symmetry.swaps_axes() && self.width != self.height {
+                continue;
+            }
+            let other = self.transformed(symmetry);
+            if key(&other) < key(&best) {
+                best = other;
+            }
+        }
+        best
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = LineId> {
+        (0..self.height)
+            .map(LineId::Row)
+            .chain((0..self.width).map(LineId::Column))
+    }
+}
+
+// Versions say nothing about the cells themselves
+impl<V: CellValue> PartialEq for Grid<V> {
+    fn eq(&self, other: &Grid<V>) -> bool {
+        self.width == other.width && self.height == other.height && self.nodes == other.nodes
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn from_art(art: &[&str]) -> Grid {
+    let image: Vec<Vec<bool>> = art
+        .iter()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect();
+    Grid::from_image(&image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_round_trip() {
+        let grid = from_art(&["#.", ".#", "##"]);
+
+        assert_eq!(grid.width(), 2);
+        assert_eq!(grid.height(), 3);
+        assert!(grid.is_complete());
+        assert_eq!(grid.get(CellPos::new(0, 1)), CellState::Empty);
+        assert_eq!(grid.get(CellPos::new(2, 0)), CellState::Filled);
+    }
+
+    #[test]
+    fn colored_cells() {
+        use crate::spaces::node::ColorCell;
+
+        let mut grid: Grid<ColorCell> = Grid::unsolved(3, 2);
+        let pos = CellPos::new(1, 2);
+        let before = grid.line_version(LineId::Column(2));
+        grid.set_state(pos, ColorCell::Color(3));
+
+        assert_eq!(grid.get(pos), ColorCell::Color(3));
+        assert_eq!(grid.unknown_count(), 5);
+        assert_ne!(grid.line_version(LineId::Column(2)), before);
+        assert_ne!(grid, Grid::unsolved(3, 2));
+        assert_eq!(ColorCell::from(CellState::Filled), ColorCell::Color(1));
+    }
+
+    #[test]
+    fn column_states() {
+        let grid = from_art(&["#.", ".#", "##"]);
+
+        assert_eq!(
+            grid.line_states(LineId::Column(1)),
+            vec![CellState::Empty, CellState::Filled, CellState::Filled]
+        );
+    }
+
+    #[test]
+    fn line_views() {
+        let grid = from_art(&["#.", ".#", "##"]);
+        let col = grid.col(1);
+
+        assert_eq!(col.len(), 3);
+        assert_eq!(col.get(0), CellState::Empty);
+        assert!(col[2].is_solved());
+        assert_eq!(
+            col.iter().collect::<Vec<_>>(),
+            grid.line_states(LineId::Column(1))
+        );
+        assert_eq!(
+            grid.row(2).iter().collect::<Vec<_>>(),
+            vec![CellState::Filled; 2]
+        );
+        assert_eq!(grid.line(LineId::Row(1)).get(1), CellState::Filled);
+    }
+
+    #[test]
+    fn transposed_view() {
+        let grid = from_art(&["##.", "..#"]);
+        let view = grid.transposed_view();
+
+        assert_eq!((view.width(), view.height()), (2, 3));
+        assert_eq!(view.get(CellPos::new(2, 1)), CellState::Filled);
+        assert_eq!(
+            view.row(2).iter().collect::<Vec<_>>(),
+            grid.line_states(LineId::Column(2))
+        );
+        assert_eq!(
+            view.line(LineId::Column(0)).iter().collect::<Vec<_>>(),
+            grid.line_states(LineId::Row(0))
+        );
+        assert_eq!(view.to_grid(), grid.transposed());
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = from_art(&["##.", "..#"]);
+
+        assert_eq!(grid.transposed(), from_art(&["#.", "#.", ".#"]));
+        assert_eq!(grid.mirrored_h(), from_art(&[".##", "#.."]));
+        assert_eq!(grid.mirrored_v(), from_art(&["..#", "##."]));
+        assert_eq!(grid.rotated90(), from_art(&[".#", ".#", "#."]));
+    }
+
+    #[test]
+    fn crop_and_pad() {
+        let grid = from_art(&["....", ".#..", "..#.", "...."]);
+        let (origin, width, height) = grid.filled_bounds().unwrap();
+
+        assert_eq!((origin, width, height), (CellPos::new(1, 1), 2, 2));
+        let cropped = grid.cropped(origin, width, height);
+        assert_eq!(cropped, from_art(&["#.", ".#"]));
+        assert_eq!(cropped.padded(1, 1, 1, 1, CellState::Empty), grid);
+        assert_eq!(Grid::new(2, 2).filled_bounds(), None);
+        assert_eq!(
+            cropped
+                .padded(0, 1, 0, 0, CellState::Unknown)
+                .get(CellPos::new(0, 2)),
+            CellState::Unknown
+        );
+    }
+
+    #[test]
+    fn versions_follow_changes() {
+        let mut grid = Grid::new(3, 2);
+        let (row, col) = (LineId::Row(1), LineId::Column(2));
+        let before = (grid.line_version(row), grid.line_version(col));
+
+        grid.solve(CellPos::new(1, 0), true);
+        let after = grid.line_version(row);
+        assert_ne!(after, before.0);
+        assert_eq!(grid.line_version(col), before.1);
+
+        grid.solve(CellPos::new(0, 2), false);
+        assert_eq!(grid.line_version(row), after);
+        assert_ne!(grid.line_version(col), before.1);
+        assert_eq!(grid.clone(), grid);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cannot_solve_twice() {
+        let mut grid = Grid::new(2, 2);
+        grid.solve(CellPos::new(1, 1), true);
+        grid.solve(CellPos::new(1, 1), false);
+    }
+}
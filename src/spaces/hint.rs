@@ -1,33 +1,88 @@
-use super::node::Node;
+use super::node::{CellState, Node};
+use super::slack;
+use super::view::LineCells;
+use super::{from_index, to_index, Index};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug)]
 pub struct HSoln {
-    offset: usize,
-    length: usize,
+    offset: Index,
+    length: Index,
+    // Line version of the last `is_valid_at` shifted over its result, zero
+    // before the first. Atomic so solvers stay `Sync`.
+    valid: AtomicU64,
 }
 
+#[derive(Clone, Debug)]
 pub struct Hint {
-    hint: usize,
+    hint: Index,
     solutions: Vec<HSoln>,
 }
 
+/// Queue reused between calls to `HSoln::split_into`
+#[derive(Debug, Default)]
+pub struct SplitScratch {
+    ranges: RangeQueue,
+}
+
+#[derive(Debug, Default)]
 struct RangeQueue {
-    queue: VecDeque<(usize, usize)>,
+    queue: VecDeque<(Index, Index)>,
 }
 
 impl HSoln {
-    pub fn is_valid(&self, nodes: &[Node], hint: usize) -> bool {
-        let nodes = self.partition(nodes);
-        // TODO: Might be worthwhile to cache this value until a registered change occurs
+    pub fn new(offset: usize, length: usize) -> HSoln {
+        HSoln {
+            offset: to_index(offset),
+            length: to_index(length),
+            valid: AtomicU64::new(0),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        from_index(self.offset)
+    }
+
+    pub fn length(&self) -> usize {
+        from_index(self.length)
+    }
+
+    pub fn is_valid<C: LineCells + ?Sized>(&self, cells: &C, hint: usize) -> bool {
+        self.check(cells, hint)
+    }
+
+    /// `is_valid` cached against the line's version, as given by
+    /// `Grid::line_version`, so it is only redone once the line changes
+    ///
+    /// Versions are only comparable within one grid's history, so every call
+    /// on a solution must pass versions from the grid it was last checked
+    /// against, or a clone of it taken since.
+    pub fn is_valid_at<C: LineCells + ?Sized>(&self, cells: &C, hint: usize, version: u64) -> bool {
+        let cached = self.valid.load(Ordering::Relaxed);
+        if cached != 0 && cached >> 1 == version {
+            return cached & 1 == 1;
+        }
+        let valid = self.check(cells, hint);
+        self.valid
+            .store(version << 1 | valid as u64, Ordering::Relaxed);
+        valid
+    }
+
+    /// Version the last `is_valid_at` was cached against, if any
+    pub(crate) fn checked_version(&self) -> Option<u64> {
+        let cached = self.valid.load(Ordering::Relaxed);
+        Some(cached >> 1).filter(|_| cached != 0)
+    }
+
+    fn check<C: LineCells + ?Sized>(&self, cells: &C, hint: usize) -> bool {
         let mut min_filled = None;
         let mut max_filled = None;
 
-        for (i, node) in nodes.iter().enumerate() {
-            if node.is_solved() {
-                if node.solution_is_empty() {
-                    return false;
-                } else if node.solution_is_filled() {
+        for i in 0..self.length() {
+            match cells.state(self.offset() + i) {
+                CellState::Empty => return false,
+                CellState::Filled => {
                     match min_filled {
                         // Distance between two filled nodes is greater than hint number
                         Some(j) if i - j >= hint => return false,
@@ -39,23 +94,80 @@ impl HSoln {
                         _ => max_filled = Some(i),
                     };
                 }
+                CellState::Unknown => {}
             }
         }
-        match max_filled {
-            Some(j) if nodes.len() - j > hint || j > hint => false,
-            _ => true,
+        !matches!(max_filled, Some(j) if self.length() - j > hint || j > hint)
+    }
+
+    /// Split the window at cells known to be empty, keeping the pieces that
+    /// can still hold the block
+    pub fn refine<C: LineCells + ?Sized>(&self, cells: &C, hint: usize) -> Vec<HSoln> {
+        let mut pieces = Vec::new();
+        let end = self.offset() + self.length();
+        let mut start = self.offset();
+        for i in self.offset()..=end {
+            if i == end || cells.state(i) == CellState::Empty {
+                if i - start >= hint {
+                    pieces.push(HSoln::new(start, i - start));
+                }
+                start = i + 1;
+            }
         }
+        pieces
+    }
+
+    /// Drop empty cells from either end of the window, false once it can no
+    /// longer hold the block
+    ///
+    /// Cheaper than `refine`, which also splits around empty cells inside.
+    pub fn tighten<C: LineCells + ?Sized>(&mut self, cells: &C, hint: usize) -> bool {
+        let (mut start, mut end) = (self.offset(), self.offset() + self.length());
+        while start < end && cells.state(start) == CellState::Empty {
+            start += 1;
+        }
+        while start < end && cells.state(end - 1) == CellState::Empty {
+            end -= 1;
+        }
+        self.clamp(start, end, hint)
+    }
+
+    /// Cut the window down to `start..end`, false once it can no longer hold
+    /// the block
+    pub fn clamp(&mut self, start: usize, end: usize, hint: usize) -> bool {
+        let start = start.max(self.offset());
+        let end = end.min(self.offset() + self.length()).max(start);
+        if (start, end - start) != (self.offset(), self.length()) {
+            self.offset = to_index(start);
+            self.length = to_index(end - start);
+            *self.valid.get_mut() = 0;
+        }
+        end - start >= hint
     }
 
     fn partition<'a>(&self, nodes: &'a [Node]) -> &'a [Node] {
-        &nodes[self.offset..self.offset + self.length]
+        &nodes[self.offset()..self.offset() + self.length()]
     }
 
     pub fn split(&self, nodes: &[Node], hint: usize) -> Vec<HSoln> {
-        let nodes = self.partition(nodes);
         let mut splits = Vec::new();
+        self.split_into(nodes, hint, &mut SplitScratch::default(), &mut splits);
+        splits
+    }
+
+    /// Split reusing the scratch queue, appending the splits to `splits`
+    pub fn split_into(
+        &self,
+        nodes: &[Node],
+        hint: usize,
+        scratch: &mut SplitScratch,
+        splits: &mut Vec<HSoln>,
+    ) {
+        let nodes = self.partition(nodes);
+        let offset = self.offset();
         // Store index of first and last node in continous filled solution group
-        let mut ranges = RangeQueue::new();
+        let ranges = &mut scratch.ranges;
+        ranges.clear();
 
         // Index of the earliest node that can be included in a split
         let mut min = 0;
@@ -70,28 +182,14 @@ impl HSoln {
                 if i - min > hint {
                     // If the partition is all unsolved and large enough we store it
                     if ranges.is_empty() {
-                        splits.push(HSoln {
-                            offset: self.offset + min,
-                            length: i - min,
-                        });
+                        splits.push(HSoln::new(offset + min, i - min));
                     } else {
-                        // Clean queue
-                        let (captures, new_min) = ranges.map_and_clean(hint, min, i + 1, true);
-                        min = new_min;
-                        // Handle splits
-                        captures.iter().for_each(|&(j, length)| {
-                            splits.push(HSoln {
-                                offset: self.offset + j,
-                                length,
-                            })
-                        });
+                        // Clean queue, bumper is moved past the empty node below
+                        ranges.map_and_clean(hint, min, i + 1, true, offset, splits);
                     }
                 } else if i - min == hint {
                     // Exact size, can ignore filled nodes
-                    splits.push(HSoln {
-                        offset: self.offset + min,
-                        length: hint,
-                    });
+                    splits.push(HSoln::new(offset + min, hint));
                 }
                 min = i + 1;
             } else if node.solution_is_filled() {
@@ -99,7 +197,7 @@ impl HSoln {
                 if i - min == hint {
                     // Move bumper further if a filled node is at the bumper
                     match ranges.front() {
-                        Some(&(j, k)) if j == min => {
+                        Some((j, k)) if j == min => {
                             ranges.pop();
                             min = k + 1;
                         }
@@ -108,21 +206,10 @@ impl HSoln {
                 } else if i - min > hint {
                     // Check if we need to clean the queue or not
                     if ranges.is_empty() {
-                        splits.push(HSoln {
-                            offset: self.offset + min,
-                            length: min - i - 1,
-                        })
+                        splits.push(HSoln::new(offset + min, min - i - 1))
                     } else {
                         // Clean queue
-                        let (captures, new_min) = ranges.map_and_clean(hint, min, i, false);
-                        min = new_min;
-                        // Handle splits
-                        captures.iter().for_each(|&(j, length)| {
-                            splits.push(HSoln {
-                                offset: self.offset + j,
-                                length,
-                            })
-                        });
+                        min = ranges.map_and_clean(hint, min, i, false, offset, splits);
                     }
                 }
 
@@ -131,54 +218,118 @@ impl HSoln {
         }
 
         // Last queue cleanup
-        let (captures, min) = ranges.map_and_clean(hint, min, nodes.len() + 1, true);
-        captures.iter().for_each(|&(j, length)| {
-            splits.push(HSoln {
-                offset: self.offset + j,
-                length,
-            })
-        });
+        let min = ranges.map_and_clean(hint, min, nodes.len() + 1, true, offset, splits);
 
         if nodes.len() - min >= hint {
-            splits.push(HSoln {
-                offset: min + self.offset,
-                length: nodes.len() - min,
-            });
+            splits.push(HSoln::new(min + offset, nodes.len() - min));
         }
+    }
+}
 
-        splits
+impl Clone for HSoln {
+    fn clone(&self) -> HSoln {
+        HSoln {
+            offset: self.offset,
+            length: self.length,
+            valid: AtomicU64::new(self.valid.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl PartialEq for HSoln {
+    fn eq(&self, other: &HSoln) -> bool {
+        self.offset == other.offset && self.length == other.length
     }
 }
 
 impl Hint {
     pub fn gen(hints: &[usize], nodes: usize) -> Vec<Hint> {
-        let mut offset = 0;
-        let mut result = Vec::with_capacity(hints.len());
-        let length = nodes - (hints.iter().map(|item| item + 1).sum::<usize>() - 1);
-
-        for &hint in hints {
-            result.push(Hint {
-                hint,
-                solutions: vec![HSoln {
-                    offset,
-                    length: length + hint,
-                }],
-            });
-            offset += hint + 1;
+        // A 0 hint places no block, so `[0]` is an empty line like `[]`
+        if hints.contains(&0) {
+            let hints: Vec<usize> = hints.iter().copied().filter(|&hint| hint > 0).collect();
+            return Hint::gen(&hints, nodes);
+        }
+        if hints.is_empty() {
+            return Vec::new();
         }
 
-        result
+        // Clues too long for the line get no windows, and the line solver
+        // reports the line as a contradiction
+        let length = nodes.checked_sub(slack::min_length(hints));
+        hints
+            .iter()
+            .zip(slack::earliest_starts(hints))
+            .map(|(&hint, offset)| Hint {
+                hint: to_index(hint),
+                solutions: length
+                    .map(|length| HSoln::new(offset, length + hint))
+                    .into_iter()
+                    .collect(),
+            })
+            .collect()
     }
-}
 
-impl RangeQueue {
-    fn new() -> RangeQueue {
-        RangeQueue {
-            queue: VecDeque::new(),
+    pub fn hint(&self) -> usize {
+        from_index(self.hint)
+    }
+
+    pub fn solutions(&self) -> &[HSoln] {
+        &self.solutions
+    }
+
+    pub fn set_solutions(&mut self, solutions: Vec<HSoln>) {
+        self.solutions = solutions;
+    }
+
+    /// Narrow every window around cells that have since become empty
+    pub fn refine<C: LineCells + ?Sized>(&mut self, cells: &C) {
+        let hint = self.hint();
+        let solutions = self
+            .solutions
+            .iter()
+            .flat_map(|soln| soln.refine(cells, hint));
+        self.solutions = solutions.collect();
+    }
+
+    /// Start of the block if its only window is exactly its length
+    pub fn exact(&self) -> Option<usize> {
+        match &self.solutions[..] {
+            [soln] if soln.length == self.hint => Some(soln.offset()),
+            _ => None,
         }
     }
 
+    /// Trim every window's empty ends, dropping windows too short for the block
+    pub fn tighten<C: LineCells + ?Sized>(&mut self, cells: &C) {
+        let hint = self.hint();
+        self.solutions.retain_mut(|soln| soln.tighten(cells, hint));
+    }
+
+    /// Keep the block within `start..end`, dropping windows outside it
+    pub fn clamp(&mut self, start: usize, end: usize) {
+        let hint = self.hint();
+        self.solutions
+            .retain_mut(|soln| soln.clamp(start, end, hint));
+    }
+
+    /// Group sorted feasible start positions into contiguous solution windows
+    pub fn set_starts(&mut self, starts: &[usize]) {
+        let hint = self.hint();
+        self.solutions.clear();
+        for &start in starts {
+            match self.solutions.last_mut() {
+                Some(soln) if soln.offset() + soln.length() - hint + 1 == start => {
+                    soln.length = to_index(soln.length() + 1)
+                }
+                _ => self.solutions.push(HSoln::new(start, hint)),
+            }
+        }
+    }
+}
+
+impl RangeQueue {
     fn push(&mut self, value: usize) {
+        let value = to_index(value);
         match self.queue.back_mut() {
             Some(i) if value == i.1 + 1 => {
                 i.1 = value;
@@ -191,25 +342,27 @@ impl RangeQueue {
         };
     }
 
+    // Push the captured splits, offset into the line, and return the new bumper
     fn map_and_clean(
         &mut self,
         range: usize,
         min: usize,
         max: usize,
         clean_all: bool,
-    ) -> (Vec<(usize, usize)>, usize) {
+        offset: usize,
+        solutions: &mut Vec<HSoln>,
+    ) -> usize {
         let mut min = min;
-        let mut solutions = Vec::new();
+        let mut capture = |j: usize, length: usize| solutions.push(HSoln::new(offset + j, length));
         if max - min > range {
-            while let Some(&(i, j)) = self.queue.front() {
-                println!("Values are: {}, {}, {}, {}", min, max, i, j);
+            while let Some((i, j)) = self.front() {
                 // Check if we have enough space to capture a range
                 if range < max - min {
                     // Check if that range is constricted or not
                     if max - i > range {
-                        solutions.push((min, range + i - min))
+                        capture(min, range + i - min)
                     } else {
-                        solutions.push((min, max - 1 - min))
+                        capture(min, max - 1 - min)
                     }
                 }
                 // Pop any values that fall outside of the new range
@@ -225,23 +378,27 @@ impl RangeQueue {
                 }
             }
         }
-        (solutions, min)
+        min
     }
 
-    fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+    fn clear(&mut self) {
+        self.queue.clear();
     }
 
-    fn front(&self) -> Option<&(usize, usize)> {
-        self.queue.front()
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
     }
 
-    fn back(&self) -> Option<&(usize, usize)> {
-        self.queue.back()
+    fn front(&self) -> Option<(usize, usize)> {
+        self.queue
+            .front()
+            .map(|&(i, j)| (from_index(i), from_index(j)))
     }
 
     fn pop(&mut self) -> Option<(usize, usize)> {
-        self.queue.pop_front()
+        self.queue
+            .pop_front()
+            .map(|(i, j)| (from_index(i), from_index(j)))
     }
 }
 
@@ -252,36 +409,58 @@ mod tests {
 
     fn check_hints(hints: &[Hint], offsets: &[usize], length: usize) {
         hints.iter().enumerate().for_each(|(i, hint)| {
-            let soln = hint.solutions.get(0).unwrap();
+            let soln = hint.solutions.first().unwrap();
             assert_eq!(
-                soln.length,
-                hint.hint + length,
+                soln.length(),
+                hint.hint() + length,
                 "Hint {} has incorrect length",
                 i
             );
-            assert_eq!(soln.offset, offsets[i], "Hint {} has incorrect offset", i);
+            assert_eq!(soln.offset(), offsets[i], "Hint {} has incorrect offset", i);
         });
     }
 
     #[test]
     fn gen_two_hints() {
-        check_hints(&Hint::gen(vec![2, 4], 10), &[0, 3], 3);
+        check_hints(&Hint::gen(&[2, 4], 10), &[0, 3], 3);
     }
 
     #[test]
     fn gen_full_hints() {
-        check_hints(&Hint::gen(vec![3, 3, 2], 10), &[0, 4, 8], 0);
+        check_hints(&Hint::gen(&[3, 3, 2], 10), &[0, 4, 8], 0);
+    }
+
+    #[test]
+    fn gen_zero_hints() {
+        assert!(Hint::gen(&[0], 5).is_empty());
+        check_hints(&Hint::gen(&[0, 3, 0], 10), &[0], 7);
     }
 
     #[test]
     fn gen_one_hint() {
-        check_hints(&Hint::gen(vec![3], 10), &[0], 7);
+        check_hints(&Hint::gen(&[3], 10), &[0], 7);
     }
 
     #[test]
-    #[should_panic(expected = "attempt to subtract with overflow")]
     fn gen_overflow_hint() {
-        check_hints(&Hint::gen(vec![3, 7], 10), &[0, 4], 0);
+        let hints = Hint::gen(&[3, 7], 10);
+        assert_eq!(hints.len(), 2);
+        assert!(hints.iter().all(|hint| hint.solutions().is_empty()));
+    }
+
+    #[test]
+    fn gen_no_hints() {
+        assert!(Hint::gen(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn starts_group_into_windows() {
+        let mut hint = Hint::gen(&[3], 10).remove(0);
+        hint.set_starts(&[0, 1, 2, 6, 7]);
+
+        assert_eq!(hint.solutions().len(), 2);
+        assert_soln(&hint.solutions()[0], 0, 5);
+        assert_soln(&hint.solutions()[1], 6, 4);
     }
 
     fn setup_hsoln_test(size: usize, filled: &[usize], empty: &[usize]) -> (HSoln, Vec<Node>) {
@@ -298,18 +477,12 @@ mod tests {
             nodes.get_mut(*i).unwrap().solve_empty();
         }
 
-        return (
-            HSoln {
-                offset: 0,
-                length: size,
-            },
-            nodes,
-        );
+        (HSoln::new(0, size), nodes)
     }
 
     fn assert_soln(soln: &HSoln, offset: usize, length: usize) {
-        assert_eq!(soln.offset, offset);
-        assert_eq!(soln.length, length);
+        assert_eq!(soln.offset(), offset);
+        assert_eq!(soln.length(), length);
     }
 
     #[test]
@@ -331,6 +504,17 @@ mod tests {
         assert!(!soln.is_valid(&nodes, 3));
     }
 
+    #[test]
+    fn validity_cached_by_version() {
+        let (soln, mut nodes) = setup_hsoln_test(5, &[0], &[]);
+        assert!(soln.is_valid_at(&nodes, 3, 1));
+
+        nodes[3].solve_filled();
+        assert!(soln.is_valid_at(&nodes, 3, 1));
+        assert!(!soln.is_valid_at(&nodes, 3, 2));
+        assert!(!soln.is_valid(&nodes, 3));
+    }
+
     #[test]
     fn split_empty_nodes() {
         let (soln, nodes) = setup_hsoln_test(10, &[], &[1, 6]);
@@ -338,10 +522,44 @@ mod tests {
         let splits = soln.split(&nodes, 2);
 
         assert_eq!(splits.len(), 2);
-        assert_soln(splits.get(0).unwrap(), 2, 4);
+        assert_soln(splits.first().unwrap(), 2, 4);
         assert_soln(splits.get(1).unwrap(), 7, 3);
     }
 
+    #[test]
+    fn refine_around_new_empties() {
+        let (soln, mut nodes) = setup_hsoln_test(10, &[], &[]);
+        let mut hint = Hint {
+            hint: 2,
+            solutions: vec![HSoln::new(1, 8)],
+        };
+        assert_eq!(soln.refine(&nodes[..], 2), vec![soln.clone()]);
+
+        nodes[2].solve_empty();
+        nodes[5].solve_empty();
+        hint.refine(&nodes[..]);
+        assert_eq!(hint.solutions(), &[HSoln::new(3, 2), HSoln::new(6, 3)]);
+
+        nodes[7].solve_empty();
+        hint.refine(&nodes[..]);
+        assert_eq!(hint.solutions(), &[HSoln::new(3, 2)]);
+    }
+
+    #[test]
+    fn split_into_reuses_scratch() {
+        let mut scratch = SplitScratch::default();
+        let mut splits = Vec::new();
+
+        let (soln, nodes) = setup_hsoln_test(12, &[2, 3, 5, 6, 8], &[]);
+        soln.split_into(&nodes, 4, &mut scratch, &mut splits);
+        assert_eq!(splits, soln.split(&nodes, 4));
+
+        splits.clear();
+        let (soln, nodes) = setup_hsoln_test(10, &[], &[1, 6]);
+        soln.split_into(&nodes, 2, &mut scratch, &mut splits);
+        assert_eq!(splits, soln.split(&nodes, 2));
+    }
+
     #[test]
     fn split_test_a() {
         // 0FF00, h = 3
@@ -350,7 +568,7 @@ mod tests {
         let splits = soln.split(&nodes, 3);
 
         assert_eq!(splits.len(), 1);
-        assert_soln(splits.get(0).unwrap(), 0, 4);
+        assert_soln(splits.first().unwrap(), 0, 4);
     }
 
     #[test]
@@ -363,7 +581,7 @@ mod tests {
         println!("{:?}", splits);
 
         assert_eq!(splits.len(), 3);
-        assert_soln(splits.get(0).unwrap(), 0, 4);
+        assert_soln(splits.first().unwrap(), 0, 4);
         assert_soln(splits.get(1).unwrap(), 5, 4);
         assert_soln(splits.get(2).unwrap(), 8, 4);
     }
@@ -378,7 +596,7 @@ mod tests {
         println!("{:?}", splits);
 
         assert_eq!(splits.len(), 4);
-        assert_soln(splits.get(0).unwrap(), 0, 5);
+        assert_soln(splits.first().unwrap(), 0, 5);
         assert_soln(splits.get(1).unwrap(), 2, 5);
         assert_soln(splits.get(2).unwrap(), 4, 5);
         assert_soln(splits.get(3).unwrap(), 6, 5);
@@ -394,7 +612,7 @@ mod tests {
         println!("{:?}", splits);
 
         assert_eq!(splits.len(), 3);
-        assert_soln(splits.get(0).unwrap(), 0, 5);
+        assert_soln(splits.first().unwrap(), 0, 5);
         assert_soln(splits.get(1).unwrap(), 4, 5);
         assert_soln(splits.get(2).unwrap(), 6, 5);
     }
@@ -409,8 +627,58 @@ mod tests {
         println!("{:?}", splits);
 
         assert_eq!(splits.len(), 3);
-        assert_soln(splits.get(0).unwrap(), 0, 5);
+        assert_soln(splits.first().unwrap(), 0, 5);
         assert_soln(splits.get(1).unwrap(), 2, 5);
         assert_soln(splits.get(2).unwrap(), 4, 5);
     }
+
+    #[test]
+    fn index_width() {
+        let soln = HSoln::new(200, 40);
+        assert_eq!((soln.offset(), soln.length()), (200, 40));
+    }
+
+    #[cfg(any(feature = "index-u16", feature = "index-u32"))]
+    #[test]
+    fn narrow_index_shrinks_windows() {
+        use std::mem::size_of;
+        // Both halves of the window share the word beside the cache
+        let wide = 2 * size_of::<usize>() + size_of::<AtomicU64>();
+        assert_eq!(size_of::<HSoln>(), size_of::<AtomicU64>() * 2);
+        assert!(size_of::<HSoln>() < wide);
+    }
+
+    #[cfg(feature = "index-u16")]
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn index_overflow() {
+        HSoln::new(70_000, 1);
+    }
+
+    #[test]
+    fn tighten_trims_empty_ends() {
+        use CellState::*;
+        let cells = vec![Empty, Empty, Unknown, Filled, Empty, Unknown, Empty, Empty];
+        let mut soln = HSoln::new(0, 8);
+        assert!(soln.tighten(&cells, 2));
+        assert_soln(&soln, 2, 4);
+        assert!(!HSoln::new(5, 3).tighten(&cells, 2));
+
+        let mut hint = Hint {
+            hint: to_index(2),
+            solutions: vec![HSoln::new(0, 4), HSoln::new(5, 3)],
+        };
+        hint.tighten(&cells);
+        assert_eq!(hint.solutions(), &[HSoln::new(2, 2)][..]);
+    }
+
+    #[test]
+    fn exact_window() {
+        let mut hint = Hint::gen(&[3], 5).remove(0);
+        assert_eq!(hint.exact(), None);
+        hint.set_solutions(vec![HSoln::new(1, 3)]);
+        assert_eq!(hint.exact(), Some(1));
+        hint.set_solutions(vec![HSoln::new(0, 3), HSoln::new(4, 3)]);
+        assert_eq!(hint.exact(), None);
+    }
 }
@@ -0,0 +1,131 @@
+//! Combining two partly solved copies of the same board, as left by
+//! collaborating players or by an autosave and the live game
+
+use super::grid::{CellPos, Grid};
+use super::node::CellState;
+use std::fmt;
+
+/// What to do with a cell the two grids solved differently
+///
+/// A cell solved in only one grid always takes that value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Policy {
+    /// Keep every solved cell, leaving disagreements unknown
+    PreferSolved,
+    /// Keep this grid's value
+    PreferSelf,
+    /// Refuse to merge
+    Error,
+}
+
+/// A cell the two grids solved differently
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MergeConflict {
+    pub pos: CellPos,
+    pub ours: CellState,
+    pub theirs: CellState,
+}
+
+/// The combined grid and the disagreements resolved to make it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Merged {
+    pub grid: Grid,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Every disagreement between grids merged with `Policy::Error`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MergeError {
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "grids disagree on {} cells", self.conflicts.len())
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl Grid {
+    /// Combine with another grid of the same size, resolving conflicts by `policy`
+    pub fn merge(&self, other: &Grid, policy: Policy) -> Result<Merged, MergeError> {
+        assert_eq!(self.width(), other.width(), "Grids have different widths");
+        assert_eq!(
+            self.height(),
+            other.height(),
+            "Grids have different heights"
+        );
+
+        let mut grid = self.clone();
+        let mut conflicts = Vec::new();
+        for pos in self.positions() {
+            let (ours, theirs) = (self.get(pos), other.get(pos));
+            if ours == theirs || theirs == CellState::Unknown {
+                continue;
+            }
+            if ours == CellState::Unknown {
+                grid.set_state(pos, theirs);
+                continue;
+            }
+            conflicts.push(MergeConflict { pos, ours, theirs });
+            if policy == Policy::PreferSolved {
+                grid.set_state(pos, CellState::Unknown);
+            }
+        }
+
+        if policy == Policy::Error && !conflicts.is_empty() {
+            return Err(MergeError { conflicts });
+        }
+        Ok(Merged { grid, conflicts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(art: &[&str]) -> Grid {
+        let mut grid = Grid::new(art[0].len(), art.len());
+        for (row, line) in art.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c != '?' {
+                    grid.solve(CellPos::new(row, col), c == '#');
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn policies() {
+        let ours = board(&["#?.", "??#"]);
+        let theirs = board(&["#.#", "?#?"]);
+        let conflict = MergeConflict {
+            pos: CellPos::new(0, 2),
+            ours: CellState::Empty,
+            theirs: CellState::Filled,
+        };
+
+        let merged = ours.merge(&theirs, Policy::PreferSolved).unwrap();
+        assert_eq!(merged.grid, board(&["#.?", "?##"]));
+        assert_eq!(merged.conflicts, vec![conflict]);
+
+        let merged = ours.merge(&theirs, Policy::PreferSelf).unwrap();
+        assert_eq!(merged.grid, board(&["#..", "?##"]));
+
+        let err = ours.merge(&theirs, Policy::Error).unwrap_err();
+        assert_eq!(err.conflicts, vec![conflict]);
+        assert_eq!(err.to_string(), "grids disagree on 1 cells");
+    }
+
+    #[test]
+    fn agreeing_grids_merge_under_any_policy() {
+        let ours = board(&["#?", "?."]);
+        let theirs = board(&["??", "#."]);
+
+        let merged = ours.merge(&theirs, Policy::Error).unwrap();
+        assert_eq!(merged.grid, board(&["#?", "#."]));
+        assert!(merged.conflicts.is_empty());
+    }
+}
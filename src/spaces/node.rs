@@ -1,22 +1,93 @@
-#[derive(PartialEq, Debug)]
-enum NodeSoln {
-    UNKNOWN,
-    EMPTY,
-    FILLED,
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CellState {
+    Unknown,
+    Empty,
+    Filled,
 }
 
-#[derive(Debug)]
-pub struct Node {
-    solution: NodeSoln,
+/// What a single cell can hold, `UNKNOWN` until it is solved
+///
+/// Black and white puzzles use `CellState`; colored ones use `ColorCell`.
+pub trait CellValue: Copy + Eq + Hash + fmt::Debug {
+    const UNKNOWN: Self;
+
+    fn is_known(self) -> bool {
+        self != Self::UNKNOWN
+    }
 }
 
-impl Node {
-    pub fn new() -> Node {
+impl CellValue for CellState {
+    const UNKNOWN: CellState = CellState::Unknown;
+}
+
+/// A cell of a colored puzzle, `Color(0)` being the background
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ColorCell {
+    Unknown,
+    Color(u8),
+}
+
+impl CellValue for ColorCell {
+    const UNKNOWN: ColorCell = ColorCell::Unknown;
+}
+
+impl From<CellState> for ColorCell {
+    /// Empty cells are the background and filled ones color 1
+    fn from(state: CellState) -> ColorCell {
+        match state {
+            CellState::Unknown => ColorCell::Unknown,
+            CellState::Empty => ColorCell::Color(0),
+            CellState::Filled => ColorCell::Color(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node<V = CellState> {
+    solution: V,
+}
+
+impl<V: CellValue> Default for Node<V> {
+    fn default() -> Node<V> {
+        Node::unknown()
+    }
+}
+
+impl<V: CellValue> Node<V> {
+    pub fn unknown() -> Node<V> {
         Node {
-            solution: NodeSoln::UNKNOWN,
+            solution: V::UNKNOWN,
         }
     }
 
+    // Overwrite the state regardless of what was solved before
+    pub(crate) fn set_state(&mut self, state: V) {
+        self.solution = state;
+    }
+
+    // Return a solved cell to unknown, for undoing a search
+    pub(crate) fn unsolve(&mut self) {
+        assert!(self.is_solved()); // Cannot unsolve an unknown cell
+        self.solution = V::UNKNOWN;
+    }
+
+    pub fn state(&self) -> V {
+        self.solution
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.solution.is_known()
+    }
+}
+
+impl Node {
+    pub fn new() -> Node {
+        Node::unknown()
+    }
+
     pub fn solve_filled(&mut self) {
         self.solve(true);
     }
@@ -29,22 +100,18 @@ impl Node {
         assert!(!self.is_solved()); // Cannot solve twice
 
         self.solution = match filled {
-            true => NodeSoln::FILLED,
-            false => NodeSoln::EMPTY,
+            true => CellState::Filled,
+            false => CellState::Empty,
         };
     }
 
-    pub fn is_solved(&self) -> bool {
-        self.solution != NodeSoln::UNKNOWN
-    }
-
     pub fn solution_is_filled(&self) -> bool {
         assert!(self.is_solved());
-        self.solution == NodeSoln::FILLED
+        self.solution == CellState::Filled
     }
 
     pub fn solution_is_empty(&self) -> bool {
         assert!(self.is_solved());
-        self.solution == NodeSoln::EMPTY
+        self.solution == CellState::Empty
     }
 }
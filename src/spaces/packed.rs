@@ -0,0 +1,177 @@
+use super::grid::{CellPos, Grid};
+use super::node::CellState;
+use super::store::{NodeStore, StoreLine};
+use super::LineId;
+
+const CELLS_PER_WORD: usize = 32;
+
+/// Board stored at two bits per cell in one contiguous row-major buffer
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PackedGrid {
+    width: usize,
+    height: usize,
+    words: Vec<u64>,
+}
+
+fn encode(state: CellState) -> u64 {
+    match state {
+        CellState::Unknown => 0,
+        CellState::Empty => 1,
+        CellState::Filled => 2,
+    }
+}
+
+fn decode(bits: u64) -> CellState {
+    match bits {
+        1 => CellState::Empty,
+        2 => CellState::Filled,
+        _ => CellState::Unknown,
+    }
+}
+
+impl PackedGrid {
+    pub fn new(width: usize, height: usize) -> PackedGrid {
+        PackedGrid {
+            width,
+            height,
+            words: vec![0; (width * height).div_ceil(CELLS_PER_WORD)],
+        }
+    }
+
+    pub fn from_grid(grid: &Grid) -> PackedGrid {
+        let mut packed = PackedGrid::new(grid.width(), grid.height());
+        for pos in grid.positions() {
+            packed.set(pos, grid.get(pos));
+        }
+        packed
+    }
+
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        let positions: Vec<CellPos> = grid.positions().collect();
+        for pos in positions {
+            match self.get(pos) {
+                CellState::Unknown => {}
+                state => grid.solve(pos, state == CellState::Filled),
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, pos: CellPos) -> usize {
+        assert!(pos.row < self.height && pos.col < self.width);
+        pos.row * self.width + pos.col
+    }
+
+    fn get_index(&self, i: usize) -> CellState {
+        decode(self.words[i / CELLS_PER_WORD] >> (i % CELLS_PER_WORD * 2) & 0b11)
+    }
+
+    pub fn get(&self, pos: CellPos) -> CellState {
+        self.get_index(self.index(pos))
+    }
+
+    pub fn set(&mut self, pos: CellPos, state: CellState) {
+        self.set_index(self.index(pos), state);
+    }
+
+    fn set_index(&mut self, i: usize, state: CellState) {
+        let shift = i % CELLS_PER_WORD * 2;
+        let word = &mut self.words[i / CELLS_PER_WORD];
+        *word = *word & !(0b11 << shift) | encode(state) << shift;
+    }
+
+    /// Bytes used by the cell buffer
+    pub fn storage_bytes(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn row(&self, row: usize) -> StoreLine<'_, PackedGrid> {
+        self.line(LineId::Row(row))
+    }
+
+    pub fn col(&self, col: usize) -> StoreLine<'_, PackedGrid> {
+        self.line(LineId::Column(col))
+    }
+
+    pub fn line(&self, line: LineId) -> StoreLine<'_, PackedGrid> {
+        NodeStore::line(self, self.width, line)
+    }
+}
+
+impl NodeStore for PackedGrid {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn get(&self, i: usize) -> CellState {
+        assert!(i < self.width * self.height);
+        self.get_index(i)
+    }
+
+    fn set(&mut self, i: usize, state: CellState) {
+        assert!(i < self.width * self.height);
+        self.set_index(i, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::line;
+    use crate::spaces::grid::from_art;
+
+    #[test]
+    fn round_trip() {
+        let mut grid = from_art(&["#.#..", ".####", "....#"]);
+        grid.set_state(CellPos::new(1, 2), CellState::Unknown);
+        let packed = PackedGrid::from_grid(&grid);
+
+        assert_eq!(packed.to_grid(), grid);
+        assert_eq!(packed.get(CellPos::new(1, 2)), CellState::Unknown);
+        assert_eq!(packed.storage_bytes(), 8);
+    }
+
+    #[test]
+    fn strided_views() {
+        let packed = PackedGrid::from_grid(&from_art(&["#.#", ".##", "..#"]));
+
+        assert_eq!(
+            packed.row(1).to_states(),
+            vec![CellState::Empty, CellState::Filled, CellState::Filled]
+        );
+        assert_eq!(
+            packed
+                .col(2)
+                .iter()
+                .filter(|&s| s == CellState::Filled)
+                .count(),
+            3
+        );
+        assert_eq!(packed.line(LineId::Column(0)).get(0), CellState::Filled);
+
+        let solved = line::solve(&[2], &packed.row(1)).unwrap();
+        assert_eq!(solved.cells, packed.row(1).to_states());
+    }
+
+    #[test]
+    fn overwrite_across_word_boundary() {
+        let mut packed = PackedGrid::new(40, 2);
+        let pos = CellPos::new(0, 31);
+        packed.set(pos, CellState::Filled);
+        packed.set(pos, CellState::Empty);
+        packed.set(CellPos::new(0, 32), CellState::Filled);
+
+        assert_eq!(packed.get(pos), CellState::Empty);
+        assert_eq!(packed.get(CellPos::new(0, 32)), CellState::Filled);
+        assert_eq!(packed.get(CellPos::new(0, 30)), CellState::Unknown);
+    }
+}
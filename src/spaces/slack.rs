@@ -0,0 +1,53 @@
+//! Arithmetic on how clues fit into a line
+
+/// Fewest cells that hold every block with a gap between each pair
+pub fn min_length(clues: &[usize]) -> usize {
+    clues.iter().sum::<usize>() + clues.len().saturating_sub(1)
+}
+
+/// Cells left over once the blocks are packed to one end, `None` if they
+/// do not fit at all
+pub fn slack(clues: &[usize], len: usize) -> Option<usize> {
+    len.checked_sub(min_length(clues))
+}
+
+/// Earliest start of each block on an empty line, with every block packed left
+pub fn earliest_starts(clues: &[usize]) -> Vec<usize> {
+    clues
+        .iter()
+        .scan(0, |start, &clue| {
+            let this = *start;
+            *start += clue + 1;
+            Some(this)
+        })
+        .collect()
+}
+
+/// Latest start of each block on an empty line of `len` cells, `None` if the
+/// clues do not fit
+pub fn latest_starts(clues: &[usize], len: usize) -> Option<Vec<usize>> {
+    let slack = slack(clues, len)?;
+    Some(
+        earliest_starts(clues)
+            .into_iter()
+            .map(|start| start + slack)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing() {
+        assert_eq!(min_length(&[3, 1, 2]), 8);
+        assert_eq!(min_length(&[]), 0);
+        assert_eq!(slack(&[3, 1, 2], 10), Some(2));
+        assert_eq!(slack(&[3, 1, 2], 7), None);
+        assert_eq!(slack(&[], 4), Some(4));
+        assert_eq!(earliest_starts(&[3, 1, 2]), vec![0, 4, 6]);
+        assert_eq!(latest_starts(&[3, 1, 2], 10), Some(vec![2, 6, 8]));
+        assert_eq!(latest_starts(&[5], 4), None);
+    }
+}
@@ -0,0 +1,175 @@
+//! Backends for a board's cells, so callers can trade memory for speed
+//!
+//! A store is a flat row-major run of cells. `StoreLine` reads one row or
+//! column of any store in place and implements `LineCells`, so the line
+//! solver runs unchanged whichever backend holds the board.
+
+use super::bits::LineBits;
+use super::node::{CellState, Node};
+use super::view::LineCells;
+use super::LineId;
+
+/// Flat row-major cell storage
+pub trait NodeStore {
+    fn len(&self) -> usize;
+
+    fn get(&self, i: usize) -> CellState;
+
+    /// Overwrite the `i`th cell regardless of what it held
+    fn set(&mut self, i: usize, state: CellState);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// One row or column of the board, `width` cells to a row
+    fn line(&self, width: usize, line: LineId) -> StoreLine<'_, Self>
+    where
+        Self: Sized,
+    {
+        let height = match width {
+            0 => 0,
+            _ => self.len() / width,
+        };
+        let (start, stride, len) = match line {
+            LineId::Row(row) => {
+                assert!(row < height);
+                (row * width, 1, width)
+            }
+            LineId::Column(col) => {
+                assert!(col < width);
+                (col, width, height)
+            }
+        };
+        StoreLine {
+            store: self,
+            start,
+            stride,
+            len,
+        }
+    }
+}
+
+impl NodeStore for Vec<Node> {
+    fn len(&self) -> usize {
+        <[Node]>::len(self)
+    }
+
+    fn get(&self, i: usize) -> CellState {
+        self[i].state()
+    }
+
+    fn set(&mut self, i: usize, state: CellState) {
+        self[i].set_state(state);
+    }
+}
+
+impl NodeStore for Vec<CellState> {
+    fn len(&self) -> usize {
+        <[CellState]>::len(self)
+    }
+
+    fn get(&self, i: usize) -> CellState {
+        self[i]
+    }
+
+    fn set(&mut self, i: usize, state: CellState) {
+        self[i] = state;
+    }
+}
+
+/// A row or column of a store, strided over it for columns
+#[derive(Debug)]
+pub struct StoreLine<'a, S> {
+    store: &'a S,
+    start: usize,
+    stride: usize,
+    len: usize,
+}
+
+// Derives would needlessly require `S: Clone`
+impl<S> Clone for StoreLine<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for StoreLine<'_, S> {}
+
+impl<'a, S: NodeStore> StoreLine<'a, S> {
+    pub fn get(&self, i: usize) -> CellState {
+        assert!(i < self.len, "index {} out of a line of {}", i, self.len);
+        self.store.get(self.start + i * self.stride)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = CellState> + 'a {
+        let line = *self;
+        (0..line.len).map(move |i| line.get(i))
+    }
+
+    pub fn to_states(&self) -> Vec<CellState> {
+        self.iter().collect()
+    }
+
+    pub fn to_bits(&self) -> LineBits {
+        LineBits::from_states(&self.to_states())
+    }
+}
+
+impl<S: NodeStore> LineCells for StoreLine<'_, S> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self.get(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::line;
+    use crate::spaces::grid::from_art;
+    use crate::spaces::packed::PackedGrid;
+
+    // The same board in each backend, with a few cells forgotten
+    fn backends() -> (Vec<Node>, Vec<CellState>, PackedGrid) {
+        let grid = from_art(&["##.#.", ".###.", "#...#"]);
+        let mut nodes: Vec<Node> = grid.positions().map(|pos| grid.node(pos).clone()).collect();
+        for i in [1, 6, 7, 12] {
+            NodeStore::set(&mut nodes, i, CellState::Unknown);
+        }
+        let states: Vec<CellState> = nodes.iter().map(Node::state).collect();
+        let mut packed = PackedGrid::new(5, 3);
+        for (i, &state) in states.iter().enumerate() {
+            NodeStore::set(&mut packed, i, state);
+        }
+        (nodes, states, packed)
+    }
+
+    #[test]
+    fn backends_agree() {
+        let (nodes, states, packed) = backends();
+        let lines = [
+            LineId::Row(0),
+            LineId::Row(1),
+            LineId::Column(1),
+            LineId::Column(2),
+        ];
+
+        for &id in &lines {
+            let expected = line::solve(&[2, 1], &nodes.line(5, id)).map(|s| s.cells);
+            let from_states = line::solve(&[2, 1], &states.line(5, id)).map(|s| s.cells);
+            let packed_line = NodeStore::line(&packed, 5, id);
+            let from_packed = line::solve(&[2, 1], &packed_line).map(|s| s.cells);
+            assert_eq!(from_states, expected);
+            assert_eq!(from_packed, expected);
+        }
+        assert_eq!(
+            NodeStore::line(&packed, 5, LineId::Column(1)).get(0),
+            CellState::Unknown
+        );
+        assert_eq!(nodes.line(5, LineId::Column(4)).len(), 3);
+    }
+}
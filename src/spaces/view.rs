@@ -0,0 +1,165 @@
+//! Borrowed views over a grid's cells, read in place rather than copied
+
+use super::grid::{CellPos, Grid};
+use super::node::{CellState, Node};
+use super::LineId;
+use std::ops::Index;
+
+/// Cells of one line read by position, from a slice or a view into a grid
+pub trait LineCells {
+    fn len(&self) -> usize;
+
+    fn state(&self, i: usize) -> CellState;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl LineCells for [CellState] {
+    fn len(&self) -> usize {
+        <[CellState]>::len(self)
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self[i]
+    }
+}
+
+impl LineCells for Vec<CellState> {
+    fn len(&self) -> usize {
+        <[CellState]>::len(self)
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self[i]
+    }
+}
+
+impl LineCells for [Node] {
+    fn len(&self) -> usize {
+        <[Node]>::len(self)
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self[i].state()
+    }
+}
+
+impl LineCells for Vec<Node> {
+    fn len(&self) -> usize {
+        <[Node]>::len(self)
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self[i].state()
+    }
+}
+
+/// A row or column of a grid, strided over its storage for columns
+#[derive(Clone, Copy, Debug)]
+pub struct LineView<'a> {
+    nodes: &'a [Node],
+    start: usize,
+    stride: usize,
+    len: usize,
+}
+
+impl<'a> LineView<'a> {
+    pub(crate) fn new(nodes: &'a [Node], start: usize, stride: usize, len: usize) -> LineView<'a> {
+        LineView {
+            nodes,
+            start,
+            stride,
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> CellState {
+        self[i].state()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = CellState> + 'a {
+        let view = *self;
+        (0..view.len).map(move |i| view.nodes[view.start + i * view.stride].state())
+    }
+}
+
+impl Index<usize> for LineView<'_> {
+    type Output = Node;
+
+    fn index(&self, i: usize) -> &Node {
+        assert!(i < self.len, "index {} out of a line of {}", i, self.len);
+        &self.nodes[self.start + i * self.stride]
+    }
+}
+
+impl LineCells for LineView<'_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn state(&self, i: usize) -> CellState {
+        self.get(i)
+    }
+}
+
+/// A grid seen with rows and columns swapped, borrowing rather than copying
+///
+/// Row `i` of the view is column `i` of the grid, so code written for rows
+/// handles columns too.
+#[derive(Clone, Copy, Debug)]
+pub struct TransposedView<'a> {
+    grid: &'a Grid,
+}
+
+impl<'a> TransposedView<'a> {
+    pub(crate) fn new(grid: &'a Grid) -> TransposedView<'a> {
+        TransposedView { grid }
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn get(&self, pos: CellPos) -> CellState {
+        self.grid.get(CellPos::new(pos.col, pos.row))
+    }
+
+    pub fn row(&self, row: usize) -> LineView<'a> {
+        self.grid.col(row)
+    }
+
+    pub fn col(&self, col: usize) -> LineView<'a> {
+        self.grid.row(col)
+    }
+
+    pub fn line(&self, line: LineId) -> LineView<'a> {
+        match line {
+            LineId::Row(row) => self.row(row),
+            LineId::Column(col) => self.col(col),
+        }
+    }
+
+    /// The grid being viewed, the right way round
+    pub fn inner(&self) -> &'a Grid {
+        self.grid
+    }
+
+    /// Copy the view out into a grid of its own
+    pub fn to_grid(&self) -> Grid {
+        self.grid.transposed()
+    }
+}
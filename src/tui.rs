@@ -0,0 +1,216 @@
+//! A line-driven terminal player built on `Session`
+//!
+//! Each input line is a string of commands: `hjkl` or `wasd` move the cursor,
+//! `x` fills, `o` marks empty, `c` clears, `f` freezes or unfreezes a cell,
+//! `?` asks for a hint, `!` flags mistakes and `q` quits.
+
+use crate::session::Session;
+use crate::spaces::grid::CellPos;
+use crate::spaces::node::CellState;
+use std::io::{self, BufRead, Write};
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+/// Cursor and messages for one game
+pub struct Player {
+    session: Session,
+    cursor: CellPos,
+    flagged: Vec<CellPos>,
+    message: String,
+}
+
+impl Player {
+    pub fn new(session: Session) -> Player {
+        Player {
+            session,
+            cursor: CellPos::new(0, 0),
+            flagged: Vec::new(),
+            message: String::new(),
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn cursor(&self) -> CellPos {
+        self.cursor
+    }
+
+    /// Apply one command, false if it asks to quit
+    pub fn command(&mut self, key: char) -> bool {
+        let board = self.session.board();
+        let (rows, cols) = (board.height(), board.width());
+        let CellPos { row, col } = self.cursor;
+        match key {
+            'h' | 'a' => self.cursor.col = col.saturating_sub(1),
+            'l' | 'd' => self.cursor.col = (col + 1).min(cols - 1),
+            'k' | 'w' => self.cursor.row = row.saturating_sub(1),
+            'j' | 's' => self.cursor.row = (row + 1).min(rows - 1),
+            'x' => self.mark(CellState::Filled),
+            'o' => self.mark(CellState::Empty),
+            'c' => self.mark(CellState::Unknown),
+            'f' => {
+                let cursor = self.cursor;
+                if self.session.is_frozen(cursor) {
+                    self.session.unfreeze(vec![cursor]);
+                    self.message = "cell unfrozen".to_string();
+                } else {
+                    self.session.freeze(vec![cursor]);
+                    self.message = match self.session.verify_frozen() {
+                        Ok(()) => "cell frozen".to_string(),
+                        Err(err) => err.to_string(),
+                    };
+                }
+            }
+            '?' => self.hint(),
+            '!' => {
+                self.flagged = self.session.mistakes();
+                self.message = match self.flagged.len() {
+                    0 => "no mistakes so far".to_string(),
+                    n => format!("{} mistake(s) flagged", n),
+                };
+            }
+            'q' => return false,
+            _ => self.message = format!("unknown command `{}`", key),
+        }
+        true
+    }
+
+    fn mark(&mut self, state: CellState) {
+        if let Err(err) = self.session.mark(self.cursor, state) {
+            self.message = err.to_string();
+            return;
+        }
+        let cursor = self.cursor;
+        self.flagged.retain(|&pos| pos != cursor);
+        if self.session.is_solved() {
+            self.message = "solved!".to_string();
+        }
+    }
+
+    fn hint(&mut self) {
+        self.message = match self.session.hint() {
+            Some(hint) => {
+                self.cursor = hint.pos;
+                let state = match hint.state {
+                    CellState::Filled => "filled",
+                    _ => "empty",
+                };
+                format!("this cell is {} by {}", state, hint.technique)
+            }
+            None => "nothing left to hint".to_string(),
+        };
+    }
+
+    /// Draw the board, row clues to the right and column clues below
+    pub fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let board = self.session.board();
+        let puzzle = self.session.puzzle();
+        write!(out, "{}", CLEAR)?;
+        for row in 0..board.height() {
+            let mut line = String::new();
+            for col in 0..board.width() {
+                let pos = CellPos::new(row, col);
+                let symbol = match board.get(pos) {
+                    _ if self.flagged.contains(&pos) => '!',
+                    CellState::Filled => '#',
+                    CellState::Empty => '.',
+                    CellState::Unknown => '?',
+                };
+                let separator = if pos == self.cursor {
+                    '['
+                } else if col > 0 && CellPos::new(row, col - 1) == self.cursor {
+                    ']'
+                } else {
+                    ' '
+                };
+                line.push(separator);
+                line.push(symbol);
+            }
+            if self.cursor == CellPos::new(row, board.width() - 1) {
+                line.push(']');
+            }
+            writeln!(out, "{}  {}", line, clue_text(&puzzle.rows()[row]))?;
+        }
+        writeln!(out)?;
+        for (col, clues) in puzzle.cols().iter().enumerate() {
+            writeln!(out, "column {}: {}", col + 1, clue_text(clues))?;
+        }
+        writeln!(out, "{}", self.message)?;
+        write!(out, "> ")?;
+        out.flush()
+    }
+}
+
+fn clue_text(clues: &Option<Vec<usize>>) -> String {
+    match clues {
+        Some(clues) if clues.is_empty() => "0".to_string(),
+        Some(clues) => {
+            let clues: Vec<String> = clues.iter().map(|c| c.to_string()).collect();
+            clues.join(" ")
+        }
+        None => "-".to_string(),
+    }
+}
+
+/// Play until the puzzle is solved, the player quits or input runs out
+pub fn run(session: Session, input: impl BufRead, mut output: impl Write) -> io::Result<Session> {
+    let mut player = Player::new(session);
+    player.draw(&mut output)?;
+    for line in input.lines() {
+        for key in line?.chars().filter(|c| !c.is_whitespace()) {
+            if !player.command(key) {
+                return Ok(player.session);
+            }
+        }
+        player.draw(&mut output)?;
+        if player.session.is_solved() {
+            break;
+        }
+    }
+    Ok(player.session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Puzzle;
+    use crate::spaces::grid::from_art;
+
+    fn session() -> Session {
+        Session::new(Puzzle::from_goal(from_art(&["#.", "##"]))).unwrap()
+    }
+
+    #[test]
+    fn play_to_completion() {
+        let mut output = Vec::new();
+        let session = run(session(), &b"x d o\nj x a x\nq"[..], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(session.is_solved());
+        assert!(output.contains("[#]#  2"));
+        assert!(output.ends_with("solved!\n> "));
+    }
+
+    #[test]
+    fn flags_mistakes_and_hints() {
+        let mut player = Player::new(session());
+        player.command('d');
+        player.command('x');
+        player.command('!');
+        assert_eq!(player.flagged, vec![CellPos::new(0, 1)]);
+
+        let mut output = Vec::new();
+        player.draw(&mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains(" ?[!]  1"));
+
+        player.command('c');
+        assert!(player.flagged.is_empty());
+        player.command('?');
+        assert_eq!(
+            player.session().hint().map(|h| h.pos),
+            Some(player.cursor())
+        );
+    }
+}